@@ -73,6 +73,13 @@ export declare class Account {
   getMessage(id: string): Message | undefined
   generateAddress(): Address
   latestAddress(): Address | undefined
+  signData(address: string, data: string): DataSignature
+}
+
+export declare interface DataSignature {
+  address: string;
+  publicKey: string;
+  signature: string;
 }
 
 export declare class RemainderValueStrategy {
@@ -81,8 +88,30 @@ export declare class RemainderValueStrategy {
   static accountAddress(address: string): RemainderValueStrategy
 }
 
+export declare interface TransferOutput {
+  address: string;
+  amount: number;
+}
+
+export declare interface TransferPreviewInput {
+  outputId: { transactionId: string; index: number };
+  address: string;
+  amount: number;
+}
+
+export declare interface TransferPreview {
+  inputs: TransferPreviewInput[];
+  outputs: TransferOutput[];
+  remainder?: TransferOutput;
+  changeAddress?: Address;
+  essenceSize: number;
+}
+
 export declare class SyncedAccount {
   send(address: string, amount: number, remainderValueStrategy?: RemainderValueStrategy): Promise<Message>
+  send(outputs: TransferOutput[], remainderValueStrategy?: RemainderValueStrategy): Promise<Message>
+  preview(address: string, amount: number, remainderValueStrategy?: RemainderValueStrategy): Promise<TransferPreview>
+  preview(outputs: TransferOutput[], remainderValueStrategy?: RemainderValueStrategy): Promise<TransferPreview>
   retry(messageId: string): Promise<Message>
   reattach(messageId: string): Promise<Message>
   promote(messageId: string): Promise<Message>
@@ -108,12 +137,31 @@ export declare enum SignerType {
   EnvMnemonic = 2
 }
 
+export declare interface WatchOnlyKey {
+  keyIndex: number;
+  internal?: boolean;
+  key: { type: 'Address', data: string } | { type: 'PublicKey', data: string };
+}
+
+export declare interface WatchOnlyKeys {
+  accountIndex: number;
+  keys: WatchOnlyKey[];
+}
+
+export declare interface DerivationScheme {
+  coinType: number;
+  pathTemplate: string;
+}
+
 export declare interface AccountToCreate {
   clientOptions: ClientOptions;
   mnemonic?: string;
+  passphrase?: string;
   alias?: string;
   createdAt?: string;
   signerType?: SignerType;
+  watchOnly?: WatchOnlyKeys;
+  derivationScheme?: DerivationScheme;
 }
 
 export declare enum StorageType {
@@ -126,10 +174,32 @@ export declare interface ManagerOptions {
   storageType?: StorageType
 }
 
+export declare type DuplicateAccountStrategy = 'Abort' | 'Skip' | 'Overwrite' | 'Merge'
+
+export declare interface RestoreOptions {
+  accounts?: Array<string | number>
+  onDuplicate?: DuplicateAccountStrategy
+}
+
+export declare interface AccountRestore {
+  accountId: string
+  alias: string
+  status: 'Imported' | 'Skipped' | 'Overwritten' | 'Merged'
+}
+
+export declare interface RestoreReport {
+  accounts: AccountRestore[]
+}
+
 export declare class AccountManager {
   constructor(storagePath?: string)
   startBackgroundSync(): void
   setStrongholdPassword(password: string): void
+  changeStrongholdPassword(currentPassword: string, newPassword: string): void
+  lockStronghold(): void
+  unlockStronghold(password: string): void
+  isStrongholdLocked(): boolean
+  setStrongholdLockTimeout(timeout: number): void
   createAccount(account: AccountToCreate): Account
   getAccount(accountId: string | number): Account | undefined
   getAccountByAlias(alias: string): Account | undefined
@@ -137,8 +207,8 @@ export declare class AccountManager {
   removeAccount(accountId: string | number): void
   syncAccounts(): Promise<SyncedAccount[]>
   internalTransfer(fromAccount: Account, toAccount: Account, amount: number): Promise<Message>
-  backup(destination: string): string
-  importAccounts(source: string): void
+  backup(destination: string, password: string): string
+  importAccounts(source: string, password: string, options?: RestoreOptions): RestoreReport
 }
 
 export declare type Event = 'ErrorThrown' |
@@ -149,3 +219,4 @@ export declare type Event = 'ErrorThrown' |
   'Broadcast'
 
 export declare function addEventListener(event: Event, cb: (err?: any, data?: { [k: string]: any }) => void): void
+export declare function verifySignature(data: string, signature: DataSignature): boolean
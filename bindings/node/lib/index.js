@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 var addon = require('../native')
-const { AccountManager, Account, SyncedAccount, EventListener } = addon
+const { AccountManager, Account, SyncedAccount, EventListener, verifySignature } = addon
 
 function promisify (fn) {
   return function () {
@@ -55,6 +55,7 @@ class RemainderValueStrategy {
 
 Account.prototype.sync = promisify(Account.prototype.sync)
 SyncedAccount.prototype.send = promisify(SyncedAccount.prototype.send)
+SyncedAccount.prototype.preview = promisify(SyncedAccount.prototype.preview)
 SyncedAccount.prototype.retry = promisify(SyncedAccount.prototype.retry)
 SyncedAccount.prototype.reattach = promisify(SyncedAccount.prototype.reattach)
 SyncedAccount.prototype.promote = promisify(SyncedAccount.prototype.promote)
@@ -64,6 +65,7 @@ AccountManager.prototype.internalTransfer = promisify(AccountManager.prototype.i
 module.exports = {
   AccountManager,
   addEventListener,
+  verifySignature,
   RemainderValueStrategy,
   StorageType: {
     Stronghold: 1,
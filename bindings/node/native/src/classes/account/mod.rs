@@ -5,6 +5,7 @@ use std::str::FromStr;
 
 use iota_wallet::{
     account::{Account, AccountIdentifier},
+    address::parse as parse_address,
     message::MessageId,
 };
 use neon::prelude::*;
@@ -115,7 +116,10 @@ declare_types! {
             let id = cx.borrow(&this, |r| r.0.clone());
             let account = crate::get_account(&id);
             let account = account.read().unwrap();
-            let messages = account.list_messages(count, from, filter);
+            let messages = match filter {
+                Some(filter) => account.list_messages(count, from, Some(filter)).into_iter().cloned().collect(),
+                None => account.load_messages(count, from).expect("failed to load messages"),
+            };
 
             let js_array = JsArray::new(&mut cx, messages.len() as u32);
             for (index, message) in messages.iter().enumerate() {
@@ -182,7 +186,7 @@ declare_types! {
             let id = cx.borrow(&this, |r| r.0.clone());
             let account = crate::get_account(&id);
             let account = account.read().unwrap();
-            let message = account.get_message(&message_id);
+            let message = account.load_message(&message_id).expect("failed to load message");
             match message {
                 Some(m) => Ok(neon_serde::to_value(&mut cx, &m)?),
                 None => Ok(cx.undefined().upcast())
@@ -201,6 +205,20 @@ declare_types! {
             Ok(neon_serde::to_value(&mut cx, &address)?)
         }
 
+        method signData(mut cx) {
+            let address = parse_address(cx.argument::<JsString>(0)?.value()).expect("invalid address format");
+            let data = hex::decode(cx.argument::<JsString>(1)?.value()).expect("data must be a hex string");
+            let signature = {
+                let this = cx.this();
+                let guard = cx.lock();
+                let id = &this.borrow(&guard).0;
+                let account = crate::get_account(id);
+                let account = account.read().unwrap();
+                account.sign_data(&address, &data).expect("error signing data")
+            };
+            Ok(neon_serde::to_value(&mut cx, &signature)?)
+        }
+
         method latestAddress(mut cx) {
             let this = cx.this();
             let id = cx.borrow(&this, |r| r.0.clone());
@@ -5,13 +5,15 @@ use super::JsAccount;
 use std::{
     path::PathBuf,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use iota_wallet::{
-    account::AccountIdentifier,
+    account::{AccountIdentifier, DerivationScheme},
     account_manager::{AccountManager, DEFAULT_STORAGE_PATH},
+    backup::RestoreOptions,
     client::ClientOptions,
-    signing::SignerType,
+    signing::{SignerType, WatchOnlyKeys},
     storage::{sqlite::SqliteStorageAdapter, stronghold::StrongholdStorageAdapter},
     DateTime, Utc,
 };
@@ -40,11 +42,16 @@ pub struct AccountToCreate {
     #[serde(rename = "clientOptions")]
     pub client_options: ClientOptions,
     pub mnemonic: Option<String>,
+    pub passphrase: Option<String>,
     pub alias: Option<String>,
     #[serde(rename = "createdAt")]
     pub created_at: Option<String>,
     #[serde(rename = "signerType", default)]
     pub signer_type: AccountSignerType,
+    #[serde(rename = "watchOnly")]
+    pub watch_only: Option<WatchOnlyKeys>,
+    #[serde(rename = "derivationScheme")]
+    pub derivation_scheme: Option<DerivationScheme>,
 }
 
 fn js_value_to_account_id(
@@ -120,6 +127,65 @@ declare_types! {
             Ok(cx.undefined().upcast())
         }
 
+        method changeStrongholdPassword(mut cx) {
+            let current_password = cx.argument::<JsString>(0)?.value();
+            let new_password = cx.argument::<JsString>(1)?.value();
+            {
+                let this = cx.this();
+                let guard = cx.lock();
+                let ref_ = &this.borrow(&guard).0;
+                let manager = ref_.read().unwrap();
+                manager.change_stronghold_password(current_password, new_password).expect("error changing stronghold password");
+            }
+            Ok(cx.undefined().upcast())
+        }
+
+        method lockStronghold(mut cx) {
+            {
+                let this = cx.this();
+                let guard = cx.lock();
+                let ref_ = &this.borrow(&guard).0;
+                let manager = ref_.read().unwrap();
+                manager.lock_stronghold();
+            }
+            Ok(cx.undefined().upcast())
+        }
+
+        method unlockStronghold(mut cx) {
+            let password = cx.argument::<JsString>(0)?.value();
+            {
+                let this = cx.this();
+                let guard = cx.lock();
+                let ref_ = &this.borrow(&guard).0;
+                let manager = ref_.read().unwrap();
+                manager.unlock_stronghold(password).expect("error unlocking stronghold");
+            }
+            Ok(cx.undefined().upcast())
+        }
+
+        method isStrongholdLocked(mut cx) {
+            let is_locked = {
+                let this = cx.this();
+                let guard = cx.lock();
+                let ref_ = &this.borrow(&guard).0;
+                let manager = ref_.read().unwrap();
+                manager.is_stronghold_locked()
+            };
+            Ok(cx.boolean(is_locked).upcast())
+        }
+
+        method setStrongholdLockTimeout(mut cx) {
+            let timeout = cx.argument::<JsNumber>(0)?.value() as u64;
+            {
+                let this = cx.this();
+                let guard = cx.lock();
+                let ref_ = &this.borrow(&guard).0;
+                let manager = ref_.read().unwrap();
+                manager.set_stronghold_lock_timeout(Duration::from_secs(timeout));
+            }
+            Ok(cx.undefined().upcast())
+        }
+
         method startBackgroundSync(mut cx) {
             {
                 let this = cx.this();
@@ -149,6 +215,9 @@ declare_types! {
                 if let Some(mnemonic) = &account_to_create.mnemonic {
                     builder = builder.mnemonic(mnemonic);
                 }
+                if let Some(passphrase) = &account_to_create.passphrase {
+                    builder = builder.passphrase(passphrase);
+                }
                 if let Some(alias) = &account_to_create.alias {
                     builder = builder.alias(alias);
                 }
@@ -159,6 +228,12 @@ declare_types! {
                         .expect("invalid account created at format"),
                     );
                 }
+                if let Some(watch_only) = account_to_create.watch_only {
+                    builder = builder.watch_only(watch_only);
+                }
+                if let Some(derivation_scheme) = account_to_create.derivation_scheme {
+                    builder = builder.derivation_scheme(derivation_scheme);
+                }
                 builder.initialise().expect("error creating account")
             };
             let account = serde_json::to_string(&account).unwrap();
@@ -281,26 +356,35 @@ declare_types! {
 
         method backup(mut cx) {
             let backup_path = cx.argument::<JsString>(0)?.value();
+            let password = cx.argument::<JsString>(1)?.value();
             let destination = {
                 let this = cx.this();
                 let guard = cx.lock();
                 let ref_ = &this.borrow(&guard).0;
                 let manager = ref_.read().unwrap();
-                manager.backup(backup_path).expect("error performing backup").display().to_string()
+                manager.backup(backup_path, password).expect("error performing backup").display().to_string()
             };
             Ok(cx.string(destination).upcast())
         }
 
         method importAccounts(mut cx) {
             let source = cx.argument::<JsString>(0)?.value();
-            {
+            let password = cx.argument::<JsString>(1)?.value();
+            let options: RestoreOptions = match cx.argument_opt(2) {
+                Some(arg) => {
+                    let options = arg.downcast::<JsValue>().or_throw(&mut cx)?;
+                    neon_serde::from_value(&mut cx, options)?
+                }
+                None => Default::default(),
+            };
+            let report = {
                 let this = cx.this();
                 let guard = cx.lock();
                 let ref_ = &this.borrow(&guard).0;
                 let manager = ref_.read().unwrap();
-                manager.import_accounts(source).expect("error importing accounts");
+                manager.import_accounts(source, password, options).expect("error importing accounts")
             };
-            Ok(cx.undefined().upcast())
+            Ok(neon_serde::to_value(&mut cx, &report)?)
         }
     }
 }
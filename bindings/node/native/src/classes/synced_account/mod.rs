@@ -9,16 +9,58 @@ use std::{
 use iota_wallet::{
     account::SyncedAccount,
     address::parse as parse_address,
-    message::{MessageId, RemainderValueStrategy, Transfer},
+    message::{MessageId, RemainderValueStrategy, Transfer, TransferOutput},
 };
 use neon::prelude::*;
 
+mod preview;
 mod repost;
 mod send;
 
 #[derive(Clone)]
 pub struct SyncedAccountWrapper(Arc<RwLock<SyncedAccount>>, String);
 
+/// Reads the transfer arguments, either `address, amount` or `outputs`, followed by the optional remainder value
+/// strategy and the callback.
+fn transfer_and_callback<'a>(
+    cx: &mut CallContext<'a, JsSyncedAccount>,
+) -> NeonResult<(Transfer, Handle<'a, JsFunction>)> {
+    match cx.argument::<JsValue>(0)?.downcast::<JsArray>() {
+        Ok(outputs) => {
+            let outputs: Vec<TransferOutput> = neon_serde::from_value(cx, outputs.upcast::<JsValue>())?;
+            let (remainder_value_strategy, cb) = remainder_value_strategy_and_callback(cx, 1)?;
+            Ok((
+                Transfer::batch(outputs).remainder_value_strategy(remainder_value_strategy),
+                cb,
+            ))
+        }
+        Err(_) => {
+            let address = cx.argument::<JsString>(0)?.value();
+            let amount = cx.argument::<JsNumber>(1)?.value() as u64;
+            let (remainder_value_strategy, cb) = remainder_value_strategy_and_callback(cx, 2)?;
+            let transfer = Transfer::new(parse_address(address).expect("invalid address format"), amount)
+                .remainder_value_strategy(remainder_value_strategy);
+            Ok((transfer, cb))
+        }
+    }
+}
+
+/// Reads the optional remainder value strategy argument at `index` and the callback after it.
+fn remainder_value_strategy_and_callback<'a>(
+    cx: &mut CallContext<'a, JsSyncedAccount>,
+    index: i32,
+) -> NeonResult<(RemainderValueStrategy, Handle<'a, JsFunction>)> {
+    match cx.argument_opt(index + 1) {
+        Some(arg) => {
+            let cb = arg.downcast::<JsFunction>().or_throw(cx)?;
+            let remainder_value_strategy = cx.argument::<JsValue>(index)?;
+            let remainder_value_strategy = neon_serde::from_value(cx, remainder_value_strategy)?;
+            Ok((remainder_value_strategy, cb))
+        }
+        None => Ok((RemainderValueStrategy::ChangeAddress, cx.argument::<JsFunction>(index)?)),
+    }
+}
+
 declare_types! {
     pub class JsSyncedAccount for SyncedAccountWrapper {
         init(mut cx) {
@@ -29,20 +71,7 @@ declare_types! {
         }
 
         method send(mut cx) {
-            let address = cx.argument::<JsString>(0)?.value();
-            let amount = cx.argument::<JsNumber>(1)?.value() as u64;
-            let (remainder_value_strategy, cb) = match cx.argument_opt(3) {
-                Some(arg) => {
-                    let cb = arg.downcast::<JsFunction>().or_throw(&mut cx)?;
-                    let remainder_value_strategy = cx.argument::<JsValue>(2)?;
-                    let remainder_value_strategy = neon_serde::from_value(&mut cx, remainder_value_strategy)?;
-                    (remainder_value_strategy, cb)
-                }
-                None => (RemainderValueStrategy::ChangeAddress, cx.argument::<JsFunction>(2)?),
-            };
-
-            let transfer = Transfer::new(parse_address(address).expect("invalid address format"), amount)
-                .remainder_value_strategy(remainder_value_strategy);
+            let (transfer, cb) = transfer_and_callback(&mut cx)?;
 
             let this = cx.this();
             let instance = cx.borrow(&this, |r| r.clone());
@@ -55,6 +84,19 @@ declare_types! {
             Ok(cx.undefined().upcast())
         }
 
+        method preview(mut cx) {
+            let (transfer, cb) = transfer_and_callback(&mut cx)?;
+
+            let this = cx.this();
+            let instance = cx.borrow(&this, |r| r.clone());
+            let task = preview::PreviewTask {
+                synced: instance.0,
+                transfer,
+            };
+            task.schedule(cb);
+            Ok(cx.undefined().upcast())
+        }
+
         method retry(mut cx) {
             let message_id = MessageId::from_str(cx.argument::<JsString>(0)?.value().as_str()).expect("invalid message id length");
             let cb = cx.argument::<JsFunction>(1)?;
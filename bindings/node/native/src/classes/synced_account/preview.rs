@@ -0,0 +1,34 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{Arc, RwLock};
+
+use iota_wallet::{
+    account::{SyncedAccount, TransferPreview},
+    message::Transfer,
+    WalletError,
+};
+use neon::prelude::*;
+
+pub struct PreviewTask {
+    pub synced: Arc<RwLock<SyncedAccount>>,
+    pub transfer: Transfer,
+}
+
+impl Task for PreviewTask {
+    type Output = TransferPreview;
+    type Error = WalletError;
+    type JsEvent = JsValue;
+
+    fn perform(&self) -> Result<Self::Output, Self::Error> {
+        let synced = self.synced.read().unwrap();
+        synced.preview_transfer(self.transfer.clone())
+    }
+
+    fn complete(self, mut cx: TaskContext, value: Result<Self::Output, Self::Error>) -> JsResult<Self::JsEvent> {
+        match value {
+            Ok(val) => Ok(neon_serde::to_value(&mut cx, &val)?),
+            Err(e) => cx.throw_error(e.to_string()),
+        }
+    }
+}
@@ -12,6 +12,7 @@ use std::{
 use futures::{Future, FutureExt};
 use iota_wallet::{
     account::{Account, AccountIdentifier},
+    signing::{verify, DataSignature},
     WalletError,
 };
 use neon::prelude::*;
@@ -148,11 +149,19 @@ pub(crate) fn block_on<C: futures::Future>(cb: C) -> C::Output {
     runtime.lock().unwrap().block_on(cb)
 }
 
+fn verify_signature(mut cx: FunctionContext) -> JsResult<JsBoolean> {
+    let data = hex::decode(cx.argument::<JsString>(0)?.value()).expect("data must be a hex string");
+    let signature = cx.argument::<JsValue>(1)?;
+    let signature: DataSignature = neon_serde::from_value(&mut cx, signature)?;
+    Ok(cx.boolean(verify(&data, &signature)))
+}
+
 // Export the class
 register_module!(mut m, {
     m.export_class::<JsAccountManager>("AccountManager")?;
     m.export_class::<JsAccount>("Account")?;
     m.export_class::<JsSyncedAccount>("SyncedAccount")?;
     m.export_class::<JsEventListener>("EventListener")?;
+    m.export_function("verifySignature", verify_signature)?;
     Ok(())
 });
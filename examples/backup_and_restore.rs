@@ -1,7 +1,7 @@
 // Copyright 2020 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use iota_wallet::{account_manager::AccountManager, client::ClientOptionsBuilder};
+use iota_wallet::{account_manager::AccountManager, backup::RestoreOptions, client::ClientOptionsBuilder};
 
 fn main() -> iota_wallet::Result<()> {
     let mut manager = AccountManager::new().unwrap();
@@ -12,14 +12,15 @@ fn main() -> iota_wallet::Result<()> {
     let account = manager.create_account(client_options).alias("alias").initialise()?;
     let id = account.id();
 
-    // backup the stored accounts to ./backup/${backup_name}
-    let backup_path = manager.backup("./backup")?;
+    // backup the stored accounts to an encrypted archive at ./backup/${backup_name}
+    std::fs::create_dir_all("./backup")?;
+    let backup_path = manager.backup("./backup", "backup-password")?;
 
     // delete the account on the current storage
     manager.remove_account(&id)?;
 
     // import the accounts from the backup and assert that it's the same
-    manager.import_accounts(backup_path)?;
+    manager.import_accounts(backup_path, "backup-password", RestoreOptions::new())?;
     let imported_account = manager.get_account(id)?;
     assert_eq!(account, imported_account);
 
@@ -0,0 +1,149 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+use slip10::BIP32Path;
+
+use std::convert::TryFrom;
+
+/// The registered BIP44 coin type of the IOTA token.
+pub const IOTA_COIN_TYPE: u32 = 4218;
+/// The default derivation path template: BIP44 with every level hardened.
+pub const DEFAULT_PATH_TEMPLATE: &str = "m/44H/{coin_type}H/{account}H/{internal}H/{index}H";
+
+/// How the account keys are derived from the seed: the coin type and the BIP32 path template.
+///
+/// The template placeholders are `{coin_type}`, `{account}` (the account index), `{internal}` (1 for change
+/// addresses, 0 otherwise) and `{index}` (the address key index). Every level must be hardened.
+///
+/// The Stronghold signer derives the keys on the default IOTA path, so it rejects the accounts with another scheme.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+#[serde(try_from = "UncheckedDerivationScheme")]
+pub struct DerivationScheme {
+    /// The BIP44 coin type.
+    #[serde(rename = "coinType")]
+    coin_type: u32,
+    /// The BIP32 path template.
+    #[serde(rename = "pathTemplate")]
+    path_template: String,
+}
+
+/// A deserialized scheme, validated before it's used.
+#[derive(Deserialize)]
+struct UncheckedDerivationScheme {
+    #[serde(rename = "coinType")]
+    coin_type: u32,
+    #[serde(rename = "pathTemplate")]
+    path_template: String,
+}
+
+impl TryFrom<UncheckedDerivationScheme> for DerivationScheme {
+    type Error = String;
+
+    fn try_from(scheme: UncheckedDerivationScheme) -> Result<Self, Self::Error> {
+        let scheme = Self {
+            coin_type: scheme.coin_type,
+            path_template: scheme.path_template,
+        };
+        scheme.check()?;
+        Ok(scheme)
+    }
+}
+
+impl Default for DerivationScheme {
+    fn default() -> Self {
+        Self {
+            coin_type: IOTA_COIN_TYPE,
+            path_template: DEFAULT_PATH_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl DerivationScheme {
+    /// Creates a scheme with the given coin type and path template.
+    pub fn new(coin_type: u32, path_template: impl Into<String>) -> crate::Result<Self> {
+        let scheme = Self {
+            coin_type,
+            path_template: path_template.into(),
+        };
+        scheme.check().map_err(|e| anyhow::anyhow!(e))?;
+        Ok(scheme)
+    }
+
+    /// Creates a scheme with the default path template and the given coin type.
+    pub fn with_coin_type(coin_type: u32) -> Self {
+        Self {
+            coin_type,
+            path_template: DEFAULT_PATH_TEMPLATE.to_string(),
+        }
+    }
+
+    /// Whether this is the default IOTA derivation scheme.
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Gets the derivation path of an address.
+    pub fn path(&self, account_index: usize, internal: bool, address_index: usize) -> String {
+        self.path_template
+            .replace("{coin_type}", &self.coin_type.to_string())
+            .replace("{account}", &account_index.to_string())
+            .replace("{internal}", &(internal as u32).to_string())
+            .replace("{index}", &address_index.to_string())
+    }
+
+    /// Validates the path template.
+    /// The error is a plain message, so a rejected deserialization doesn't emit a wallet error event.
+    fn check(&self) -> Result<(), String> {
+        for placeholder in &["{account}", "{internal}", "{index}"] {
+            if !self.path_template.contains(placeholder) {
+                return Err(format!("the derivation path template must contain `{}`", placeholder));
+            }
+        }
+        let path = self.path(0, false, 0);
+        let hardened = path
+            .strip_prefix("m/")
+            .map(|levels| {
+                levels
+                    .split('/')
+                    .all(|level| level.ends_with('H') || level.ends_with('\''))
+            })
+            .unwrap_or(false);
+        if !hardened {
+            return Err(format!("invalid derivation path template `{}`", self.path_template));
+        }
+        BIP32Path::from_str(&path).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DerivationScheme;
+
+    #[test]
+    fn derivation_paths() {
+        let scheme = DerivationScheme::default();
+        assert_eq!(scheme.path(1, true, 5), "m/44H/4218H/1H/1H/5H");
+        assert!(scheme.is_default());
+
+        let testnet = DerivationScheme::with_coin_type(1);
+        assert_eq!(testnet.path(0, false, 2), "m/44H/1H/0H/0H/2H");
+
+        let custom = DerivationScheme::new(4218, "m/44H/{coin_type}H/{account}H/{index}H/{internal}H").unwrap();
+        assert_eq!(custom.path(0, true, 3), "m/44H/4218H/0H/3H/1H");
+
+        assert!(DerivationScheme::new(4218, "m/44H/{coin_type}H/{account}H/{index}H").is_err());
+        assert!(DerivationScheme::new(4218, "m/44H/{coin_type}H/{account}H/{internal}/{index}H").is_err());
+
+        // the deserialized schemes are validated too
+        let scheme: DerivationScheme = serde_json::from_str(&serde_json::to_string(&custom).unwrap()).unwrap();
+        assert_eq!(scheme, custom);
+        assert!(serde_json::from_str::<DerivationScheme>(
+            r#"{"coinType":4218,"pathTemplate":"m/44H/{coin_type}H/{account}H/{index}H"}"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::address::IotaAddress;
+
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+/// The number of addresses re-derived by the integrity check run before the first signature of an account.
+pub const DEFAULT_INTEGRITY_SAMPLE_SIZE: usize = 5;
+
+/// Which stored addresses `Account::verify_integrity` re-derives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum IntegrityCheck {
+    /// Every stored address.
+    All,
+    /// The given number of randomly picked addresses.
+    Sample(usize),
+}
+
+impl Default for IntegrityCheck {
+    fn default() -> Self {
+        Self::Sample(DEFAULT_INTEGRITY_SAMPLE_SIZE)
+    }
+}
+
+/// A stored address that doesn't match the address derived by the account signer.
+#[derive(Debug, Clone, PartialEq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct AddressMismatch {
+    /// The address key index.
+    #[serde(rename = "keyIndex")]
+    key_index: usize,
+    /// Whether the address is a change address.
+    internal: bool,
+    /// The stored address.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    stored: IotaAddress,
+    /// The address derived by the signer.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    derived: IotaAddress,
+}
+
+impl AddressMismatch {
+    pub(crate) fn new(key_index: usize, internal: bool, stored: IotaAddress, derived: IotaAddress) -> Self {
+        Self {
+            key_index,
+            internal,
+            stored,
+            derived,
+        }
+    }
+}
+
+/// The result of an account integrity check.
+#[derive(Debug, Clone, Default, PartialEq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct IntegrityReport {
+    /// The number of re-derived addresses.
+    checked: usize,
+    /// The addresses the signer derives differently.
+    mismatches: Vec<AddressMismatch>,
+}
+
+impl IntegrityReport {
+    pub(crate) fn new(checked: usize, mismatches: Vec<AddressMismatch>) -> Self {
+        Self { checked, mismatches }
+    }
+
+    /// Whether every checked address matches its stored value.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
@@ -2,17 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    address::{Address, IotaAddress},
+    address::{Address, AddressBuilder, InputSource, IotaAddress, OutputId},
     client::ClientOptions,
+    context::{ContextHandle, WalletContext},
     message::{Message, MessageType},
-    signing::{with_signer, SignerType},
+    signing::{BoxedSigner, DataSignature, SignerType, TransactionInput, WatchOnlyKeys},
+    storage::Storage,
 };
 
 use chrono::prelude::{DateTime, Utc};
 use getset::{Getters, Setters};
-use iota::message::prelude::MessageId;
+use iota::message::prelude::{MessageId, Transaction};
 use once_cell::sync::OnceCell;
+use rand::thread_rng;
 use serde::{Deserialize, Serialize};
+use slip10::BIP32Path;
 
 use std::{
     collections::HashMap,
@@ -21,9 +25,16 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+mod derivation;
+pub use derivation::{DerivationScheme, DEFAULT_PATH_TEMPLATE, IOTA_COIN_TYPE};
+mod integrity;
+pub use integrity::{AddressMismatch, IntegrityCheck, IntegrityReport, DEFAULT_INTEGRITY_SAMPLE_SIZE};
 mod sync;
 pub(crate) use sync::{repost_message, RepostAction};
-pub use sync::{AccountSynchronizer, SyncedAccount, TransferMetadata};
+pub use sync::{
+    AccountSynchronizer, CoinSelection, CoinSelector, PreparedTransfer, PreparedTransferInput, SelectionInput,
+    SignedTransfer, SyncedAccount, TransferMetadata, TransferPreview, TransferPreviewInput, TransferRemainder,
+};
 
 type AddressesLock = Arc<Mutex<Vec<IotaAddress>>>;
 type AccountAddressesLock = Arc<Mutex<HashMap<AccountIdentifier, AddressesLock>>>;
@@ -70,6 +81,7 @@ impl From<usize> for AccountIdentifier {
 /// Account initialiser.
 pub struct AccountInitialiser<'a> {
     mnemonic: Option<String>,
+    passphrase: Option<String>,
     alias: Option<String>,
     created_at: Option<DateTime<Utc>>,
     messages: Vec<Message>,
@@ -77,14 +89,19 @@ pub struct AccountInitialiser<'a> {
     client_options: ClientOptions,
     skip_persistance: bool,
     storage_path: &'a PathBuf,
+    context: Arc<WalletContext>,
     signer_type: Option<SignerType>,
+    index: Option<usize>,
+    watch_only: Option<WatchOnlyKeys>,
+    derivation_scheme: Option<DerivationScheme>,
 }
 
 impl<'a> AccountInitialiser<'a> {
     /// Initialises the account builder.
-    pub(crate) fn new(client_options: ClientOptions, storage_path: &'a PathBuf) -> Self {
+    pub(crate) fn new(client_options: ClientOptions, storage_path: &'a PathBuf, context: Arc<WalletContext>) -> Self {
         Self {
             mnemonic: None,
+            passphrase: None,
             alias: None,
             created_at: None,
             messages: vec![],
@@ -92,10 +109,14 @@ impl<'a> AccountInitialiser<'a> {
             client_options,
             skip_persistance: false,
             storage_path,
+            context,
             #[cfg(feature = "stronghold")]
             signer_type: Some(SignerType::Stronghold),
             #[cfg(not(feature = "stronghold"))]
             signer_type: None,
+            index: None,
+            watch_only: None,
+            derivation_scheme: None,
         }
     }
 
@@ -105,13 +126,21 @@ impl<'a> AccountInitialiser<'a> {
         self
     }
 
-    /// Defines the account BIP-39 mnemonic.
+    /// Defines the account BIP-39 mnemonic, in any of the `mnemonic::MnemonicLanguage` wordlists.
     /// When importing an account from stronghold, the mnemonic won't be required.
     pub fn mnemonic(mut self, mnemonic: impl AsRef<str>) -> Self {
         self.mnemonic = Some(mnemonic.as_ref().to_string());
         self
     }
 
+    /// Defines the BIP-39 passphrase of the account seed.
+    /// Only the signers that derive the seed themselves (e.g. Stronghold) take it; the Stronghold signer defaults to
+    /// the passphrase of the accounts created before it was configurable.
+    pub fn passphrase(mut self, passphrase: impl AsRef<str>) -> Self {
+        self.passphrase = Some(passphrase.as_ref().to_string());
+        self
+    }
+
     /// Defines the account alias. If not defined, we'll generate one.
     pub fn alias(mut self, alias: impl AsRef<str>) -> Self {
         self.alias = Some(alias.as_ref().to_string());
@@ -138,55 +167,102 @@ impl<'a> AccountInitialiser<'a> {
         self
     }
 
+    /// Creates a watch-only account from exported addresses or public keys.
+    /// Watch-only accounts sync their balance and messages, but can't derive new addresses or sign transactions.
+    /// The account takes the next wallet index like any other account; the keys carry the index of the watched account,
+    /// so the prepared transfers have the derivation paths of its signer.
+    pub fn watch_only(mut self, keys: WatchOnlyKeys) -> Self {
+        self.signer_type.replace(SignerType::WatchOnly);
+        self.watch_only = Some(keys);
+        self
+    }
+
+    /// Sets the coin type and path template used to derive the account keys.
+    /// Defaults to the IOTA coin type and the BIP44 path. The Stronghold signer only supports the default scheme.
+    pub fn derivation_scheme(mut self, scheme: DerivationScheme) -> Self {
+        self.derivation_scheme = Some(scheme);
+        self
+    }
+
     pub(crate) fn skip_persistance(mut self) -> Self {
         self.skip_persistance = true;
         self
     }
 
+    /// Sets the account index. Defaults to the number of stored accounts.
+    /// Used by the account discovery, where the discovered accounts are only stored at the end of the process.
+    pub(crate) fn index(mut self, index: usize) -> Self {
+        self.index = Some(index);
+        self
+    }
+
     /// Initialises the account.
     pub fn initialise(self) -> crate::Result<Account> {
-        let accounts = crate::storage::with_adapter(self.storage_path, |storage| storage.get_all())?;
-        let alias = self.alias.unwrap_or_else(|| format!("Account {}", accounts.len()));
+        let accounts = self.context.with_storage(|storage| storage.get_all())?;
+        let accounts = crate::storage::parse_accounts(self.storage_path, &accounts)?;
         let signer_type = self
             .signer_type
             .ok_or_else(|| anyhow::anyhow!("account signer type is required"))?;
+        let is_watch_only = signer_type == SignerType::WatchOnly;
+        let index = self.index.unwrap_or_else(|| accounts.len());
+        let alias = self.alias.unwrap_or_else(|| format!("Account {}", index));
         let created_at = self.created_at.unwrap_or_else(chrono::Utc::now);
-        let mut mnemonic = self.mnemonic;
-        if signer_type == SignerType::EnvMnemonic && accounts.is_empty() {
-            let _ = dotenv::dotenv();
-            if std::env::var("IOTA_WALLET_MNEMONIC").is_err() {
-                mnemonic = Some(
-                    bip39::Mnemonic::new(bip39::MnemonicType::Words24, bip39::Language::English)
-                        .phrase()
-                        .to_string(),
-                );
-            }
+        let mnemonic = self.mnemonic;
+        if let Some(mnemonic) = &mnemonic {
+            crate::mnemonic::mnemonic_language(mnemonic)?;
         }
 
         // check for empty latest account only when not skipping persistance (account discovery process)
-        if !self.skip_persistance {
-            if let Some(latest_account) = accounts.last() {
-                let latest_account: Account = serde_json::from_str(&latest_account)?;
+        if !self.skip_persistance && !is_watch_only {
+            let latest_account = accounts
+                .iter()
+                .rev()
+                .find(|account| account.signer_type() != &SignerType::WatchOnly);
+            if let Some(latest_account) = latest_account {
                 if latest_account.messages().is_empty() && latest_account.total_balance() == 0 {
                     return Err(crate::WalletError::LatestAccountIsEmpty);
                 }
             }
         }
 
+        let addresses = match &self.watch_only {
+            Some(keys) => keys
+                .addresses()?
+                .into_iter()
+                .map(|(key_index, internal, address)| {
+                    AddressBuilder::new()
+                        .address(address)
+                        .key_index(key_index)
+                        .internal(internal)
+                        .balance(0)
+                        .outputs(vec![])
+                        .build()
+                })
+                .collect::<crate::Result<Vec<Address>>>()?,
+            None => self.addresses,
+        };
+
         let mut account = Account {
-            id: AccountIdentifier::Index(accounts.len()),
+            id: AccountIdentifier::Index(index),
             signer_type: signer_type.clone(),
-            index: accounts.len(),
+            index,
             alias,
             created_at,
             messages: self.messages,
-            addresses: self.addresses,
+            addresses,
             client_options: self.client_options,
+            derivation_scheme: self.derivation_scheme.unwrap_or_default(),
+            watched_account_index: self.watch_only.as_ref().map(|keys| keys.account_index),
+            legacy_mnemonic_passphrase: false,
+            frozen_inputs: Vec::new(),
             storage_path: self.storage_path.clone(),
             has_pending_changes: false,
+            context: ContextHandle::new(self.context.clone()),
         };
 
-        let id = with_signer(&signer_type, |signer| signer.init_account(&account, mnemonic))?;
+        let id = self.context.with_signer(&signer_type, |signer| {
+            signer.init_account_with_passphrase(&account, mnemonic, self.passphrase)
+        })?;
         account.set_id(id.into());
 
         if !self.skip_persistance {
@@ -235,11 +311,34 @@ pub struct Account {
     /// The client options.
     #[serde(rename = "clientOptions")]
     client_options: ClientOptions,
+    /// The coin type and path template used to derive the account keys.
+    #[serde(rename = "derivationScheme", default)]
+    derivation_scheme: DerivationScheme,
+    /// The index of the watched account on its signer, for watch-only accounts.
+    /// The key derivation paths use it instead of the wallet account index.
+    #[serde(rename = "watchedAccountIndex", default, skip_serializing_if = "Option::is_none")]
+    watched_account_index: Option<usize>,
+    /// Whether the account seed takes the legacy `password` BIP39 passphrase when the mnemonic has no passphrase.
+    /// The storage migration sets it on the `EnvMnemonic` accounts created while it was the default.
+    #[getset(set = "pub(crate)")]
+    #[serde(
+        rename = "legacyMnemonicPassphrase",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    legacy_mnemonic_passphrase: bool,
+    /// The addresses and outputs excluded from every transfer.
+    #[serde(rename = "frozenInputs", default)]
+    frozen_inputs: Vec<InputSource>,
     #[getset(set = "pub(crate)", get = "pub(crate)")]
     storage_path: PathBuf,
     #[doc(hidden)]
     #[serde(skip)]
     has_pending_changes: bool,
+    /// The wallet instance the account belongs to.
+    #[getset(skip)]
+    #[serde(skip)]
+    context: ContextHandle,
 }
 
 impl Account {
@@ -251,6 +350,42 @@ impl Account {
             .max_by_key(|a| a.key_index())
     }
 
+    /// Gets the derivation path of the address with the given key index.
+    pub fn derivation_path(&self, address_index: usize, internal: bool) -> String {
+        let account_index = self.watched_account_index.unwrap_or(self.index);
+        self.derivation_scheme.path(account_index, internal, address_index)
+    }
+
+    /// Gets the wallet instance the account belongs to.
+    /// The accounts deserialized without an account manager use the instance registered for their storage path.
+    pub(crate) fn context(&self) -> Option<Arc<WalletContext>> {
+        self.context.get(&self.storage_path)
+    }
+
+    pub(crate) fn set_context(&mut self, context: Arc<WalletContext>) {
+        self.context = ContextHandle::new(context);
+    }
+
+    /// Runs the callback with the signer interface of the given type on the account wallet instance.
+    fn with_signer<T, F: FnOnce(&BoxedSigner) -> crate::Result<T>>(
+        &self,
+        signer_type: &SignerType,
+        cb: F,
+    ) -> crate::Result<T> {
+        match self.context() {
+            Some(context) => context.with_signer(signer_type, cb),
+            None => crate::signing::with_default_signer(signer_type, cb),
+        }
+    }
+
+    /// Runs the callback with the storage adapter of the account wallet instance.
+    fn with_storage<T, F: FnOnce(&Storage) -> crate::Result<T>>(&self, cb: F) -> crate::Result<T> {
+        match self.context() {
+            Some(context) => context.with_storage(cb),
+            None => Err(crate::WalletError::StorageAdapterNotSet(self.storage_path.clone())),
+        }
+    }
+
     /// Returns the builder to setup the process to synchronize this account with the Tangle.
     pub fn sync(&'_ mut self) -> AccountSynchronizer<'_> {
         AccountSynchronizer::new(self, self.storage_path.clone())
@@ -396,6 +531,201 @@ impl Account {
         Ok(address)
     }
 
+    /// Signs arbitrary data with the key of one of the account addresses, proving its ownership.
+    /// The signature can be checked with `signing::verify`.
+    pub fn sign_data(&self, address: &IotaAddress, data: &[u8]) -> crate::Result<DataSignature> {
+        self.check_integrity()?;
+        let address = self
+            .addresses
+            .iter()
+            .find(|a| a.address() == address)
+            .ok_or_else(|| anyhow::anyhow!("address {} doesn't belong to the account", address.to_bech32()))?;
+        self.with_signer(&self.signer_type, |signer| {
+            signer.sign_data(self, *address.key_index(), *address.internal(), data)
+        })
+    }
+
+    /// Freezes an address or an output, so the transfers never spend it.
+    pub fn freeze(&mut self, source: InputSource) -> crate::Result<()> {
+        self.check_input_source(&source)?;
+        if !self.frozen_inputs.contains(&source) {
+            self.frozen_inputs.push(source);
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Unfreezes an address or an output frozen with `freeze`.
+    pub fn unfreeze(&mut self, source: &InputSource) -> crate::Result<()> {
+        if let Some(index) = self.frozen_inputs.iter().position(|frozen| frozen == source) {
+            self.frozen_inputs.remove(index);
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Whether the output held by the address is frozen.
+    pub(crate) fn is_frozen(&self, address: &IotaAddress, output: &OutputId) -> bool {
+        self.frozen_inputs.iter().any(|frozen| frozen.contains(address, output))
+    }
+
+    /// Checks that the address or output belongs to the account.
+    pub(crate) fn check_input_source(&self, source: &InputSource) -> crate::Result<()> {
+        let belongs_to_account = match source {
+            InputSource::Address(address) => self.addresses.iter().any(|a| a.address() == address),
+            InputSource::Output(output) => self
+                .addresses
+                .iter()
+                .any(|a| a.outputs().iter().any(|o| &o.id() == output)),
+        };
+        if belongs_to_account {
+            Ok(())
+        } else {
+            Err(crate::WalletError::InvalidTransferInput(format!(
+                "{:?} doesn't belong to the account",
+                source
+            )))
+        }
+    }
+
+    /// Moves the account to another signer type.
+    /// The new signer initialises the account with the given mnemonic and BIP-39 passphrase, which signers creating
+    /// their own seed (e.g. Stronghold) need to derive the account keys. It must derive every address of the account,
+    /// otherwise the account keeps its signer and the new signer removes what it stored for it. The account takes the
+    /// identifier given by the new signer, and it's stored under it in the same storage transaction that removes the
+    /// previous record.
+    pub fn migrate_signer(
+        &mut self,
+        signer_type: SignerType,
+        mnemonic: Option<String>,
+        passphrase: Option<String>,
+    ) -> crate::Result<()> {
+        let mut migrated = self.clone();
+        // the copy is only persisted once the migration is verified
+        migrated.has_pending_changes = false;
+        migrated.signer_type = signer_type.clone();
+        let id = self.with_signer(&signer_type, |signer| {
+            signer.init_account_with_passphrase(&migrated, mnemonic, passphrase)
+        })?;
+        migrated.set_id(id.into());
+
+        let previous_id = self.id.clone();
+        let result = self
+            .with_signer(&signer_type, |signer| {
+                for address in &migrated.addresses {
+                    let derived_address =
+                        signer.generate_address(&migrated, *address.key_index(), *address.internal())?;
+                    if &derived_address != address.address() {
+                        return Err(crate::WalletError::SignerAddressMismatch(address.address().to_bech32()));
+                    }
+                }
+                Ok(())
+            })
+            .and_then(|_| {
+                crate::storage::transaction(&self.storage_path, |storage| {
+                    if &previous_id != migrated.id() {
+                        storage.remove(&previous_id)?;
+                    }
+                    crate::storage::merge_and_set(storage, &mut migrated)
+                })
+            });
+        if let Err(e) = result {
+            // a signer returning the previous identifier didn't store a new account, so nothing is removed
+            if &previous_id != migrated.id() {
+                let _ = self.with_signer(&signer_type, |signer| signer.remove_account(&migrated));
+            }
+            return Err(e);
+        }
+        // the pending changes were saved with the migrated account
+        self.has_pending_changes = false;
+        *self = migrated;
+        Ok(())
+    }
+
+    /// Re-derives the stored addresses with the account signer and reports the ones that don't match.
+    /// While the last check of the account reported mismatches, it refuses to sign. An account that wasn't checked
+    /// since its signer was registered runs the check with a sample of its addresses before its first signature.
+    pub fn verify_integrity(&self, check: IntegrityCheck) -> crate::Result<IntegrityReport> {
+        let addresses: Vec<&Address> = match check {
+            IntegrityCheck::All => self.addresses.iter().collect(),
+            IntegrityCheck::Sample(size) => rand::sample(&mut thread_rng(), self.addresses.iter(), size),
+        };
+        let mismatches = self.with_signer(&self.signer_type, |signer| {
+            let mut mismatches = vec![];
+            for address in &addresses {
+                let derived_address = signer.generate_address(self, *address.key_index(), *address.internal())?;
+                if &derived_address != address.address() {
+                    mismatches.push(AddressMismatch::new(
+                        *address.key_index(),
+                        *address.internal(),
+                        address.address().clone(),
+                        derived_address,
+                    ));
+                }
+            }
+            Ok(mismatches)
+        })?;
+        let report = IntegrityReport::new(addresses.len(), mismatches);
+        if let Some(context) = self.context() {
+            context.set_integrity_result(&self.id, report.is_ok());
+        }
+        Ok(report)
+    }
+
+    /// Fails if the last integrity check of the account found mismatches, running the check if there's none.
+    fn check_integrity(&self) -> crate::Result<()> {
+        let is_ok = match self.context().and_then(|context| context.integrity_result(&self.id)) {
+            Some(is_ok) => is_ok,
+            None => self.verify_integrity(IntegrityCheck::default())?.is_ok(),
+        };
+        if is_ok {
+            Ok(())
+        } else {
+            Err(crate::WalletError::AccountIntegrityMismatch(self.id.clone()))
+        }
+    }
+
+    /// Signs a transfer prepared with `SyncedAccount::prepare_transfer`.
+    /// It only needs the account signer, so it can run on an offline machine.
+    pub fn sign_prepared(&self, prepared: &PreparedTransfer) -> crate::Result<SignedTransfer> {
+        self.check_integrity()?;
+        let essence = prepared.unpack_essence()?;
+        // the key paths are derived from the account's own addresses, never taken from the prepared transfer
+        let mut inputs = prepared
+            .inputs()
+            .iter()
+            .map(|input| {
+                let address = self
+                    .addresses
+                    .iter()
+                    .find(|address| address.address() == input.address())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "input address {} doesn't belong to the account",
+                            input.address().to_bech32()
+                        )
+                    })?;
+                let address_path = self.derivation_path(*address.key_index(), *address.internal());
+                Ok(TransactionInput {
+                    input: input.unpack_input()?,
+                    address_index: *address.key_index(),
+                    address_path: BIP32Path::from_str(&address_path).map_err(|e| anyhow::anyhow!(e.to_string()))?,
+                })
+            })
+            .collect::<crate::Result<Vec<TransactionInput>>>()?;
+
+        let unlock_blocks = self.with_signer(&self.signer_type, |signer| {
+            signer.sign_message(self, &essence, &mut inputs)
+        })?;
+        let mut tx_builder = Transaction::builder().with_essence(essence);
+        for unlock_block in unlock_blocks {
+            tx_builder = tx_builder.add_unlock_block(unlock_block);
+        }
+        let transaction = tx_builder.finish().map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+
+        Ok(SignedTransfer::new(prepared.clone(), &transaction))
+    }
+
     #[doc(hidden)]
     pub fn append_messages(&mut self, messages: Vec<Message>) {
         self.messages.extend(messages);
@@ -428,6 +758,21 @@ impl Account {
     pub fn get_message(&self, message_id: &MessageId) -> Option<&Message> {
         self.messages.iter().find(|tx| tx.id() == message_id)
     }
+
+    /// Gets a page of the account messages from the storage, ordered by timestamp (most recent first).
+    /// Unlike `list_messages`, the page is read through the storage adapter instead of the in-memory list,
+    /// so reattachments aren't merged and unsaved changes aren't included.
+    ///
+    /// * `count` - Number of messages to fetch. Zero fetches every message.
+    /// * `from` - Number of messages to skip.
+    pub fn load_messages(&self, count: usize, from: usize) -> crate::Result<Vec<Message>> {
+        self.with_storage(|storage| storage.list_messages(&self.id, count, from))
+    }
+
+    /// Gets the message with the given id from the storage.
+    pub fn load_message(&self, message_id: &MessageId) -> crate::Result<Option<Message>> {
+        self.with_storage(|storage| storage.get_message(&self.id, message_id))
+    }
 }
 
 impl Drop for Account {
@@ -456,8 +801,231 @@ pub struct InitialisedAccount<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::client::ClientOptionsBuilder;
+    use super::{Account, AccountIdentifier};
+    use crate::{
+        client::ClientOptionsBuilder,
+        mock_node::MockSigner,
+        signing::{Signer, SignerType, TransactionInput},
+    };
     use rusty_fork::rusty_fork_test;
+    use std::sync::{Arc, Mutex};
+
+    /// Derives the mock signer addresses under another account identifier, recording the removed accounts.
+    struct RenamingSigner(MockSigner, Arc<Mutex<Vec<AccountIdentifier>>>);
+
+    impl Signer for RenamingSigner {
+        fn init_account(&self, account: &Account, mnemonic: Option<String>) -> crate::Result<String> {
+            let id = self.0.init_account(account, mnemonic)?;
+            Ok(id.chars().rev().collect())
+        }
+
+        fn generate_address(&self, account: &Account, index: usize, internal: bool) -> crate::Result<iota::Address> {
+            self.0.generate_address(account, index, internal)
+        }
+
+        fn sign_message(
+            &self,
+            account: &Account,
+            essence: &iota::TransactionEssence,
+            inputs: &mut Vec<TransactionInput>,
+        ) -> crate::Result<Vec<iota::UnlockBlock>> {
+            self.0.sign_message(account, essence, inputs)
+        }
+
+        fn remove_account(&self, account: &Account) -> crate::Result<()> {
+            self.1.lock().unwrap().push(account.id().clone());
+            Ok(())
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn invalid_mnemonic() {
+            let (manager, node) = crate::test_utils::get_mock_account_manager(crate::mock_node::MockLedger::new());
+            let mnemonic = crate::mnemonic::generate_mnemonic(256, crate::mnemonic::MnemonicLanguage::English).unwrap();
+            let invalid_mnemonic = mnemonic.replacen(mnemonic.split_whitespace().next().unwrap(), "notaword", 1);
+            let result = manager
+                .create_account(node.client_options())
+                .signer_type(SignerType::Custom("unregistered".to_string()))
+                .mnemonic(invalid_mnemonic)
+                .initialise();
+            // the mnemonic is rejected before the signer lookup
+            assert!(matches!(result, Err(crate::WalletError::InvalidMnemonic(_))));
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn migrate_signer() {
+            let (manager, node) = crate::test_utils::get_mock_account_manager(crate::mock_node::MockLedger::new());
+            let mut account = manager
+                .create_account(node.client_options())
+                .signer_type(crate::test_utils::mock_signer_type())
+                .initialise()
+                .unwrap();
+
+            let other_seed_type = SignerType::Custom("other-seed".to_string());
+            manager
+                .context()
+                .register_signer(other_seed_type.clone(), MockSigner::new([1; 32]))
+                .unwrap();
+            assert!(matches!(
+                account.migrate_signer(other_seed_type, None, None),
+                Err(crate::WalletError::SignerAddressMismatch(_))
+            ));
+            assert_eq!(account.signer_type(), &crate::test_utils::mock_signer_type());
+
+            // the signer removes the account it initialised for the failed migration
+            let removed = Arc::new(Mutex::new(vec![]));
+            let other_seed_renaming_type = SignerType::Custom("other-seed-renaming".to_string());
+            manager
+                .context()
+                .register_signer(
+                    other_seed_renaming_type.clone(),
+                    RenamingSigner(MockSigner::new([1; 32]), removed.clone()),
+                )
+                .unwrap();
+            assert!(account.migrate_signer(other_seed_renaming_type, None, None).is_err());
+            assert_eq!(removed.lock().unwrap().len(), 1);
+            assert_ne!(&removed.lock().unwrap()[0], account.id());
+
+            let same_seed_type = SignerType::Custom("same-seed".to_string());
+            manager
+                .context()
+                .register_signer(same_seed_type.clone(), MockSigner::default())
+                .unwrap();
+            // the signer doesn't take a passphrase
+            assert!(account
+                .migrate_signer(same_seed_type.clone(), None, Some("passphrase".to_string()))
+                .is_err());
+            account.migrate_signer(same_seed_type.clone(), None, None).unwrap();
+            assert_eq!(manager.get_account(account.id()).unwrap().signer_type(), &same_seed_type);
+
+            // the account is stored under the identifier given by the new signer
+            let previous_id = account.id().clone();
+            let renaming_type = SignerType::Custom("renaming".to_string());
+            manager
+                .context()
+                .register_signer(
+                    renaming_type.clone(),
+                    RenamingSigner(MockSigner::default(), Default::default()),
+                )
+                .unwrap();
+            account.migrate_signer(renaming_type.clone(), None, None).unwrap();
+            assert_ne!(account.id(), &previous_id);
+            assert_eq!(manager.get_account(account.id()).unwrap().signer_type(), &renaming_type);
+            assert!(manager.get_account(&previous_id).is_err());
+            assert_eq!(manager.get_accounts().unwrap().len(), 1);
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn derivation_scheme() {
+            let (manager, node) = crate::test_utils::get_mock_account_manager(crate::mock_node::MockLedger::new());
+            let account = manager
+                .create_account(node.client_options())
+                .signer_type(crate::test_utils::mock_signer_type())
+                .derivation_scheme(super::DerivationScheme::with_coin_type(1))
+                .initialise()
+                .unwrap();
+            assert_eq!(account.derivation_path(2, true), "m/44H/1H/0H/1H/2H");
+            assert_eq!(
+                manager.get_account(account.id()).unwrap().derivation_scheme(),
+                &super::DerivationScheme::with_coin_type(1)
+            );
+
+            let mut default_account = account.clone();
+            default_account.derivation_scheme = super::DerivationScheme::default();
+            let signer = MockSigner::default();
+            assert_ne!(
+                signer.generate_address(&account, 0, false).unwrap(),
+                signer.generate_address(&default_account, 0, false).unwrap()
+            );
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn verify_integrity() {
+            let (manager, node) = crate::test_utils::get_mock_account_manager(crate::mock_node::MockLedger::new());
+            let mut account = manager
+                .create_account(node.client_options())
+                .signer_type(crate::test_utils::mock_signer_type())
+                .initialise()
+                .unwrap();
+            let address = account.generate_address().unwrap();
+            account.generate_address().unwrap();
+
+            let report = account.verify_integrity(super::IntegrityCheck::All).unwrap();
+            assert!(report.is_ok());
+            assert_eq!(report.checked(), &account.addresses().len());
+            account.sign_data(address.address(), b"data").unwrap();
+
+            // the stored address was derived from another seed
+            let other_address = MockSigner::new([1; 32])
+                .generate_address(&account, *address.key_index(), *address.internal())
+                .unwrap();
+            let position = account.addresses().iter().position(|a| a == &address).unwrap();
+            account.addresses_mut()[position] = super::AddressBuilder::new()
+                .address(other_address.clone())
+                .key_index(*address.key_index())
+                .balance(0)
+                .outputs(vec![])
+                .build()
+                .unwrap();
+
+            let report = account.verify_integrity(super::IntegrityCheck::All).unwrap();
+            assert_eq!(report.mismatches().len(), 1);
+            assert_eq!(report.mismatches()[0].stored(), &other_address);
+            assert_eq!(report.mismatches()[0].derived(), address.address());
+            assert!(matches!(
+                account.sign_data(&other_address, b"data"),
+                Err(crate::WalletError::AccountIntegrityMismatch(_))
+            ));
+
+            account.addresses_mut()[position] = address.clone();
+            assert!(account.verify_integrity(super::IntegrityCheck::Sample(10)).unwrap().is_ok());
+            account.sign_data(address.address(), b"data").unwrap();
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn lazy_integrity_check() {
+            let (manager, node) = crate::test_utils::get_mock_account_manager(crate::mock_node::MockLedger::new());
+            let mut account = manager
+                .create_account(node.client_options())
+                .signer_type(crate::test_utils::mock_signer_type())
+                .initialise()
+                .unwrap();
+            let address = account.addresses()[0].clone();
+
+            // the first signature checks the account, which was never verified
+            let other_address = MockSigner::new([1; 32])
+                .generate_address(&account, *address.key_index(), *address.internal())
+                .unwrap();
+            account.addresses_mut()[0] = super::AddressBuilder::new()
+                .address(other_address.clone())
+                .key_index(*address.key_index())
+                .balance(0)
+                .outputs(vec![])
+                .build()
+                .unwrap();
+            assert!(matches!(
+                account.sign_data(&other_address, b"data"),
+                Err(crate::WalletError::AccountIntegrityMismatch(_))
+            ));
+
+            // registering a signer invalidates the previous checks
+            account.addresses_mut()[0] = address.clone();
+            manager
+                .context()
+                .register_signer(SignerType::Custom("lazy".to_string()), MockSigner::default())
+                .unwrap();
+            account.sign_data(address.address(), b"data").unwrap();
+        }
+    }
 
     rusty_fork_test! {
         #[test]
@@ -1,67 +1,257 @@
 // Copyright 2020 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::address::IotaAddress;
+use crate::address::{IotaAddress, OutputId};
+use chrono::prelude::{DateTime, Utc};
+use iota::message::prelude::TransactionId;
 use rand::{thread_rng, Rng};
-use std::convert::TryInto;
+use serde::Deserialize;
+use std::{fmt, sync::Arc};
 
-#[derive(Clone)]
-pub struct Input {
+/// The number of branches the branch and bound search explores before giving up on an exact match.
+const BRANCH_AND_BOUND_MAX_TRIES: usize = 100_000;
+
+/// An available output of the account that can fund a transfer.
+#[derive(Debug, Clone)]
+pub struct SelectionInput {
+    /// The transaction id of the output.
+    pub transaction_id: TransactionId,
+    /// The output index.
+    pub index: u16,
+    /// The address holding the output.
     pub address: IotaAddress,
+    /// The output amount.
     pub balance: u64,
+    /// When the output was received, if known.
+    pub received_at: Option<DateTime<Utc>>,
+}
+
+impl SelectionInput {
+    /// The identifier of the output.
+    pub fn output_id(&self) -> OutputId {
+        OutputId::new(self.transaction_id, self.index)
+    }
+}
+
+/// Picks the inputs of a transfer.
+pub trait CoinSelector {
+    /// Selects at most `max_inputs` inputs with a total balance of at least `target` among the available ones.
+    /// The caller makes sure the target can be reached with `max_inputs` of the available inputs.
+    fn select(
+        &self,
+        target: u64,
+        max_inputs: usize,
+        available_inputs: &mut [SelectionInput],
+    ) -> crate::Result<Vec<SelectionInput>>;
+}
+
+/// The coin selection strategy of a transfer.
+#[derive(Clone, Deserialize)]
+#[serde(tag = "strategy", content = "value")]
+pub enum CoinSelection {
+    /// Looks for an exact match with branch and bound, falling back to a single random draw.
+    BranchAndBound,
+    /// Spends the largest balances first, using few inputs.
+    LargestFirst,
+    /// Spends the smallest balances first, sweeping dust.
+    SmallestFirst,
+    /// Spends the oldest received outputs first.
+    OldestFirst,
+    /// Picks the inputs leaving the smallest remainder.
+    MinimizeChange,
+    /// A custom selector.
+    #[serde(skip)]
+    Custom(Arc<dyn CoinSelector + Send + Sync>),
+}
+
+impl Default for CoinSelection {
+    fn default() -> Self {
+        Self::BranchAndBound
+    }
+}
+
+impl fmt::Debug for CoinSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BranchAndBound => write!(f, "BranchAndBound"),
+            Self::LargestFirst => write!(f, "LargestFirst"),
+            Self::SmallestFirst => write!(f, "SmallestFirst"),
+            Self::OldestFirst => write!(f, "OldestFirst"),
+            Self::MinimizeChange => write!(f, "MinimizeChange"),
+            Self::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl CoinSelector for CoinSelection {
+    fn select(
+        &self,
+        target: u64,
+        max_inputs: usize,
+        available_inputs: &mut [SelectionInput],
+    ) -> crate::Result<Vec<SelectionInput>> {
+        match self {
+            Self::BranchAndBound => branch_and_bound_or_random_draw(target, max_inputs, available_inputs),
+            Self::LargestFirst => {
+                available_inputs.sort_by(|a, b| b.balance.cmp(&a.balance));
+                Ok(accumulate(target, max_inputs, available_inputs))
+            }
+            Self::SmallestFirst => {
+                available_inputs.sort_by(|a, b| a.balance.cmp(&b.balance));
+                Ok(accumulate(target, max_inputs, available_inputs))
+            }
+            Self::OldestFirst => {
+                // the inputs without a known receive date go last
+                available_inputs.sort_by_key(|input| (input.received_at.is_none(), input.received_at));
+                Ok(accumulate(target, max_inputs, available_inputs))
+            }
+            Self::MinimizeChange => minimize_change(target, max_inputs, available_inputs),
+            Self::Custom(selector) => selector.select(target, max_inputs, available_inputs),
+        }
+    }
 }
 
-pub fn select_input(target: u64, available_utxos: &mut [Input]) -> crate::Result<Vec<Input>> {
+/// Selects at most `max_inputs` inputs of a transfer with the given strategy.
+pub(crate) fn select_input(
+    target: u64,
+    max_inputs: usize,
+    available_utxos: &mut [SelectionInput],
+    selector: &dyn CoinSelector,
+) -> crate::Result<Vec<SelectionInput>> {
     if target > available_utxos.iter().fold(0, |acc, address| acc + address.balance) {
         return Err(crate::WalletError::InsufficientFunds);
     }
 
+    // the fewest inputs reaching the target are the largest ones
+    let mut balances: Vec<u64> = available_utxos.iter().map(|address| address.balance).collect();
+    balances.sort_by(|a, b| b.cmp(a));
+    let mut sum = 0;
+    let needed_inputs = balances
+        .iter()
+        .take_while(|balance| {
+            let old_sum = sum;
+            sum += **balance;
+            old_sum < target
+        })
+        .count();
+    if needed_inputs > max_inputs {
+        return Err(crate::WalletError::TooManyInputs(needed_inputs, max_inputs));
+    }
+
+    let selected_coins = selector.select(target, max_inputs, available_utxos)?;
+    if target > selected_coins.iter().fold(0, |acc, address| acc + address.balance) {
+        return Err(anyhow::anyhow!("the coin selector didn't select enough inputs").into());
+    }
+    if selected_coins.len() > max_inputs {
+        return Err(crate::WalletError::TooManyInputs(selected_coins.len(), max_inputs));
+    }
+    Ok(selected_coins)
+}
+
+/// Takes the inputs in order until they reach the target.
+/// Once `max_inputs` inputs are taken, a larger input replaces the smallest taken one, so the target is reached within
+/// the limit whenever the largest inputs reach it.
+fn accumulate(target: u64, max_inputs: usize, available_utxos: &[SelectionInput]) -> Vec<SelectionInput> {
+    let mut selected_coins: Vec<SelectionInput> = Vec::new();
+    let mut sum = 0;
+    for address in available_utxos {
+        if sum >= target {
+            break;
+        }
+        if selected_coins.len() < max_inputs {
+            sum += address.balance;
+            selected_coins.push(address.clone());
+        } else if let Some((index, smallest)) = selected_coins
+            .iter()
+            .map(|coin| coin.balance)
+            .enumerate()
+            .min_by_key(|(_, balance)| *balance)
+        {
+            if smallest < address.balance {
+                sum = sum - smallest + address.balance;
+                selected_coins[index] = address.clone();
+            }
+        }
+    }
+    selected_coins
+}
+
+fn minimize_change(
+    target: u64,
+    max_inputs: usize,
+    available_utxos: &mut [SelectionInput],
+) -> crate::Result<Vec<SelectionInput>> {
     available_utxos.sort_by(|a, b| b.balance.cmp(&a.balance));
     let mut selected_coins = Vec::new();
+    let mut tries = BRANCH_AND_BOUND_MAX_TRIES;
+    if branch_and_bound(
+        target,
+        max_inputs,
+        available_utxos,
+        0,
+        &mut selected_coins,
+        0,
+        &mut tries,
+    ) {
+        return Ok(selected_coins);
+    }
+
+    // the smallest single input covering the target, or the largest inputs otherwise
+    let sum = |coins: &[SelectionInput]| coins.iter().fold(0, |acc, address| acc + address.balance);
+    let largest_first = accumulate(target, max_inputs, available_utxos);
+    let single = available_utxos
+        .iter()
+        .filter(|address| address.balance >= target)
+        .min_by_key(|address| address.balance);
+    match single {
+        Some(single) if single.balance <= sum(&largest_first) => Ok(vec![single.clone()]),
+        _ => Ok(largest_first),
+    }
+}
+
+fn branch_and_bound_or_random_draw(
+    target: u64,
+    max_inputs: usize,
+    available_utxos: &mut [SelectionInput],
+) -> crate::Result<Vec<SelectionInput>> {
+    available_utxos.sort_by(|a, b| b.balance.cmp(&a.balance));
+    let mut selected_coins = Vec::new();
+    let mut tries = BRANCH_AND_BOUND_MAX_TRIES;
     let result = branch_and_bound(
         target,
+        max_inputs,
         available_utxos,
         0,
         &mut selected_coins,
         0,
-        2i128
-            .checked_pow(available_utxos.len().try_into().unwrap())
-            .unwrap_or(i128::max_value()),
+        &mut tries,
     );
 
     if result {
         Ok(selected_coins)
     } else {
         // If no match, Single Random Draw
-        single_random_draw(target, available_utxos)
+        single_random_draw(target, max_inputs, available_utxos)
     }
 }
 
-fn single_random_draw(target: u64, available_utxos: &mut [Input]) -> crate::Result<Vec<Input>> {
+fn single_random_draw(
+    target: u64,
+    max_inputs: usize,
+    available_utxos: &mut [SelectionInput],
+) -> crate::Result<Vec<SelectionInput>> {
     thread_rng().shuffle(available_utxos);
-    let mut sum = 0;
-
-    let selected_coins_iter = available_utxos.iter_mut().take_while(|address| {
-        let value = address.balance;
-        let old_sum = sum;
-        sum += value;
-        old_sum < target
-    });
-    let mut selected_coins = vec![];
-    for coin in selected_coins_iter {
-        selected_coins.push(coin.clone());
-    }
-
-    Ok(selected_coins)
+    Ok(accumulate(target, max_inputs, available_utxos))
 }
 
 fn branch_and_bound(
     target: u64,
-    available_utxos: &mut [Input],
+    max_inputs: usize,
+    available_utxos: &mut [SelectionInput],
     depth: usize,
-    current_selection: &mut Vec<Input>,
+    current_selection: &mut Vec<SelectionInput>,
     effective_value: u64,
-    mut tries: i128,
+    tries: &mut usize,
 ) -> bool {
     if effective_value > target {
         return false;
@@ -71,11 +261,11 @@ fn branch_and_bound(
         return true;
     }
 
-    if tries <= 0 || depth >= available_utxos.len() {
+    if *tries == 0 || depth >= available_utxos.len() || current_selection.len() >= max_inputs {
         return false;
     }
 
-    tries -= 1;
+    *tries -= 1;
 
     // Exploring omission and inclusion branch
     let current_utxo_value = available_utxos[depth].balance;
@@ -83,6 +273,7 @@ fn branch_and_bound(
 
     if branch_and_bound(
         target,
+        max_inputs,
         available_utxos,
         depth + 1,
         current_selection,
@@ -96,6 +287,7 @@ fn branch_and_bound(
 
     branch_and_bound(
         target,
+        max_inputs,
         available_utxos,
         depth + 1,
         current_selection,
@@ -108,11 +300,14 @@ fn branch_and_bound(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::address::{AddressBuilder, IotaAddress};
+    use crate::{
+        address::{AddressBuilder, IotaAddress},
+        message::MAX_INPUT_COUNT,
+    };
     use iota::message::prelude::Ed25519Address;
     use rand::{Rng, SeedableRng, StdRng};
 
-    fn generate_random_utxos(rng: &mut StdRng, utxos_number: usize) -> Vec<Input> {
+    fn generate_random_utxos(rng: &mut StdRng, utxos_number: usize) -> Vec<SelectionInput> {
         let mut available_utxos = Vec::new();
         for i in 0..utxos_number {
             let address = AddressBuilder::new()
@@ -122,15 +317,18 @@ mod tests {
                 .outputs(vec![])
                 .build()
                 .unwrap();
-            available_utxos.push(super::Input {
+            available_utxos.push(super::SelectionInput {
+                transaction_id: TransactionId::new([0; 32]),
+                index: i as u16,
                 address: address.address().clone(),
                 balance: *address.balance(),
+                received_at: None,
             });
         }
         available_utxos
     }
 
-    fn sum_random_utxos(rng: &mut StdRng, available_utxos: &mut Vec<Input>) -> u64 {
+    fn sum_random_utxos(rng: &mut StdRng, available_utxos: &mut Vec<SelectionInput>) -> u64 {
         let utxos_picked_len = rng.gen_range(2, available_utxos.len() / 2);
         thread_rng().shuffle(available_utxos);
         available_utxos[..utxos_picked_len]
@@ -145,7 +343,13 @@ mod tests {
         for _i in 0..20 {
             let mut available_utxos = generate_random_utxos(&mut rng, 30);
             let sum_utxos_picked = sum_random_utxos(&mut rng, &mut available_utxos);
-            let selected = select_input(sum_utxos_picked, &mut available_utxos).unwrap();
+            let selected = select_input(
+                sum_utxos_picked,
+                MAX_INPUT_COUNT,
+                &mut available_utxos,
+                &CoinSelection::default(),
+            )
+            .unwrap();
             assert_eq!(
                 selected.iter().fold(0, |acc, address| { acc + address.balance }),
                 sum_utxos_picked
@@ -159,7 +363,7 @@ mod tests {
         let mut rng: StdRng = SeedableRng::from_seed(seed);
         let mut available_utxos = generate_random_utxos(&mut rng, 30);
         let target = available_utxos.iter().fold(0, |acc, address| acc + address.balance) + 1;
-        let response = select_input(target, &mut available_utxos);
+        let response = select_input(target, MAX_INPUT_COUNT, &mut available_utxos, &CoinSelection::default());
         assert!(response.is_err());
     }
 
@@ -171,7 +375,7 @@ mod tests {
             let mut available_utxos = generate_random_utxos(&mut rng, 30);
             let sum_utxos = available_utxos.iter().fold(0, |acc, address| acc + address.balance);
             let target = rng.gen_range(sum_utxos / 2, sum_utxos * 2);
-            let response = select_input(target, &mut available_utxos);
+            let response = select_input(target, MAX_INPUT_COUNT, &mut available_utxos, &CoinSelection::default());
             if target > sum_utxos {
                 assert!(response.is_err());
             } else {
@@ -181,4 +385,90 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn strategies() {
+        let balance = |coins: &[SelectionInput]| coins.iter().map(|address| address.balance).collect::<Vec<u64>>();
+        let available_utxos: Vec<SelectionInput> = [(5, 3), (50, 1), (20, 2), (100, 4)]
+            .iter()
+            .map(|(balance, age)| SelectionInput {
+                transaction_id: TransactionId::new([*balance as u8; 32]),
+                index: 0,
+                address: IotaAddress::Ed25519(Ed25519Address::new([*balance as u8; 32])),
+                balance: *balance,
+                received_at: Some(chrono::Utc::now() - chrono::Duration::days(*age)),
+            })
+            .collect();
+
+        let select = |target: u64, strategy: CoinSelection| {
+            select_input(target, MAX_INPUT_COUNT, &mut available_utxos.clone(), &strategy).unwrap()
+        };
+        assert_eq!(balance(&select(60, CoinSelection::LargestFirst)), vec![100]);
+        assert_eq!(balance(&select(60, CoinSelection::SmallestFirst)), vec![5, 20, 50]);
+        assert_eq!(balance(&select(60, CoinSelection::OldestFirst)), vec![100]);
+        assert_eq!(balance(&select(110, CoinSelection::OldestFirst)), vec![100, 5, 20]);
+        assert_eq!(balance(&select(70, CoinSelection::MinimizeChange)), vec![50, 20]);
+        assert_eq!(balance(&select(40, CoinSelection::MinimizeChange)), vec![50]);
+        assert!(select_input(
+            200,
+            MAX_INPUT_COUNT,
+            &mut available_utxos.clone(),
+            &CoinSelection::LargestFirst
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn bounded_search() {
+        // an odd target can't be matched by even outputs, so the search has to give up
+        let available_utxos: Vec<SelectionInput> = (0..40)
+            .map(|index| SelectionInput {
+                transaction_id: TransactionId::new([0; 32]),
+                index,
+                address: IotaAddress::Ed25519(Ed25519Address::new([0; 32])),
+                balance: 10,
+                received_at: None,
+            })
+            .collect();
+        for strategy in &[CoinSelection::BranchAndBound, CoinSelection::MinimizeChange] {
+            let selected = select_input(205, MAX_INPUT_COUNT, &mut available_utxos.clone(), strategy).unwrap();
+            assert_eq!(selected.len(), 21);
+        }
+    }
+
+    #[test]
+    fn inputs_limit() {
+        let available_utxos: Vec<SelectionInput> = [5, 50, 20, 100]
+            .iter()
+            .map(|balance| SelectionInput {
+                transaction_id: TransactionId::new([*balance as u8; 32]),
+                index: 0,
+                address: IotaAddress::Ed25519(Ed25519Address::new([*balance as u8; 32])),
+                balance: *balance,
+                received_at: None,
+            })
+            .collect();
+
+        let select = |target: u64, max_inputs: usize, strategy: &CoinSelection| {
+            select_input(target, max_inputs, &mut available_utxos.clone(), strategy)
+        };
+        for strategy in &[
+            CoinSelection::BranchAndBound,
+            CoinSelection::LargestFirst,
+            CoinSelection::SmallestFirst,
+            CoinSelection::OldestFirst,
+            CoinSelection::MinimizeChange,
+        ] {
+            let selected = select(60, 1, strategy).unwrap();
+            assert_eq!(selected.len(), 1);
+            assert_eq!(selected[0].balance, 100);
+            let selected = select(120, 2, strategy).unwrap();
+            assert_eq!(selected.len(), 2);
+            assert!(selected.iter().map(|coin| coin.balance).sum::<u64>() >= 120);
+        }
+        assert!(matches!(
+            select(160, 2, &CoinSelection::SmallestFirst),
+            Err(crate::WalletError::TooManyInputs(3, 2))
+        ));
+    }
 }
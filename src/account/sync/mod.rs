@@ -3,13 +3,15 @@
 
 use crate::{
     account::{get_account_addresses_lock, Account, AccountIdentifier},
-    address::{Address, AddressBuilder, AddressOutput, IotaAddress},
+    address::{Address, AddressBuilder, AddressOutput, IotaAddress, OutputId},
     client::get_client,
-    message::{Message, RemainderValueStrategy, Transfer},
+    message::{Message, RemainderValueStrategy, Transfer, TransferOutput, MAX_INPUT_COUNT, MAX_OUTPUT_COUNT},
+    signing::SignerType,
 };
 
 use getset::Getters;
 use iota::{
+    common::packable::Packable,
     message::prelude::{
         Input, Message as IotaMessage, MessageBuilder, MessageId, Payload, SignatureLockedSingleOutput, Transaction,
         TransactionEssence, UTXOInput,
@@ -17,18 +19,18 @@ use iota::{
     ClientMiner,
 };
 use serde::{Deserialize, Serialize};
-use slip10::BIP32Path;
 
 use std::{
     convert::TryInto,
     num::NonZeroU64,
     path::PathBuf,
-    sync::{mpsc::channel, Arc, Mutex, MutexGuard},
+    sync::{mpsc::channel, Arc, Mutex},
     thread,
     time::Duration,
 };
 
 mod input_selection;
+pub use input_selection::{CoinSelection, CoinSelector, SelectionInput};
 
 const OUTPUT_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -72,7 +74,7 @@ async fn sync_addresses(
         let mut futures_ = vec![];
         for (iota_address_index, iota_address_internal, iota_address) in &generated_iota_addresses {
             futures_.push(async move {
-                let client = crate::client::get_client(account.client_options());
+                let client = crate::client::get_client(account.storage_path(), account.client_options());
                 let client = client.read().unwrap();
 
                 let address_outputs = client.get_address().outputs(&iota_address).await?;
@@ -150,6 +152,7 @@ async fn sync_messages(
 ) -> crate::Result<Vec<(MessageId, Option<bool>, IotaMessage)>> {
     let mut messages = vec![];
     let client_options = account.client_options().clone();
+    let storage_path = account.storage_path().clone();
 
     let futures_ = account
         .addresses_mut()
@@ -157,8 +160,9 @@ async fn sync_messages(
         .take(stop_at_address_index)
         .map(|address| {
             let client_options = client_options.clone();
+            let storage_path = storage_path.clone();
             async move {
-                let client = crate::client::get_client(&client_options);
+                let client = crate::client::get_client(&storage_path, &client_options);
                 let client = client.read().unwrap();
 
                 let address_outputs = client.get_address().outputs(address.address()).await?;
@@ -200,7 +204,7 @@ async fn update_account_messages<'a>(
     account: &'a mut Account,
     new_messages: &'a [(MessageId, Option<bool>, IotaMessage)],
 ) -> crate::Result<()> {
-    let client = get_client(account.client_options());
+    let client = get_client(account.storage_path(), account.client_options());
     let messages = account.messages_mut();
 
     // sync `broadcasted` state
@@ -236,7 +240,13 @@ async fn perform_sync(
     address_index: usize,
     gap_limit: usize,
 ) -> crate::Result<bool> {
-    let (found_addresses, found_messages) = sync_addresses(&storage_path, &account, address_index, gap_limit).await?;
+    // watch-only accounts can't derive addresses, so only the watched addresses are synced
+    let is_watch_only = account.signer_type() == &SignerType::WatchOnly;
+    let (found_addresses, found_messages) = if is_watch_only {
+        (vec![], vec![])
+    } else {
+        sync_addresses(&storage_path, &account, address_index, gap_limit).await?
+    };
 
     let mut new_messages = vec![];
     for (found_message_id, confirmed, found_message) in found_messages {
@@ -249,7 +259,12 @@ async fn perform_sync(
         }
     }
 
-    let synced_messages = sync_messages(&mut account, address_index).await?;
+    let stop_at_address_index = if is_watch_only {
+        account.addresses().len()
+    } else {
+        address_index
+    };
+    let synced_messages = sync_messages(&mut account, stop_at_address_index).await?;
     new_messages.extend(synced_messages.into_iter());
 
     update_account_messages(&mut account, &new_messages).await?;
@@ -343,7 +358,7 @@ impl<'a> AccountSynchronizer<'a> {
     /// associated with an account is fetched from the tangle and is stored locally.
     pub async fn execute(self) -> crate::Result<SyncedAccount> {
         let options = self.account.client_options().clone();
-        let client = get_client(&options);
+        let client = get_client(self.account.storage_path(), &options);
 
         let _ = crate::monitor::unsubscribe(&self.account);
 
@@ -411,62 +426,317 @@ pub struct TransferMetadata {
     pub account: Account,
 }
 
+/// An input of a prepared transfer.
+#[derive(Debug, Clone, PartialEq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct PreparedTransferInput {
+    /// The hex encoded packed input.
+    input: String,
+    /// The account address holding the input.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: IotaAddress,
+    /// The address key index.
+    #[serde(rename = "addressIndex")]
+    address_index: usize,
+    /// The address BIP32 derivation path.
+    #[serde(rename = "addressPath")]
+    address_path: String,
+}
+
+impl PreparedTransferInput {
+    pub(crate) fn unpack_input(&self) -> crate::Result<Input> {
+        let input = hex::decode(&self.input).map_err(|_| anyhow::anyhow!("invalid input hex"))?;
+        let input = Input::unpack(&mut input.as_slice()).map_err(|e| anyhow::anyhow!("invalid input: {:?}", e))?;
+        Ok(input)
+    }
+}
+
+/// An unsigned transfer, created by `SyncedAccount::prepare_transfer`.
+#[derive(Debug, Clone, PartialEq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct PreparedTransfer {
+    /// The account identifier.
+    #[serde(rename = "accountId")]
+    account_id: AccountIdentifier,
+    /// The hex encoded packed transaction essence.
+    essence: String,
+    /// The essence inputs, in the essence order.
+    inputs: Vec<PreparedTransferInput>,
+    /// The change address receiving the remainder value, added to the account when the transfer is submitted.
+    #[serde(rename = "changeAddress")]
+    change_address: Option<Address>,
+    /// Whether the transfer uses the account's latest address, so a new one is generated on submission.
+    #[serde(rename = "usesLatestAddress")]
+    uses_latest_address: bool,
+}
+
+impl PreparedTransfer {
+    /// Releases the input addresses locked by the transfer when it won't be submitted.
+    pub fn cancel(&self) {
+        release_input_addresses(&self.account_id, self.inputs.iter().map(|input| &input.address));
+    }
+
+    pub(crate) fn unpack_essence(&self) -> crate::Result<TransactionEssence> {
+        let essence = hex::decode(&self.essence).map_err(|_| anyhow::anyhow!("invalid essence hex"))?;
+        let essence = TransactionEssence::unpack(&mut essence.as_slice())
+            .map_err(|e| anyhow::anyhow!("invalid essence: {:?}", e))?;
+        Ok(essence)
+    }
+}
+
+/// A prepared transfer signed with `Account::sign_prepared`.
+#[derive(Debug, Clone, PartialEq, Getters, Serialize, Deserialize)]
+pub struct SignedTransfer {
+    /// The prepared transfer.
+    #[getset(get = "pub")]
+    prepared: PreparedTransfer,
+    /// The hex encoded packed transaction.
+    transaction: String,
+}
+
+impl SignedTransfer {
+    pub(crate) fn new(prepared: PreparedTransfer, transaction: &Transaction) -> Self {
+        Self {
+            prepared,
+            transaction: hex::encode(transaction.pack_new()),
+        }
+    }
+
+    pub(crate) fn unpack_transaction(&self) -> crate::Result<Transaction> {
+        let transaction = hex::decode(&self.transaction).map_err(|_| anyhow::anyhow!("invalid transaction hex"))?;
+        let transaction = Transaction::unpack(&mut transaction.as_slice())
+            .map_err(|e| anyhow::anyhow!("invalid transaction: {:?}", e))?;
+        Ok(transaction)
+    }
+}
+
+/// The value left over by the transfer inputs and the address receiving it.
+#[derive(Debug, Clone, PartialEq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct TransferRemainder {
+    /// The address receiving the remainder value.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: IotaAddress,
+    /// The remainder value.
+    amount: u64,
+}
+
+/// An input of a transfer preview.
+#[derive(Debug, Clone, PartialEq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct TransferPreviewInput {
+    /// The spent output.
+    #[serde(rename = "outputId")]
+    output_id: OutputId,
+    /// The account address holding the output.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: IotaAddress,
+    /// The output value.
+    amount: u64,
+}
+
+/// The outcome of a transfer, computed by `SyncedAccount::preview_transfer`.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct TransferPreview {
+    /// The selected inputs.
+    inputs: Vec<TransferPreviewInput>,
+    /// The transfer outputs, without the remainder.
+    outputs: Vec<TransferOutput>,
+    /// The remainder output, if the inputs exceed the transfer value.
+    remainder: Option<TransferRemainder>,
+    /// The change address the transfer adds to the account.
+    #[serde(rename = "changeAddress")]
+    change_address: Option<Address>,
+    /// The size in bytes of the packed transaction essence.
+    #[serde(rename = "essenceSize")]
+    essence_size: usize,
+}
+
+fn release_input_addresses<'a>(account_id: &AccountIdentifier, addresses: impl Iterator<Item = &'a IotaAddress>) {
+    let account_addresses_locker = get_account_addresses_lock(account_id);
+    let mut locked_addresses = account_addresses_locker.lock().unwrap();
+    for address in addresses {
+        if let Some(index) = locked_addresses.iter().position(|a| a == address) {
+            locked_addresses.remove(index);
+        }
+    }
+}
+
+/// Checks the transfer outputs.
+fn validate_transfer(transfer_obj: &Transfer) -> crate::Result<()> {
+    if transfer_obj.outputs.is_empty() {
+        return Err(crate::WalletError::EmptyTransfer);
+    }
+    if transfer_obj.outputs.iter().any(|output| output.amount == 0) {
+        return Err(crate::WalletError::ZeroAmount);
+    }
+    if transfer_obj.outputs.len() > MAX_OUTPUT_COUNT {
+        return Err(crate::WalletError::TooManyOutputs(
+            transfer_obj.outputs.len(),
+            MAX_OUTPUT_COUNT,
+        ));
+    }
+    transfer_obj.amount()?;
+    Ok(())
+}
+
+/// Checks that the remainder value target address, if any, belongs to the account.
+fn check_remainder_value_strategy(account: &Account, transfer_obj: &Transfer) -> crate::Result<()> {
+    if let RemainderValueStrategy::AccountAddress(ref remainder_target_address) = transfer_obj.remainder_value_strategy
+    {
+        if !account
+            .addresses()
+            .iter()
+            .any(|addr| addr.address() == remainder_target_address)
+        {
+            return Err(crate::WalletError::InvalidRemainderValueAddress);
+        }
+    }
+    Ok(())
+}
+
 impl SyncedAccount {
-    /// Selects input addresses for a value transaction.
-    /// The method ensures that the recipient address doesn’t match any of the selected inputs or the remainder address.
+    /// Selects the input outputs for a value transaction among the outputs of the unlocked addresses.
+    /// The method ensures that the recipient addresses don’t match any of the selected inputs or the remainder address.
     ///
     /// # Arguments
     ///
     /// * `threshold` Amount user wants to spend.
-    /// * `address` Recipient address.
+    /// * `transfer_obj` The transfer, holding the recipient addresses, the input options and the inputs limit.
+    ///
+    /// Frozen and excluded outputs are never selected. The explicit inputs are spent as is, otherwise the coin
+    /// selection picks among the included outputs, or among every available output if nothing is included.
     ///
     /// # Return value
     ///
-    /// Returns a (addresses, address) tuple representing the selected input addresses and the remainder address if
-    /// needed.
-    fn select_inputs<'a>(
+    /// Returns a (outputs, output) tuple representing the selected outputs and the output whose address receives the
+    /// remainder if needed.
+    fn select_inputs(
         &self,
-        locked_addresses: &'a mut MutexGuard<'_, Vec<IotaAddress>>,
+        locked_addresses: &[IotaAddress],
         threshold: u64,
-        account: &'a mut Account,
-        address: &'a IotaAddress,
-    ) -> crate::Result<(Vec<input_selection::Input>, Option<input_selection::Input>)> {
-        let mut available_addresses: Vec<input_selection::Input> = account
+        account: &Account,
+        transfer_obj: &Transfer,
+    ) -> crate::Result<(Vec<SelectionInput>, Option<SelectionInput>)> {
+        let mut available_outputs: Vec<SelectionInput> = account
             .addresses()
             .iter()
-            .filter(|a| {
-                a.address() != address && a.available_balance(&account) > 0 && !locked_addresses.contains(a.address())
+            .filter(|a| !transfer_obj.sends_to(a.address()) && !locked_addresses.contains(a.address()))
+            .flat_map(|a| {
+                a.available_outputs(&account)
+                    .into_iter()
+                    .map(move |output| SelectionInput {
+                        transaction_id: *output.transaction_id(),
+                        index: *output.index(),
+                        address: a.address().clone(),
+                        balance: *output.amount(),
+                        received_at: account
+                            .get_message(output.message_id())
+                            .map(|message| *message.timestamp()),
+                    })
             })
-            .map(|a| input_selection::Input {
-                address: a.address().clone(),
-                balance: a.available_balance(&account),
+            .filter(|output| output.balance > 0)
+            .filter(|output| {
+                let output_id = output.output_id();
+                !account.is_frozen(&output.address, &output_id)
+                    && !transfer_obj
+                        .exclude
+                        .iter()
+                        .any(|source| source.contains(&output.address, &output_id))
             })
             .collect();
-        let addresses = input_selection::select_input(threshold, &mut available_addresses)?;
 
-        locked_addresses.extend(
-            addresses
-                .iter()
-                .map(|a| a.address.clone())
-                .collect::<Vec<IotaAddress>>(),
-        );
+        let max_inputs = transfer_obj.max_inputs.unwrap_or(MAX_INPUT_COUNT).min(MAX_INPUT_COUNT);
+        let outputs = if transfer_obj.inputs.is_empty() {
+            if !transfer_obj.include.is_empty() {
+                for source in &transfer_obj.include {
+                    account.check_input_source(source)?;
+                }
+                available_outputs.retain(|output| {
+                    let output_id = output.output_id();
+                    transfer_obj
+                        .include
+                        .iter()
+                        .any(|source| source.contains(&output.address, &output_id))
+                });
+            }
+            input_selection::select_input(
+                threshold,
+                max_inputs,
+                &mut available_outputs,
+                &transfer_obj.coin_selection,
+            )?
+        } else {
+            let mut outputs: Vec<SelectionInput> = Vec::new();
+            for output_id in &transfer_obj.inputs {
+                if outputs.iter().any(|output| &output.output_id() == output_id) {
+                    continue;
+                }
+                match available_outputs.iter().find(|output| &output.output_id() == output_id) {
+                    Some(output) => outputs.push(output.clone()),
+                    None => {
+                        return Err(crate::WalletError::InvalidTransferInput(format!(
+                            "{:?} isn't an available output of the account",
+                            output_id
+                        )))
+                    }
+                }
+            }
+            if outputs.iter().fold(0, |acc, output| acc + output.balance) < threshold {
+                return Err(crate::WalletError::InsufficientFunds);
+            }
+            outputs
+        };
+
+        if outputs.len() > max_inputs {
+            return Err(crate::WalletError::TooManyInputs(outputs.len(), max_inputs));
+        }
 
-        let remainder = if addresses.iter().fold(0, |acc, a| acc + a.balance) > threshold {
-            addresses.last().cloned()
+        let remainder = if outputs.iter().fold(0, |acc, a| acc + a.balance) > threshold {
+            outputs.last().cloned()
         } else {
             None
         };
 
-        Ok((addresses, remainder))
+        Ok((outputs, remainder))
     }
 
     /// Send messages.
     pub async fn transfer(&self, transfer_obj: Transfer) -> crate::Result<TransferMetadata> {
-        // validate the transfer
-        if transfer_obj.amount == 0 {
-            return Err(crate::WalletError::ZeroAmount);
+        self.perform_transfer(transfer_obj, false).await
+    }
+
+    /// Sends the transfer, skipping the account persistance if `skip_persistance` is set.
+    /// In that case, the caller is responsible for saving the returned account.
+    pub(crate) async fn perform_transfer(
+        &self,
+        transfer_obj: Transfer,
+        skip_persistance: bool,
+    ) -> crate::Result<TransferMetadata> {
+        let account = crate::storage::get_account(&self.storage_path, &self.account_id)?;
+        if account.signer_type() == &SignerType::WatchOnly {
+            return Err(crate::WalletError::WatchOnlyAccount);
         }
 
+        let prepared = self.prepare_transfer(transfer_obj).await?;
+        let account = crate::storage::get_account(&self.storage_path, &self.account_id)?;
+        let signed = match account.sign_prepared(&prepared) {
+            Ok(signed) => signed,
+            Err(e) => {
+                prepared.cancel();
+                return Err(e);
+            }
+        };
+        self.submit(signed, skip_persistance).await
+    }
+
+    /// Selects the inputs and builds the unsigned transaction essence of a transfer.
+    /// The input addresses stay locked until the transfer is submitted with `submit_signed` or cancelled with
+    /// `PreparedTransfer::cancel`, so the returned object can be signed offline with `Account::sign_prepared`.
+    pub async fn prepare_transfer(&self, transfer_obj: Transfer) -> crate::Result<PreparedTransfer> {
+        validate_transfer(&transfer_obj)?;
+
         // lock the transfer process until we select the input addresses
         // we do this to prevent multiple threads trying to transfer at the same time
         // so it doesn't consume the same addresses multiple times, which leads to a conflict state
@@ -474,9 +744,8 @@ impl SyncedAccount {
         let mut locked_addresses = account_addresses_locker.lock().unwrap();
 
         // prepare the transfer getting some needed objects and values
-        let value: u64 = transfer_obj.amount;
+        let value: u64 = transfer_obj.amount()?;
         let mut account = crate::storage::get_account(&self.storage_path, &self.account_id)?;
-        let mut addresses_to_watch = vec![];
 
         if value > account.total_balance() {
             return Err(crate::WalletError::InsufficientFunds);
@@ -515,105 +784,116 @@ impl SyncedAccount {
             }
         }
 
-        let client = crate::client::get_client(account.client_options());
-        let client = client.read().unwrap();
+        check_remainder_value_strategy(&account, &transfer_obj)?;
 
-        if let RemainderValueStrategy::AccountAddress(ref remainder_target_address) =
-            transfer_obj.remainder_value_strategy
-        {
-            if !account
-                .addresses()
-                .iter()
-                .any(|addr| addr.address() == remainder_target_address)
-            {
-                return Err(crate::WalletError::InvalidRemainderValueAddress);
+        // select the input outputs, lock their addresses and check if a remainder address is needed
+        let (input_outputs, remainder_address) =
+            self.select_inputs(&locked_addresses, value, &account, &transfer_obj)?;
+        for output in &input_outputs {
+            if !locked_addresses.contains(&output.address) {
+                locked_addresses.push(output.address.clone());
             }
         }
 
-        // select the input addresses and check if a remainder address is needed
-        let (input_addresses, remainder_address) = self.select_inputs(
-            &mut locked_addresses,
-            transfer_obj.amount,
-            &mut account,
-            &transfer_obj.address,
-        )?;
-
-        // unlock the transfer process since we already selected the input addresses and locked them
+        // unlock the transfer process since we already selected the inputs and locked their addresses
         drop(locked_addresses);
 
-        let mut utxos = vec![];
-        let mut address_index_recorders = vec![];
+        let prepared = self.build_prepared_transfer(&account, &transfer_obj, &input_outputs, remainder_address);
+        if prepared.is_err() {
+            release_input_addresses(&self.account_id, input_outputs.iter().map(|a| &a.address));
+        }
+        prepared.map(|(prepared, _)| prepared)
+    }
 
-        for input_address in &input_addresses {
-            let account_address = account
-                .addresses()
-                .iter()
-                .find(|a| a.address() == &input_address.address)
-                .unwrap();
+    /// Runs the transfer validation, the input selection and the remainder value strategy resolution without
+    /// signing, locking the inputs or posting, so the outcome of `transfer` can be confirmed beforehand.
+    /// Unlike `transfer`, it doesn't wait for pending outputs and fails if the available balance is insufficient.
+    /// The input selection isn't deterministic (`CoinSelection::BranchAndBound` falls back to a random draw), so to
+    /// spend the previewed inputs, pass their output ids to `Transfer::inputs`.
+    pub fn preview_transfer(&self, transfer_obj: Transfer) -> crate::Result<TransferPreview> {
+        validate_transfer(&transfer_obj)?;
+
+        let account = crate::storage::get_account(&self.storage_path, &self.account_id)?;
+        check_remainder_value_strategy(&account, &transfer_obj)?;
+
+        // the addresses locked by ongoing transfers can't be spent, but the preview doesn't lock anything
+        let locked_addresses = get_account_addresses_lock(&self.account_id).lock().unwrap().clone();
+        let (input_outputs, remainder_address) =
+            self.select_inputs(&locked_addresses, transfer_obj.amount()?, &account, &transfer_obj)?;
+        let (prepared, remainder) =
+            self.build_prepared_transfer(&account, &transfer_obj, &input_outputs, remainder_address)?;
+
+        let inputs = input_outputs
+            .iter()
+            .map(|output| TransferPreviewInput {
+                output_id: output.output_id(),
+                address: output.address.clone(),
+                amount: output.balance,
+            })
+            .collect();
 
-            let mut outputs = vec![];
-            let address_path = BIP32Path::from_str(&format!(
-                "m/44H/4218H/{}H/{}H/{}H",
-                *account.index(),
-                *account_address.internal() as u32,
-                *account_address.key_index()
-            ))
-            .unwrap();
+        Ok(TransferPreview {
+            inputs,
+            outputs: transfer_obj.outputs,
+            remainder,
+            change_address: prepared.change_address,
+            essence_size: prepared.unpack_essence()?.packed_len(),
+        })
+    }
 
-            for (offset, address_output) in account_address.available_outputs(&account).iter().enumerate() {
-                outputs.push((
-                    (*address_output).clone(),
-                    *account_address.key_index(),
-                    address_path.clone(),
-                ));
-            }
-            utxos.extend(outputs.into_iter());
-        }
+    fn build_prepared_transfer(
+        &self,
+        account: &Account,
+        transfer_obj: &Transfer,
+        input_outputs: &[SelectionInput],
+        remainder_address: Option<SelectionInput>,
+    ) -> crate::Result<(PreparedTransfer, Option<TransferRemainder>)> {
+        let value = transfer_obj.amount()?;
 
         let mut essence_builder = TransactionEssence::builder();
-        let mut current_output_sum = 0;
-        let mut remainder_value = 0;
-        for (utxo, address_index, address_path) in utxos {
-            let input: Input = UTXOInput::new(*utxo.transaction_id(), *utxo.index())
+        let mut inputs = vec![];
+        let mut input_value = 0;
+        for utxo in input_outputs {
+            let account_address = account
+                .addresses()
+                .iter()
+                .find(|a| a.address() == &utxo.address)
+                .unwrap();
+            let input: Input = UTXOInput::new(utxo.transaction_id, utxo.index)
                 .map_err(|e| anyhow::anyhow!(e.to_string()))?
                 .into();
-            essence_builder = essence_builder.add_input(input.clone());
-            address_index_recorders.push(crate::signing::TransactionInput {
-                input,
-                address_index,
-                address_path,
+            inputs.push(PreparedTransferInput {
+                input: hex::encode(input.pack_new()),
+                address: account_address.address().clone(),
+                address_index: *account_address.key_index(),
+                address_path: account.derivation_path(*account_address.key_index(), *account_address.internal()),
             });
-            if current_output_sum == value {
-                // already filled the transfer value; just collect the output value as remainder
-                remainder_value += *utxo.amount();
-            } else if current_output_sum + *utxo.amount() > value {
-                // if the used UTXO amount is greater than the transfer value, this is the last iteration and we'll have
-                // remainder value. we add an Output for the missing value and collect the remainder
-                let missing_value = value - current_output_sum;
-                remainder_value += *utxo.amount() - missing_value;
-                essence_builder = essence_builder.add_output(
-                    SignatureLockedSingleOutput::new(
-                        transfer_obj.address.clone(),
-                        NonZeroU64::new(missing_value).ok_or_else(|| anyhow::anyhow!("invalid amount"))?,
-                    )
-                    .into(),
-                );
-                current_output_sum += missing_value;
-            } else {
-                essence_builder = essence_builder.add_output(
-                    SignatureLockedSingleOutput::new(
-                        transfer_obj.address.clone(),
-                        NonZeroU64::new(*utxo.amount()).ok_or_else(|| anyhow::anyhow!("invalid amount"))?,
-                    )
-                    .into(),
-                );
-                current_output_sum += *utxo.amount();
-            }
+            essence_builder = essence_builder.add_input(input);
+            input_value += utxo.balance;
         }
+        for output in &transfer_obj.outputs {
+            essence_builder = essence_builder.add_output(
+                SignatureLockedSingleOutput::new(
+                    output.address.clone(),
+                    NonZeroU64::new(output.amount).ok_or_else(|| anyhow::anyhow!("invalid amount"))?,
+                )
+                .into(),
+            );
+        }
+        let remainder_value = input_value
+            .checked_sub(value)
+            .ok_or(crate::WalletError::InsufficientFunds)?;
 
         // if there's remainder value, we check the strategy defined in the transfer
-        let mut remainder_value_deposit_address = None;
+        let mut remainder = None;
+        let mut change_address = None;
         if remainder_value > 0 {
+            if transfer_obj.outputs.len() + 1 > MAX_OUTPUT_COUNT {
+                return Err(crate::WalletError::TooManyOutputs(
+                    transfer_obj.outputs.len() + 1,
+                    MAX_OUTPUT_COUNT,
+                ));
+            }
             let remainder_address =
                 remainder_address.ok_or_else(|| anyhow::anyhow!("remainder address not defined"))?;
             let remainder_address = account
@@ -622,26 +902,30 @@ impl SyncedAccount {
                 .find(|a| a.address() == &remainder_address.address)
                 .unwrap();
 
-            let remainder_target_address = match transfer_obj.remainder_value_strategy {
+            let remainder_target_address = match &transfer_obj.remainder_value_strategy {
                 // use one of the account's addresses to send the remainder value
-                RemainderValueStrategy::AccountAddress(target_address) => target_address,
+                RemainderValueStrategy::AccountAddress(target_address) => target_address.clone(),
                 // generate a new change address to send the remainder value
                 RemainderValueStrategy::ChangeAddress => {
                     if *remainder_address.internal() {
-                        let deposit_address = account.latest_address().unwrap().address().clone();
-                        deposit_address
+                        account.latest_address().unwrap().address().clone()
+                    } else if account.signer_type() == &SignerType::WatchOnly {
+                        // a watch-only account can't derive new addresses, so the remainder stays on the input address
+                        remainder_address.address().clone()
                     } else {
-                        let change_address = crate::address::get_new_change_address(&account, &remainder_address)?;
-                        let addr = change_address.address().clone();
-                        account.append_addresses(vec![change_address]);
-                        addresses_to_watch.push(addr.clone());
-                        addr
+                        let address = crate::address::get_new_change_address(account, &remainder_address)?;
+                        let iota_address = address.address().clone();
+                        change_address = Some(address);
+                        iota_address
                     }
                 }
                 // keep the remainder value on the address
                 RemainderValueStrategy::ReuseAddress => remainder_address.address().clone(),
             };
-            remainder_value_deposit_address = Some(remainder_target_address.clone());
+            remainder = Some(TransferRemainder {
+                address: remainder_target_address.clone(),
+                amount: remainder_value,
+            });
             essence_builder = essence_builder.add_output(
                 SignatureLockedSingleOutput::new(
                     remainder_target_address,
@@ -651,20 +935,64 @@ impl SyncedAccount {
             );
         }
 
-        let (parent1, parent2) = client.get_tips().await?;
-
         let essence = essence_builder
             .finish()
             .map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
 
-        let unlock_blocks = crate::signing::with_signer(account.signer_type(), |signer| {
-            signer.sign_message(&account, &essence, &mut address_index_recorders)
-        })?;
-        let mut tx_builder = Transaction::builder().with_essence(essence);
-        for unlock_block in unlock_blocks {
-            tx_builder = tx_builder.add_unlock_block(unlock_block);
+        // if this is a transfer to the account's latest address or we use the latest as deposit of the remainder
+        // value, a new one must be generated on submission to keep the latest address unused
+        let latest_address = account.latest_address().unwrap().address();
+        let uses_latest_address =
+            transfer_obj.sends_to(latest_address) || remainder.as_ref().map(|r| &r.address) == Some(latest_address);
+
+        let prepared = PreparedTransfer {
+            account_id: self.account_id.clone(),
+            essence: hex::encode(essence.pack_new()),
+            inputs,
+            change_address,
+            uses_latest_address,
+        };
+        Ok((prepared, remainder))
+    }
+
+    /// Attaches a transfer signed with `Account::sign_prepared` to the tangle.
+    pub async fn submit_signed(&self, signed: SignedTransfer) -> crate::Result<TransferMetadata> {
+        self.submit(signed, false).await
+    }
+
+    async fn submit(&self, signed: SignedTransfer, skip_persistance: bool) -> crate::Result<TransferMetadata> {
+        if signed.prepared.account_id != self.account_id {
+            return Err(anyhow::anyhow!("the signed transfer belongs to another account").into());
         }
-        let transaction = tx_builder.finish().map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+
+        let result = self.post_signed(&signed, skip_persistance).await;
+
+        release_input_addresses(
+            &self.account_id,
+            signed.prepared.inputs.iter().map(|input| &input.address),
+        );
+
+        let (account, message) = result?;
+
+        // ignore errors because we fallback to the polling system
+        let _ = crate::monitor::monitor_confirmation_state_change(&account, message.id());
+
+        Ok(TransferMetadata { message, account })
+    }
+
+    async fn post_signed(&self, signed: &SignedTransfer, skip_persistance: bool) -> crate::Result<(Account, Message)> {
+        let transaction = signed.unpack_transaction()?;
+        if transaction.essence().pack_new() != signed.prepared.unpack_essence()?.pack_new() {
+            return Err(anyhow::anyhow!("the signed transaction doesn't match the prepared essence").into());
+        }
+
+        let mut account = crate::storage::get_account(&self.storage_path, &self.account_id)?;
+        let mut addresses_to_watch = vec![];
+
+        let client = crate::client::get_client(account.storage_path(), account.client_options());
+        let client = client.read().unwrap();
+
+        let (parent1, parent2) = client.get_tips().await?;
 
         let message = MessageBuilder::<ClientMiner>::new()
             .with_parent1(parent1)
@@ -677,13 +1005,12 @@ impl SyncedAccount {
 
         let message_id = client.post_message(&message).await?;
 
-        // if this is a transfer to the account's latest address or we used the latest as deposit of the remainder
-        // value, we generate a new one to keep the latest address unused
-        let latest_address = account.latest_address().unwrap().address();
-        if latest_address == &transfer_obj.address
-            || (remainder_value_deposit_address.is_some()
-                && &remainder_value_deposit_address.unwrap() == latest_address)
-        {
+        if let Some(change_address) = &signed.prepared.change_address {
+            addresses_to_watch.push(change_address.address().clone());
+            account.append_addresses(vec![change_address.clone()]);
+        }
+
+        if signed.prepared.uses_latest_address && account.signer_type() != &SignerType::WatchOnly {
             let addr = crate::address::get_new_address(&account)?;
             addresses_to_watch.push(addr.address().clone());
             account.append_addresses(vec![addr]);
@@ -702,22 +1029,11 @@ impl SyncedAccount {
         let message = Message::from_iota_message(message_id, account.addresses(), &message, None)?;
         account.append_messages(vec![message.clone()]);
 
-        account.save()?;
-
-        let account_addresses_locker = get_account_addresses_lock(&self.account_id);
-        let mut locked_addresses = account_addresses_locker.lock().unwrap();
-        for input_address in &input_addresses {
-            let index = locked_addresses
-                .iter()
-                .position(|a| a == &input_address.address)
-                .unwrap();
-            locked_addresses.remove(index);
+        if !skip_persistance {
+            account.save()?;
         }
 
-        // ignore errors because we fallback to the polling system
-        let _ = crate::monitor::monitor_confirmation_state_change(&account, &message_id);
-
-        Ok(TransferMetadata { message, account })
+        Ok((account, message))
     }
 
     /// Retry message.
@@ -763,7 +1079,7 @@ pub(crate) async fn repost_message(
                 ));
             }
 
-            let client = crate::client::get_client(account.client_options());
+            let client = crate::client::get_client(account.storage_path(), account.client_options());
             let client = client.read().unwrap();
 
             let (id, message) = match action {
@@ -805,25 +1121,456 @@ pub(crate) async fn repost_message(
 
 #[cfg(test)]
 mod tests {
-    use crate::client::ClientOptionsBuilder;
+    use super::{CoinSelection, PreparedTransfer, SignedTransfer};
+    use crate::{
+        account::{get_account_addresses_lock, Account},
+        address::{InputSource, IotaAddress},
+        message::{Transfer, TransferOutput, MAX_OUTPUT_COUNT},
+        mock_node::{InclusionState, MockLedger, MockNode},
+        signing::{WatchOnlyKey, WatchOnlyKeys, WatchedKey},
+        test_utils::{get_mock_account_manager, mock_signer_type},
+    };
+    use iota::message::prelude::{Ed25519Address, MessageId};
     use rusty_fork::rusty_fork_test;
 
+    /// Creates an account and funds its first address on the mock ledger.
+    fn create_funded_account(
+        manager: &crate::account_manager::AccountManager,
+        node: &MockNode,
+        amount: u64,
+    ) -> (Account, MessageId) {
+        let account = manager
+            .create_account(node.client_options())
+            .signer_type(mock_signer_type())
+            .alias("alias")
+            .initialise()
+            .unwrap();
+        let address = crate::address::get_iota_address(&account, 0, false).unwrap();
+        let message_id = node.with_ledger(|ledger| ledger.fund(&address, amount));
+        (account, message_id)
+    }
+
+    /// The watch-only keys of the first account address.
+    fn watch_only_keys(account: &Account) -> WatchOnlyKeys {
+        WatchOnlyKeys {
+            account_index: *account.index(),
+            keys: vec![WatchOnlyKey {
+                key_index: 0,
+                internal: false,
+                key: WatchedKey::Address(account.addresses()[0].address().to_bech32()),
+            }],
+        }
+    }
+
     rusty_fork_test! {
         #[test]
         fn account_sync() {
             crate::block_on(async move {
-                let manager = crate::test_utils::get_account_manager();
-                let client_options = ClientOptionsBuilder::node("https://nodes.devnet.iota.org:443")
-                    .unwrap()
-                    .build();
-                let account = manager
-                    .create_account(client_options)
-                    .alias("alias")
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (mut account, message_id) = create_funded_account(&manager, &node, 1_000_000);
+
+                let synced_account = account.sync().execute().await.unwrap();
+
+                assert_eq!(account.total_balance(), 1_000_000);
+                assert_eq!(account.get_message(&message_id).unwrap().confirmed(), &Some(true));
+                // the synced messages are persisted and paged through the storage adapter
+                assert_eq!(account.load_messages(0, 0).unwrap().len(), account.messages().len());
+                assert_eq!(account.load_messages(1, 1).unwrap().len(), account.messages().len().saturating_sub(1).min(1));
+                assert!(account.load_message(&message_id).unwrap().is_some());
+                // the deposit address must be unused
+                assert_eq!(*synced_account.deposit_address().key_index(), 1);
+                assert_eq!(*synced_account.deposit_address().balance(), 0);
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn account_transfer() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (mut account, _) = create_funded_account(&manager, &node, 1_000_000);
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+
+                let synced_account = account.sync().execute().await.unwrap();
+                let transfer = synced_account
+                    .transfer(Transfer::new(recipient.clone(), 400_000))
+                    .await
+                    .unwrap();
+
+                node.with_ledger(|ledger| {
+                    assert_eq!(ledger.balance(&recipient), 400_000);
+                    assert!(ledger.messages().contains(transfer.message.id()));
+                });
+
+                // sync from the first address to find the change address
+                let mut account = manager.get_account(account.id()).unwrap();
+                account.sync().address_index(0).execute().await.unwrap();
+                assert_eq!(account.total_balance(), 600_000);
+                assert_eq!(account.get_message(transfer.message.id()).unwrap().confirmed(), &Some(true));
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn batch_transfer() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (mut account, _) = create_funded_account(&manager, &node, 1_000_000);
+                let recipients: Vec<IotaAddress> = (1..=3)
+                    .map(|i| IotaAddress::Ed25519(Ed25519Address::new([i; 32])))
+                    .collect();
+
+                let synced_account = account.sync().execute().await.unwrap();
+                let outputs = recipients
+                    .iter()
+                    .map(|recipient| TransferOutput::new(recipient.clone(), 100_000))
+                    .collect();
+                let transfer = synced_account.transfer(Transfer::batch(outputs)).await.unwrap();
+
+                node.with_ledger(|ledger| {
+                    for recipient in &recipients {
+                        assert_eq!(ledger.balance(recipient), 100_000);
+                    }
+                    assert!(ledger.messages().contains(transfer.message.id()));
+                });
+
+                let mut account = manager.get_account(account.id()).unwrap();
+                account.sync().address_index(0).execute().await.unwrap();
+                assert_eq!(account.total_balance(), 700_000);
+
+                let outputs = (0..MAX_OUTPUT_COUNT + 1)
+                    .map(|_| TransferOutput::new(recipients[0].clone(), 1))
+                    .collect();
+                let synced_account = account.sync().execute().await.unwrap();
+                assert!(matches!(
+                    synced_account.transfer(Transfer::batch(outputs)).await,
+                    Err(crate::WalletError::TooManyOutputs(_, MAX_OUTPUT_COUNT))
+                ));
+
+                let outputs = vec![
+                    TransferOutput::new(recipients[0].clone(), u64::MAX),
+                    TransferOutput::new(recipients[1].clone(), 1),
+                ];
+                assert!(matches!(
+                    synced_account.transfer(Transfer::batch(outputs)).await,
+                    Err(crate::WalletError::AmountOverflow)
+                ));
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn transfer_inputs_limit() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (mut account, _) = create_funded_account(&manager, &node, 300_000);
+                let address = crate::address::get_iota_address(&account, 0, false).unwrap();
+                node.with_ledger(|ledger| ledger.fund(&address, 300_000));
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+
+                let synced_account = account.sync().execute().await.unwrap();
+                let transfer = Transfer::new(recipient.clone(), 400_000)
+                    .coin_selection(CoinSelection::LargestFirst)
+                    .max_inputs(1);
+                assert!(matches!(
+                    synced_account.transfer(transfer).await,
+                    Err(crate::WalletError::TooManyInputs(2, 1))
+                ));
+
+                let transfer = Transfer::new(recipient.clone(), 400_000).coin_selection(CoinSelection::SmallestFirst);
+                synced_account.transfer(transfer).await.unwrap();
+                node.with_ledger(|ledger| assert_eq!(ledger.balance(&recipient), 400_000));
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn utxo_selection() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (mut account, _) = create_funded_account(&manager, &node, 100_000);
+                let address = crate::address::get_iota_address(&account, 0, false).unwrap();
+                node.with_ledger(|ledger| {
+                    ledger.fund(&address, 300_000);
+                    ledger.fund(&address, 50_000);
+                });
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+
+                let synced_account = account.sync().execute().await.unwrap();
+                let transfer = Transfer::new(recipient.clone(), 250_000).coin_selection(CoinSelection::LargestFirst);
+                let prepared = synced_account.prepare_transfer(transfer).await.unwrap();
+                // only the largest output is spent, the other outputs of the address stay available
+                assert_eq!(prepared.inputs().len(), 1);
+                assert!(get_account_addresses_lock(account.id()).lock().unwrap().contains(&address));
+                prepared.cancel();
+                assert!(!get_account_addresses_lock(account.id()).lock().unwrap().contains(&address));
+
+                let transfer = Transfer::new(recipient.clone(), 250_000).coin_selection(CoinSelection::LargestFirst);
+                synced_account.transfer(transfer).await.unwrap();
+                node.with_ledger(|ledger| {
+                    assert_eq!(ledger.balance(&recipient), 250_000);
+                    assert_eq!(ledger.balance(&address), 150_000);
+                });
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn coin_control() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (mut account, _) = create_funded_account(&manager, &node, 100_000);
+                let address = crate::address::get_iota_address(&account, 0, false).unwrap();
+                node.with_ledger(|ledger| {
+                    ledger.fund(&address, 300_000);
+                    ledger.fund(&address, 50_000);
+                });
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+
+                let synced_account = account.sync().execute().await.unwrap();
+                let output_id = |amount: u64| {
+                    account
+                        .addresses()
+                        .iter()
+                        .flat_map(|a| a.outputs())
+                        .find(|output| *output.amount() == amount)
+                        .unwrap()
+                        .id()
+                };
+                let (small, medium, large) = (output_id(50_000), output_id(100_000), output_id(300_000));
+
+                // the frozen output is never spent
+                account.freeze(InputSource::Output(large.clone())).unwrap();
+                let transfer = Transfer::new(recipient.clone(), 250_000);
+                assert!(matches!(
+                    synced_account.transfer(transfer).await,
+                    Err(crate::WalletError::InsufficientFunds)
+                ));
+                let transfer = Transfer::new(recipient.clone(), 250_000).inputs(vec![large.clone()]);
+                assert!(matches!(
+                    synced_account.transfer(transfer).await,
+                    Err(crate::WalletError::InvalidTransferInput(_))
+                ));
+
+                // the explicit inputs are spent as is
+                let transfer = Transfer::new(recipient.clone(), 10_000).inputs(vec![small.clone(), medium.clone()]);
+                let prepared = synced_account.prepare_transfer(transfer).await.unwrap();
+                assert_eq!(prepared.inputs().len(), 2);
+                prepared.cancel();
+
+                // the excluded output is skipped
+                let transfer = Transfer::new(recipient.clone(), 10_000)
+                    .coin_selection(CoinSelection::SmallestFirst)
+                    .exclude(InputSource::Output(small));
+                let prepared = synced_account.prepare_transfer(transfer).await.unwrap();
+                assert_eq!(prepared.inputs().len(), 1);
+                prepared.cancel();
+
+                account.unfreeze(&InputSource::Output(large.clone())).unwrap();
+                let transfer = Transfer::new(recipient.clone(), 250_000).include(InputSource::Output(large));
+                synced_account.transfer(transfer).await.unwrap();
+                node.with_ledger(|ledger| {
+                    assert_eq!(ledger.balance(&recipient), 250_000);
+                    assert_eq!(ledger.balance(&address), 150_000);
+                });
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn transfer_preview() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (mut account, _) = create_funded_account(&manager, &node, 1_000_000);
+                let address = crate::address::get_iota_address(&account, 0, false).unwrap();
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+
+                let synced_account = account.sync().execute().await.unwrap();
+                let preview = synced_account
+                    .preview_transfer(Transfer::new(recipient.clone(), 400_000))
+                    .unwrap();
+
+                assert_eq!(preview.inputs().len(), 1);
+                assert_eq!(preview.inputs()[0].address(), &address);
+                assert_eq!(*preview.inputs()[0].amount(), 1_000_000);
+                assert_eq!(preview.outputs()[0].address(), &recipient);
+                let change_address = preview.change_address().as_ref().unwrap();
+                assert!(*change_address.internal());
+                let remainder = preview.remainder().as_ref().unwrap();
+                assert_eq!(remainder.address(), change_address.address());
+                assert_eq!(*remainder.amount(), 600_000);
+                assert!(*preview.essence_size() > 0);
+
+                // nothing is locked or sent
+                assert!(!get_account_addresses_lock(account.id()).lock().unwrap().contains(&address));
+                node.with_ledger(|ledger| assert_eq!(ledger.balance(&recipient), 0));
+
+                let json = serde_json::to_value(&preview).unwrap();
+                assert_eq!(json["remainder"]["amount"], 600_000);
+                assert!(json["essenceSize"].is_number());
+
+                // the previewed inputs are spent by passing them back
+                let inputs = preview.inputs().iter().map(|input| input.output_id().clone()).collect();
+                synced_account
+                    .transfer(Transfer::new(recipient.clone(), 400_000).inputs(inputs))
+                    .await
+                    .unwrap();
+                node.with_ledger(|ledger| assert_eq!(ledger.balance(&recipient), 400_000));
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn prepared_transfer() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (account, _) = create_funded_account(&manager, &node, 1_000_000);
+                let address = crate::address::get_iota_address(&account, 0, false).unwrap();
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+
+                // the online machine only knows the account addresses
+                let mut watch_only_account = manager
+                    .create_account(node.client_options())
+                    .watch_only(watch_only_keys(&account))
+                    .initialise()
+                    .unwrap();
+                // the watch-only account takes the next wallet index, but derives the paths of the watched account
+                assert_eq!(*watch_only_account.index(), 1);
+                assert_eq!(manager.get_accounts().unwrap().len(), 2);
+                let synced_account = watch_only_account.sync().execute().await.unwrap();
+                let prepared = synced_account
+                    .prepare_transfer(Transfer::new(recipient.clone(), 400_000))
+                    .await
+                    .unwrap();
+                let prepared: PreparedTransfer =
+                    serde_json::from_str(&serde_json::to_string(&prepared).unwrap()).unwrap();
+                assert_eq!(prepared.inputs().len(), 1);
+                assert_eq!(prepared.inputs()[0].address(), &address);
+                assert_eq!(prepared.inputs()[0].address_path(), &account.derivation_path(0, false));
+
+                // the input address stays locked between the steps
+                let locked_addresses = get_account_addresses_lock(watch_only_account.id());
+                assert!(locked_addresses.lock().unwrap().contains(&address));
+
+                assert!(matches!(
+                    watch_only_account.sign_prepared(&prepared),
+                    Err(crate::WalletError::WatchOnlyAccount)
+                ));
+                let signed = account.sign_prepared(&prepared).unwrap();
+                let signed: SignedTransfer = serde_json::from_str(&serde_json::to_string(&signed).unwrap()).unwrap();
+
+                let transfer = synced_account.submit_signed(signed).await.unwrap();
+                assert!(!locked_addresses.lock().unwrap().contains(&address));
+                node.with_ledger(|ledger| {
+                    assert_eq!(ledger.balance(&recipient), 400_000);
+                    assert_eq!(ledger.balance(&address), 600_000);
+                    assert!(ledger.messages().contains(transfer.message.id()));
+                });
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn prepared_transfer_paths() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (mut first_account, _) = create_funded_account(&manager, &node, 1_000_000);
+                first_account.sync().execute().await.unwrap();
+                let (account, _) = create_funded_account(&manager, &node, 1_000_000);
+                assert_eq!(*account.index(), 1);
+                let address = account.addresses()[0].address().clone();
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+
+                let mut watch_only_account = manager
+                    .create_account(node.client_options())
+                    .watch_only(watch_only_keys(&account))
+                    .initialise()
+                    .unwrap();
+                let synced_account = watch_only_account.sync().execute().await.unwrap();
+                let prepared = synced_account
+                    .prepare_transfer(Transfer::new(recipient.clone(), 400_000))
+                    .await
+                    .unwrap();
+                assert_eq!(prepared.inputs()[0].address_path(), &account.derivation_path(0, false));
+
+                // the signing account derives the input paths from its own addresses
+                let mut tampered = serde_json::to_value(&prepared).unwrap();
+                tampered["inputs"][0]["addressPath"] = serde_json::Value::from(first_account.derivation_path(0, false));
+                let tampered: PreparedTransfer = serde_json::from_value(tampered).unwrap();
+                let signed = account.sign_prepared(&tampered).unwrap();
+
+                synced_account.submit_signed(signed).await.unwrap();
+                node.with_ledger(|ledger| {
+                    assert_eq!(ledger.balance(&recipient), 400_000);
+                    assert_eq!(ledger.balance(&address), 600_000);
+                });
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn watch_only_account() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (account, _) = create_funded_account(&manager, &node, 1_000_000);
+                let mut watch_only_account = manager
+                    .create_account(node.client_options())
+                    .watch_only(watch_only_keys(&account))
                     .initialise()
                     .unwrap();
+                let synced_account = watch_only_account.sync().execute().await.unwrap();
+                assert_eq!(watch_only_account.total_balance(), 1_000_000);
+                assert_eq!(watch_only_account.messages().len(), 1);
+
+                assert!(matches!(
+                    synced_account
+                        .transfer(Transfer::new(IotaAddress::Ed25519(Ed25519Address::new([1; 32])), 400_000))
+                        .await,
+                    Err(crate::WalletError::WatchOnlyAccount)
+                ));
+                assert!(matches!(
+                    watch_only_account.generate_address(),
+                    Err(crate::WalletError::WatchOnlyAccount)
+                ));
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn account_retry() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let (mut account, _) = create_funded_account(&manager, &node, 1_000_000);
+                node.with_ledger(|ledger| ledger.set_posted_inclusion_state(InclusionState::Pending));
+
+                let synced_account = account.sync().execute().await.unwrap();
+                let transfer = synced_account
+                    .transfer(Transfer::new(IotaAddress::Ed25519(Ed25519Address::new([1; 32])), 400_000))
+                    .await
+                    .unwrap();
 
-                // let synced_accounts = account.sync().execute().await.unwrap();
-                // TODO improve test when the node API is ready to use
+                // the pending message should be reattached
+                let reattachment = synced_account.retry(transfer.message.id()).await.unwrap();
+                assert_ne!(reattachment.id(), transfer.message.id());
+                assert_eq!(reattachment.payload(), transfer.message.payload());
+                node.with_ledger(|ledger| assert!(ledger.messages().contains(reattachment.id())));
+
+                // confirmed messages don't need to be retried
+                node.with_ledger(|ledger| ledger.set_inclusion_state(reattachment.id(), InclusionState::Included))
+                    .unwrap();
+                let mut account = manager.get_account(account.id()).unwrap();
+                account.sync().execute().await.unwrap();
+                assert!(synced_account.retry(reattachment.id()).await.is_err());
             });
         }
     }
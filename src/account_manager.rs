@@ -4,13 +4,17 @@
 use crate::{
     account::{
         account_id_to_stronghold_record_id, repost_message, Account, AccountIdentifier, AccountInitialiser,
-        RepostAction, SyncedAccount,
+        DerivationScheme, IntegrityCheck, IntegrityReport, RepostAction, SyncedAccount,
+    },
+    backup::{
+        AccountRestore, AccountRestoreStatus, BackupArchive, DuplicateAccountStrategy, RestoreOptions, RestoreReport,
     },
     client::ClientOptions,
+    context::WalletContext,
     event::{emit_balance_change, emit_confirmation_state_change, emit_transaction_event, TransactionEventType},
     message::{Message, MessageType, Transfer},
     signing::SignerType,
-    storage::StorageAdapter,
+    storage::{migration::MigrationReport, StorageAdapter},
 };
 
 use std::{
@@ -18,6 +22,7 @@ use std::{
     fs,
     panic::AssertUnwindSafe,
     path::{Path, PathBuf},
+    sync::Arc,
     thread,
     time::Duration,
 };
@@ -25,6 +30,7 @@ use std::{
 use futures::FutureExt;
 use getset::{Getters, Setters};
 use iota::message::prelude::MessageId;
+use rand::{thread_rng, Rng};
 use stronghold::Stronghold;
 
 /// The default storage path.
@@ -42,6 +48,9 @@ pub struct AccountManager {
     #[getset(get = "pub", set = "pub")]
     polling_interval: Duration,
     started_monitoring: bool,
+    /// the wallet instance resources (storage adapter, signers, clients and event listeners).
+    #[getset(get = "pub")]
+    context: Arc<WalletContext>,
 }
 
 /// Internal transfer response metadata.
@@ -63,26 +72,42 @@ impl AccountManager {
     /// Initialises a new instance of the account manager with the default storage adapter using the specified storage
     /// path.
     pub fn with_storage_path(storage_path: impl AsRef<Path>) -> crate::Result<Self> {
-        Self::with_storage_adapter(&storage_path, crate::storage::get_adapter_from_path(&storage_path)?)
+        let adapter = crate::storage::get_adapter_from_path(&storage_path)?;
+        Self::with_storage_adapter(&storage_path, adapter)
     }
 
     /// Initialises a new instance of the account manager with the specified adapter.
+    /// The stored accounts are migrated to the latest storage version. Locked storages (e.g. the stronghold storage or
+    /// an encrypted database) can only be read after the password is set, so their migration runs then.
     pub fn with_storage_adapter<S: StorageAdapter + Sync + Send + 'static>(
         storage_path: impl AsRef<Path>,
         adapter: S,
     ) -> crate::Result<Self> {
-        crate::storage::set_adapter(&storage_path, adapter);
+        let context = crate::context::register(storage_path.as_ref());
+        context.set_storage(adapter);
         let instance = Self {
             storage_path: storage_path.as_ref().to_path_buf(),
             polling_interval: Duration::from_millis(30_000),
             started_monitoring: false,
+            context,
         };
+        instance.load_stored_accounts()?;
         Ok(instance)
     }
 
+    /// Migrates the stored accounts if the storage isn't locked.
+    /// Their integrity is checked before their first signature, once their signers are registered.
+    fn load_stored_accounts(&self) -> crate::Result<()> {
+        let is_locked = self.context.with_storage(|storage| storage.is_locked())?;
+        if !is_locked {
+            self.migrate_storage(false)?;
+        }
+        Ok(())
+    }
+
     /// Starts monitoring the accounts with the node's mqtt topics.
     fn start_monitoring(&self) -> crate::Result<()> {
-        let accounts = crate::storage::with_adapter(&self.storage_path, |storage| storage.get_all())?;
+        let accounts = self.context.with_storage(|storage| storage.get_all())?;
         let accounts = crate::storage::parse_accounts(&self.storage_path, &accounts)?;
         for account in accounts {
             crate::monitor::monitor_account_addresses_balance(&account)?;
@@ -102,6 +127,14 @@ impl AccountManager {
 
     /// Sets the stronghold password.
     pub fn set_stronghold_password<P: AsRef<str>>(&mut self, password: P) -> crate::Result<()> {
+        self.unlock_stronghold(password)?;
+        self.load_stored_accounts()?;
+        self.start_background_sync();
+        Ok(())
+    }
+
+    /// Loads the stronghold snapshot with the password, unlocking it if it was locked.
+    pub fn unlock_stronghold<P: AsRef<str>>(&self, password: P) -> crate::Result<()> {
         let stronghold_path = self.storage_path.join(crate::storage::stronghold_snapshot_filename());
         let stronghold = Stronghold::new(
             &stronghold_path,
@@ -109,17 +142,119 @@ impl AccountManager {
             password.as_ref().to_string(),
             None,
         )?;
-        crate::init_stronghold(&self.storage_path, stronghold);
-        self.start_background_sync();
+        crate::init_stronghold(&self.storage_path, stronghold, password.as_ref().to_string());
         Ok(())
     }
 
-    /// Starts the polling mechanism.
+    /// Locks the stronghold, evicting the decrypted snapshot.
+    /// Signing, address generation and stronghold storage calls fail with `WalletError::StrongholdLocked` until
+    /// `unlock_stronghold` is called.
+    pub fn lock_stronghold(&self) {
+        self.context.lock_stronghold();
+    }
+
+    /// Whether the stronghold is locked.
+    pub fn is_stronghold_locked(&self) -> bool {
+        self.context.is_stronghold_locked()
+    }
+
+    /// Sets the inactivity period after which the stronghold is locked.
+    pub fn set_stronghold_lock_timeout(&self, timeout: Duration) {
+        self.context.set_stronghold_lock_timeout(timeout);
+    }
+
+    /// Changes the stronghold password, re-encrypting the snapshot.
+    ///
+    /// The snapshot is rewritten with the new password and replaces the current one once all of its accounts and
+    /// records are copied; on failure the current snapshot is kept.
+    pub fn change_stronghold_password<P: AsRef<str>>(&self, current_password: P, new_password: P) -> crate::Result<()> {
+        let is_current_password = self
+            .context
+            .with_stronghold_and_password(|_, password| crate::Result::Ok(password == current_password.as_ref()))?;
+        if !is_current_password {
+            return Err(crate::WalletError::InvalidStrongholdPassword);
+        }
+
+        let stronghold_path = self.storage_path.join(crate::storage::stronghold_snapshot_filename());
+        let new_stronghold_path = stronghold_path.with_extension("new");
+        if new_stronghold_path.exists() {
+            fs::remove_file(&new_stronghold_path)?;
+        }
+
+        let accounts = self.context.with_storage(|storage| storage.get_all())?;
+        let mut accounts = crate::storage::parse_accounts(&self.storage_path, &accounts)?;
+
+        let new_stronghold = Stronghold::new(&new_stronghold_path, true, new_password.as_ref().to_string(), None)?;
+        let copy_result = copy_stronghold_accounts(&self.storage_path, &new_stronghold, &accounts).and_then(|_| {
+            // the storage adapter writes to the loaded snapshot, so the new one must be loaded to copy the records
+            crate::init_stronghold(&self.storage_path, new_stronghold, new_password.as_ref().to_string());
+            crate::storage::save_accounts(&self.storage_path, accounts.iter_mut().collect())
+        });
+
+        if let Err(e) = copy_result {
+            let _ = fs::remove_file(&new_stronghold_path);
+            let stronghold = Stronghold::new(&stronghold_path, false, current_password.as_ref().to_string(), None)?;
+            crate::init_stronghold(&self.storage_path, stronghold, current_password.as_ref().to_string());
+            return Err(e);
+        }
+
+        crate::remove_stronghold(self.storage_path.clone());
+        fs::rename(&new_stronghold_path, &stronghold_path)?;
+        let stronghold = Stronghold::new(&stronghold_path, false, new_password.as_ref().to_string(), None)?;
+        crate::init_stronghold(&self.storage_path, stronghold, new_password.as_ref().to_string());
+        Ok(())
+    }
+
+    /// Sets the password used to encrypt the stored accounts.
+    /// If the storage is already encrypted, the password unlocks it; otherwise the stored accounts are encrypted.
+    pub fn set_storage_password<P: AsRef<str>>(&self, password: P) -> crate::Result<()> {
+        self.context
+            .with_storage(|storage| storage.set_password(password.as_ref()))?;
+        self.load_stored_accounts()
+    }
+
+    /// Changes the storage password, re-encrypting the stored accounts.
+    pub fn change_storage_password<P: AsRef<str>>(&self, current_password: P, new_password: P) -> crate::Result<()> {
+        self.context
+            .with_storage(|storage| storage.change_password(current_password.as_ref(), new_password.as_ref()))
+    }
+
+    /// Migrates the stored accounts to the latest storage version.
+    /// If `dry_run` is true, the storage isn't changed and the report lists the steps that would be applied.
+    pub fn migrate_storage(&self, dry_run: bool) -> crate::Result<MigrationReport> {
+        crate::storage::migration::migrate(&self.storage_path, dry_run)
+    }
+
+    /// Re-derives the stored addresses of every account with its signer.
+    /// The accounts with mismatched addresses refuse to sign until a later check passes. The accounts whose signer
+    /// failed (e.g. it isn't registered or the Stronghold is locked) are listed with the signer error.
+    pub fn verify_accounts_integrity(
+        &self,
+        check: IntegrityCheck,
+    ) -> crate::Result<Vec<(AccountIdentifier, crate::Result<IntegrityReport>)>> {
+        Ok(self
+            .get_accounts()?
+            .iter()
+            .map(|account| (account.id().clone(), account.verify_integrity(check)))
+            .collect())
+    }
+
+    /// Starts the polling mechanism, until the wallet instance is released.
     fn start_polling(&self, is_monitoring_disabled: bool) -> thread::JoinHandle<()> {
         let storage_path = self.storage_path.clone();
+        let context = Arc::downgrade(&self.context);
         let interval = self.polling_interval;
         thread::spawn(move || {
             loop {
+                // the accounts can't be synced while the stronghold is locked
+                let is_stronghold_locked = match context.upgrade() {
+                    Some(context) => context.is_stronghold_locked(),
+                    None => break,
+                };
+                if is_stronghold_locked {
+                    thread::sleep(interval);
+                    continue;
+                }
                 let storage_path_ = storage_path.clone();
                 crate::block_on(async move {
                     if let Err(panic) = AssertUnwindSafe(poll(storage_path_, is_monitoring_disabled))
@@ -144,23 +279,23 @@ impl AccountManager {
 
     /// Adds a new account.
     pub fn create_account(&self, client_options: ClientOptions) -> AccountInitialiser<'_> {
-        AccountInitialiser::new(client_options, &self.storage_path)
+        AccountInitialiser::new(client_options, &self.storage_path, self.context.clone())
     }
 
     /// Deletes an account.
     pub fn remove_account(&self, account_id: &AccountIdentifier) -> crate::Result<()> {
-        let account_str = crate::storage::with_adapter(&self.storage_path, |storage| storage.get(&account_id))?;
+        let account_str = self.context.with_storage(|storage| storage.get(&account_id))?;
         let account: Account = serde_json::from_str(&account_str)?;
         if !(account.messages().is_empty() && account.total_balance() == 0) {
             return Err(crate::WalletError::MessageNotEmpty);
         }
-        crate::storage::with_adapter(&self.storage_path, |storage| storage.remove(&account_id))?;
+        self.context.with_storage(|storage| storage.remove(&account_id))?;
         Ok(())
     }
 
     /// Syncs all accounts.
     pub async fn sync_accounts(&self) -> crate::Result<Vec<SyncedAccount>> {
-        let accounts = crate::storage::with_adapter(&self.storage_path, |storage| storage.get_all())?;
+        let accounts = self.context.with_storage(|storage| storage.get_all())?;
         let mut accounts = crate::storage::parse_accounts(&self.storage_path, &accounts)?;
         sync_accounts(&self.storage_path, None, &mut accounts).await
     }
@@ -173,15 +308,23 @@ impl AccountManager {
         amount: u64,
     ) -> crate::Result<InternalTransferMetadata> {
         let mut from_account = self.get_account(from_account_id)?;
-        let to_account = self.get_account(to_account_id)?;
+        let mut to_account = self.get_account(to_account_id)?;
         let to_address = to_account
             .latest_address()
             .ok_or_else(|| anyhow::anyhow!("destination account address list empty"))?
             .clone();
         let from_synchronized = from_account.sync().execute().await?;
-        let metadata = from_synchronized
-            .transfer(Transfer::new(to_address.address().clone(), amount))
+        let mut metadata = from_synchronized
+            .perform_transfer(Transfer::new(to_address.address().clone(), amount), true)
             .await?;
+
+        let mut incoming_message = metadata.message.clone();
+        incoming_message.set_incoming(true);
+        to_account.append_messages(vec![incoming_message]);
+
+        // persist both accounts in a single storage transaction
+        crate::storage::save_accounts(&self.storage_path, vec![&mut metadata.account, &mut to_account])?;
+
         Ok(InternalTransferMetadata {
             to_account,
             from_account: metadata.account,
@@ -189,73 +332,227 @@ impl AccountManager {
         })
     }
 
-    /// Backups the accounts to the given destination
-    pub fn backup<P: AsRef<Path>>(&self, destination: P) -> crate::Result<PathBuf> {
-        let storage_path = &self.storage_path;
-        if storage_path.exists() {
-            let metadata = fs::metadata(&storage_path)?;
-            let backup_path = destination.as_ref().to_path_buf();
-            if metadata.is_dir() {
-                copy_dir(storage_path, &backup_path)?;
-            } else {
-                fs::create_dir_all(&destination)?;
-                fs::copy(storage_path, &backup_path)?;
-            }
-            Ok(backup_path)
+    /// Backups the accounts to an encrypted archive file.
+    ///
+    /// The archive bundles the account records and a Stronghold snapshot re-encrypted with the backup `password`,
+    /// so the backup password is all that's needed to restore it. If the destination is a directory, the archive is
+    /// created inside of it with a timestamped file name.
+    pub fn backup<P: AsRef<Path>, S: AsRef<str>>(&self, destination: P, password: S) -> crate::Result<PathBuf> {
+        let records = self.context.with_storage(|storage| storage.get_all())?;
+        let accounts = crate::storage::parse_accounts(&self.storage_path, &records)?;
+        let stronghold_snapshot = if accounts
+            .iter()
+            .any(|account| account.signer_type() == &SignerType::Stronghold)
+        {
+            Some(self.backup_stronghold(&accounts, password.as_ref())?)
         } else {
-            Err(crate::WalletError::StorageDoesntExist)
-        }
+            None
+        };
+
+        let backup_path = if destination.as_ref().is_dir() {
+            destination.as_ref().join(format!(
+                "wallet-backup-{}",
+                chrono::Utc::now().format("%Y-%m-%dT%H-%M-%S")
+            ))
+        } else {
+            if let Some(parent) = destination.as_ref().parent() {
+                fs::create_dir_all(parent)?;
+            }
+            destination.as_ref().to_path_buf()
+        };
+        BackupArchive::new(records, stronghold_snapshot)?.write(&backup_path, password.as_ref())?;
+        Ok(backup_path)
     }
 
-    /// Import backed up accounts.
-    pub fn import_accounts<P: AsRef<Path>>(&self, source: P) -> crate::Result<()> {
-        let backup_stronghold_path = source.as_ref().join(crate::storage::stronghold_snapshot_filename());
-        let backup_stronghold =
-            stronghold::Stronghold::new(&backup_stronghold_path, false, "password".to_string(), None)?;
-        crate::init_stronghold(&source.as_ref().to_path_buf(), backup_stronghold);
+    /// Copies the Stronghold accounts to a temporary snapshot encrypted with the backup password and reads it.
+    fn backup_stronghold(&self, accounts: &[Account], password: &str) -> crate::Result<Vec<u8>> {
+        let temp_path = temp_backup_path();
+        fs::create_dir_all(&temp_path)?;
+        let snapshot_path = temp_path.join(crate::storage::stronghold_snapshot_filename());
+        let snapshot = Stronghold::new(&snapshot_path, true, password.to_string(), None)
+            .map_err(Into::into)
+            .and_then(|backup_stronghold| copy_stronghold_accounts(&self.storage_path, &backup_stronghold, accounts))
+            .and_then(|_| fs::read(&snapshot_path).map_err(Into::into));
+        let _ = fs::remove_dir_all(&temp_path);
+        snapshot
+    }
 
-        let backup_storage = crate::storage::get_adapter_from_path(&source)?;
-        let accounts = backup_storage.get_all()?;
-        let mut accounts = crate::storage::parse_accounts(&source.as_ref().to_path_buf(), &accounts)?;
+    /// Imports the accounts of a backup created with `backup`.
+    ///
+    /// The archive is decrypted with the backup `password` and validated before the wallet is changed, so a wrong
+    /// password or a corrupted file fails with `WalletError::InvalidBackup`. The `options` select the accounts to
+    /// restore and what to do with the ones already on the wallet; the accounts are written on a single storage
+    /// transaction.
+    pub fn import_accounts<P: AsRef<Path>, S: AsRef<str>>(
+        &self,
+        source: P,
+        password: S,
+        options: RestoreOptions,
+    ) -> crate::Result<RestoreReport> {
+        let archive = BackupArchive::read(source.as_ref(), password.as_ref())?;
+        let records = archive
+            .accounts()
+            .iter()
+            .map(|account| crate::storage::migration::migrate_account(account))
+            .collect::<crate::Result<Vec<String>>>()?;
+        let backup_accounts = crate::storage::parse_accounts(&self.storage_path, &records)?;
+        if let Some(selected_accounts) = &options.accounts {
+            let all_found = selected_accounts.iter().all(|account_id| {
+                backup_accounts
+                    .iter()
+                    .any(|account| crate::backup::is_account(account, account_id))
+            });
+            if !all_found {
+                return Err(crate::WalletError::AccountNotFound);
+            }
+        }
 
-        let stored_accounts = crate::storage::with_adapter(&self.storage_path, |storage| storage.get_all())?;
+        let stored_accounts = self.context.with_storage(|storage| storage.get_all())?;
         let stored_accounts = crate::storage::parse_accounts(&self.storage_path, &stored_accounts)?;
 
-        let already_imported_account = stored_accounts.iter().find(|stored_account| {
-            stored_account.addresses().iter().any(|stored_address| {
-                accounts.iter().any(|account| {
-                    account
-                        .addresses()
-                        .iter()
-                        .any(|address| address.address() == stored_address.address())
-                })
-            })
-        });
-        if let Some(imported_account) = already_imported_account {
-            return Err(crate::WalletError::AccountAlreadyImported {
-                alias: imported_account.alias().to_string(),
+        let mut report = RestoreReport::default();
+        // the stored accounts replaced by a backup account with another id
+        let mut removed_accounts = Vec::new();
+        // the Stronghold accounts backing the removed accounts
+        let mut removed_stronghold_accounts = Vec::new();
+        let mut overwritten_accounts = Vec::new();
+        let mut saved_accounts = Vec::new();
+        // the accounts whose Stronghold account isn't on the wallet's snapshot yet
+        let mut new_accounts = Vec::new();
+
+        for account in backup_accounts
+            .into_iter()
+            .filter(|account| options.is_selected(account))
+        {
+            let stored_account = stored_accounts.iter().find(|stored_account| {
+                stored_account.id() == account.id()
+                    || stored_account.addresses().iter().any(|stored_address| {
+                        account
+                            .addresses()
+                            .iter()
+                            .any(|address| address.address() == stored_address.address())
+                    })
             });
+            let status = match (stored_account, options.on_duplicate) {
+                (None, _) => {
+                    new_accounts.push(account.clone());
+                    AccountRestoreStatus::Imported
+                }
+                (Some(stored_account), DuplicateAccountStrategy::Abort) => {
+                    return Err(crate::WalletError::AccountAlreadyImported {
+                        alias: stored_account.alias().to_string(),
+                    });
+                }
+                (Some(_), DuplicateAccountStrategy::Skip) => AccountRestoreStatus::Skipped,
+                (Some(stored_account), DuplicateAccountStrategy::Overwrite) => {
+                    if stored_account.id() != account.id() {
+                        // the index is part of the derivation path, so it can't be reassigned; the backup account
+                        // can only take it if no other stored account uses it
+                        if let Some(other_account) = stored_accounts.iter().find(|other_account| {
+                            other_account.id() != stored_account.id() && other_account.index() == account.index()
+                        }) {
+                            return Err(crate::WalletError::AccountIndexInUse {
+                                index: *account.index(),
+                                alias: other_account.alias().to_string(),
+                            });
+                        }
+                        removed_accounts.push(stored_account.id().clone());
+                        if stored_account.signer_type() == &SignerType::Stronghold {
+                            removed_stronghold_accounts.push(account_id_to_stronghold_record_id(stored_account.id())?);
+                        }
+                        new_accounts.push(account.clone());
+                    }
+                    AccountRestoreStatus::Overwritten
+                }
+                (Some(stored_account), DuplicateAccountStrategy::Merge) => {
+                    let mut merged_account = stored_account.clone();
+                    merged_account.append_messages(
+                        account
+                            .messages()
+                            .iter()
+                            .cloned()
+                            .filter(|message| !stored_account.messages().contains(message))
+                            .collect(),
+                    );
+                    merged_account.append_addresses(
+                        account
+                            .addresses()
+                            .iter()
+                            .cloned()
+                            .filter(|address| !stored_account.addresses().contains(address))
+                            .collect(),
+                    );
+                    saved_accounts.push(merged_account);
+                    AccountRestoreStatus::Merged
+                }
+            };
+            report.accounts.push(AccountRestore::new(&account, status));
+            match status {
+                AccountRestoreStatus::Imported => saved_accounts.push(account),
+                AccountRestoreStatus::Overwritten => overwritten_accounts.push(account),
+                _ => {}
+            }
         }
 
-        let backup_stronghold =
-            stronghold::Stronghold::new(&backup_stronghold_path, false, "password".to_string(), None)?;
-        for account in accounts.iter_mut() {
-            let stronghold_account =
-                backup_stronghold.account_get_by_id(&account_id_to_stronghold_record_id(account.id())?)?;
-            let created_at_timestamp: u128 = account.created_at().timestamp().try_into().unwrap(); // safe to unwrap since it's > 0
-            let stronghold_account = crate::with_stronghold_from_path(&self.storage_path, |stronghold| {
-                stronghold.account_import(
-                    Some(created_at_timestamp),
-                    Some(created_at_timestamp),
-                    stronghold_account.mnemonic().to_string(),
-                    Some("password"),
-                )
-            });
+        let snapshot = archive.stronghold_snapshot()?;
+        // the Stronghold accounts imported to the wallet's snapshot, removed if the storage changes aren't committed
+        let mut imported_accounts = Vec::new();
+        let result = crate::storage::transaction(&self.storage_path, |storage| {
+            for account_id in &removed_accounts {
+                storage.remove(account_id)?;
+            }
+            for account in &overwritten_accounts {
+                crate::storage::set_account(storage, account)?;
+            }
+            for account in saved_accounts.iter_mut() {
+                crate::storage::merge_and_set(storage, account)?;
+            }
+            // imported last, so its failure rolls back the storage changes
+            if let Some(snapshot) = &snapshot {
+                if !new_accounts.is_empty() {
+                    imported_accounts = self.import_backup_stronghold(snapshot, password.as_ref(), &new_accounts)?;
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            if !imported_accounts.is_empty() {
+                let _ = self
+                    .context
+                    .with_stronghold(|stronghold| remove_stronghold_accounts(stronghold, &imported_accounts));
+            }
+            return Err(e);
+        }
 
-            account.save()?;
+        // the replaced accounts are only removed from the snapshot once the restore is committed
+        if !removed_stronghold_accounts.is_empty() {
+            self.context
+                .with_stronghold(|stronghold| remove_stronghold_accounts(stronghold, &removed_stronghold_accounts))?;
         }
-        crate::remove_stronghold(backup_stronghold_path);
-        Ok(())
+
+        Ok(report)
+    }
+
+    /// Imports the Stronghold accounts of the backup snapshot to the wallet's snapshot.
+    /// Returns the ids of the imported Stronghold accounts.
+    fn import_backup_stronghold(
+        &self,
+        snapshot: &[u8],
+        password: &str,
+        accounts: &[Account],
+    ) -> crate::Result<Vec<[u8; 32]>> {
+        let temp_path = temp_backup_path();
+        fs::create_dir_all(&temp_path)?;
+        let snapshot_path = temp_path.join(crate::storage::stronghold_snapshot_filename());
+        let result = fs::write(&snapshot_path, snapshot)
+            .map_err(Into::into)
+            .and_then(|_| Stronghold::new(&snapshot_path, false, password.to_string(), None).map_err(Into::into))
+            .and_then(|backup_stronghold| {
+                self.context
+                    .with_stronghold(|stronghold| import_stronghold_accounts(&backup_stronghold, stronghold, accounts))
+            });
+        let _ = fs::remove_dir_all(&temp_path);
+        result
     }
 
     /// Gets the account associated with the given identifier.
@@ -277,9 +574,8 @@ impl AccountManager {
 
     /// Gets all accounts from storage.
     pub fn get_accounts(&self) -> crate::Result<Vec<Account>> {
-        crate::storage::with_adapter(&self.storage_path, |storage| {
-            crate::storage::parse_accounts(&self.storage_path, &storage.get_all()?)
-        })
+        self.context
+            .with_storage(|storage| crate::storage::parse_accounts(&self.storage_path, &storage.get_all()?))
     }
 
     /// Reattaches an unconfirmed transaction.
@@ -301,6 +597,12 @@ impl AccountManager {
     }
 }
 
+impl Drop for AccountManager {
+    fn drop(&mut self) {
+        self.context.release_manager();
+    }
+}
+
 async fn poll(storage_path: PathBuf, syncing: bool) -> crate::Result<()> {
     let retried = if syncing {
         let accounts_before_sync = crate::storage::with_adapter(&storage_path, |storage| storage.get_all())?;
@@ -325,6 +627,7 @@ async fn poll(storage_path: PathBuf, syncing: bool) -> crate::Result<()> {
                     .unwrap();
                 if address_after_sync.balance() != address_before_sync.balance() {
                     emit_balance_change(
+                        &storage_path,
                         account_after_sync.id(),
                         address_after_sync,
                         *address_after_sync.balance(),
@@ -338,7 +641,12 @@ async fn poll(storage_path: PathBuf, syncing: bool) -> crate::Result<()> {
                 .iter()
                 .filter(|message| !account_before_sync.messages().contains(message))
                 .for_each(|message| {
-                    emit_transaction_event(TransactionEventType::NewTransaction, account_after_sync.id(), &message)
+                    emit_transaction_event(
+                        &storage_path,
+                        TransactionEventType::NewTransaction,
+                        account_after_sync.id(),
+                        &message,
+                    )
                 });
 
             // confirmation state change event
@@ -348,7 +656,7 @@ async fn poll(storage_path: PathBuf, syncing: bool) -> crate::Result<()> {
                     None => false,
                 };
                 if changed {
-                    emit_confirmation_state_change(account_after_sync.id(), &message, true);
+                    emit_confirmation_state_change(&storage_path, account_after_sync.id(), &message, true);
                 }
             }
         }
@@ -384,7 +692,12 @@ async fn poll(storage_path: PathBuf, syncing: bool) -> crate::Result<()> {
 
     retried.iter().for_each(|retried_data| {
         retried_data.reattached.iter().for_each(|message| {
-            emit_transaction_event(TransactionEventType::Reattachment, &retried_data.account_id, &message);
+            emit_transaction_event(
+                &storage_path,
+                TransactionEventType::Reattachment,
+                &retried_data.account_id,
+                &message,
+            );
         });
     });
     Ok(())
@@ -394,24 +707,34 @@ async fn discover_accounts(
     storage_path: &PathBuf,
     client_options: &ClientOptions,
     signer_type: Option<SignerType>,
-) -> crate::Result<Vec<SyncedAccount>> {
-    let mut synced_accounts = vec![];
+    derivation_scheme: Option<DerivationScheme>,
+) -> crate::Result<Vec<(SyncedAccount, Account)>> {
+    let mut discovered_accounts = vec![];
+    // the discovered accounts are stored at the end of the sync process, so we track the next index here
+    let context = crate::context::get_or_create(storage_path);
+    let accounts = context.with_storage(|storage| storage.get_all())?;
+    let mut index = accounts.len();
     loop {
-        let mut account_initialiser = AccountInitialiser::new(client_options.clone(), &storage_path).skip_persistance();
+        let mut account_initialiser = AccountInitialiser::new(client_options.clone(), &storage_path, context.clone())
+            .skip_persistance()
+            .index(index);
         if let Some(signer_type) = &signer_type {
             account_initialiser = account_initialiser.signer_type(signer_type.clone());
         }
+        if let Some(derivation_scheme) = &derivation_scheme {
+            account_initialiser = account_initialiser.derivation_scheme(derivation_scheme.clone());
+        }
         let mut account = account_initialiser.initialise()?;
         let synced_account = account.sync().skip_persistance().execute().await?;
         let is_empty = *synced_account.is_empty();
         if is_empty {
             break;
         } else {
-            synced_accounts.push(synced_account);
-            account.save()?;
+            discovered_accounts.push((synced_account, account));
+            index += 1;
         }
     }
-    Ok(synced_accounts)
+    Ok(discovered_accounts)
 }
 
 async fn sync_accounts<'a>(
@@ -420,37 +743,59 @@ async fn sync_accounts<'a>(
     accounts: &mut Vec<Account>,
 ) -> crate::Result<Vec<SyncedAccount>> {
     let mut synced_accounts = vec![];
-    let mut last_account = None;
-    for account in accounts {
-        let mut sync = account.sync();
+    for account in accounts.iter_mut() {
+        let mut sync = account.sync().skip_persistance();
         if let Some(index) = address_index {
             sync = sync.address_index(index);
         }
         let synced_account = sync.execute().await?;
-        last_account = Some(account);
         synced_accounts.push(synced_account);
     }
 
-    let discovered_accounts_res = match last_account {
+    // watch-only accounts aren't derived from a seed, so the discovery continues from the latest derived account
+    let latest_account = accounts
+        .iter()
+        .rev()
+        .find(|account| account.signer_type() != &SignerType::WatchOnly);
+    let discovered_accounts_res = match latest_account {
         Some(account) => {
             if account.messages().is_empty() || account.addresses().iter().all(|addr| *addr.balance() == 0) {
                 discover_accounts(
                     &storage_path,
                     account.client_options(),
                     Some(account.signer_type().clone()),
+                    Some(account.derivation_scheme().clone()),
                 )
                 .await
             } else {
                 Ok(vec![])
             }
         }
-        None => discover_accounts(&storage_path, &ClientOptions::default(), None).await,
+        None if accounts.is_empty() => discover_accounts(&storage_path, &ClientOptions::default(), None, None).await,
+        None => Ok(vec![]),
     };
-    if let Ok(discovered_accounts) = discovered_accounts_res {
-        synced_accounts.extend(discovered_accounts.into_iter());
-    }
+    let mut discovered_accounts = vec![];
+    // the synced accounts are persisted even if the discovery fails, then the discovery error is returned
+    let discovery_error = match discovered_accounts_res {
+        Ok(discovered) => {
+            for (synced_account, account) in discovered {
+                synced_accounts.push(synced_account);
+                discovered_accounts.push(account);
+            }
+            None
+        }
+        Err(e) => Some(e),
+    };
+
+    // persist the synced and the discovered accounts in a single storage transaction
+    let mut accounts_to_save: Vec<&mut Account> = accounts.iter_mut().collect();
+    accounts_to_save.extend(discovered_accounts.iter_mut());
+    crate::storage::save_accounts(&storage_path, accounts_to_save)?;
 
-    Ok(synced_accounts)
+    match discovery_error {
+        Some(e) => Err(e),
+        None => Ok(synced_accounts),
+    }
 }
 
 struct RetriedData {
@@ -483,46 +828,77 @@ async fn retry_unconfirmed_transactions(accounts: Vec<(&SyncedAccount, &Account)
     Ok(retried_messages)
 }
 
-fn copy_dir<U: AsRef<Path>, V: AsRef<Path>>(from: U, to: V) -> Result<(), std::io::Error> {
-    let mut stack = Vec::new();
-    stack.push(PathBuf::from(from.as_ref()));
-
-    let output_root = PathBuf::from(to.as_ref());
-    let input_root = PathBuf::from(from.as_ref()).components().count();
-
-    while let Some(working_path) = stack.pop() {
-        let src: PathBuf = working_path.components().skip(input_root).collect();
-
-        let dest = if src.components().count() == 0 {
-            output_root.clone()
-        } else {
-            output_root.join(&src)
-        };
-        if fs::metadata(&dest).is_err() {
-            fs::create_dir_all(&dest)?;
-        }
-
-        for entry in fs::read_dir(working_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                stack.push(path);
-            } else if let Some(filename) = path.file_name() {
-                let dest_path = dest.join(filename);
-                fs::copy(&path, &dest_path)?;
-            }
+/// Imports the Stronghold accounts backing the given wallet accounts from one snapshot to another.
+/// The accounts keep their mnemonic and BIP-39 passphrase, so their addresses (and ids) don't change.
+/// Returns the ids of the imported Stronghold accounts; if one of them can't be imported, none is kept.
+fn import_stronghold_accounts(
+    from: &Stronghold,
+    to: &Stronghold,
+    accounts: &[Account],
+) -> crate::Result<Vec<[u8; 32]>> {
+    let mut imported = Vec::new();
+    let result = accounts
+        .iter()
+        .filter(|account| account.signer_type() == &SignerType::Stronghold)
+        .try_for_each(|account| -> crate::Result<()> {
+            let account_id = account_id_to_stronghold_record_id(account.id())?;
+            let stronghold_account = from.account_get_by_id(&account_id)?;
+            let created_at_timestamp: u128 = account.created_at().timestamp().try_into().unwrap(); // safe to unwrap since it's > 0
+            to.account_import(
+                Some(created_at_timestamp),
+                Some(created_at_timestamp),
+                stronghold_account.mnemonic().to_string(),
+                stronghold_account.passphrase().as_deref(),
+            )?;
+            imported.push(account_id);
+            Ok(())
+        });
+    match result {
+        Ok(()) => Ok(imported),
+        Err(e) => {
+            let _ = remove_stronghold_accounts(to, &imported);
+            Err(e)
         }
     }
+}
 
+/// Removes the Stronghold accounts with the given ids from the snapshot.
+fn remove_stronghold_accounts(stronghold: &Stronghold, account_ids: &[[u8; 32]]) -> crate::Result<()> {
+    for account_id in account_ids {
+        stronghold.account_remove(account_id)?;
+    }
     Ok(())
 }
 
+/// Copies the Stronghold accounts of the wallet instance to a new snapshot.
+fn copy_stronghold_accounts(storage_path: &PathBuf, to: &Stronghold, accounts: &[Account]) -> crate::Result<()> {
+    crate::with_stronghold_from_path(storage_path, |stronghold| {
+        import_stronghold_accounts(stronghold, to, accounts).map(|_| ())
+    })
+}
+
+/// A unique temporary directory used to read and write backup snapshots.
+fn temp_backup_path() -> PathBuf {
+    let name: String = thread_rng().gen_ascii_chars().take(10).collect();
+    std::env::temp_dir().join(format!("wallet-backup-{}", name))
+}
+
 #[cfg(test)]
 mod tests {
+    use super::AccountManager;
     use crate::{
+        account::{AccountIdentifier, AccountInitialiser, IntegrityCheck},
         address::{AddressBuilder, IotaAddress},
+        backup::{AccountRestoreStatus, BackupArchive, DuplicateAccountStrategy, RestoreOptions},
         client::ClientOptionsBuilder,
         message::Message,
+        mock_node::MockLedger,
+        storage::{
+            memory::MemoryStorageAdapter,
+            migration::{STORAGE_VERSION, STORAGE_VERSION_KEY},
+            StorageAdapter,
+        },
+        test_utils::{get_mock_account_manager, mock_signer_type},
     };
     use iota::message::prelude::{Ed25519Address, Indexation, Message as IotaMessage, MessageId, Payload};
     use rusty_fork::rusty_fork_test;
@@ -621,4 +997,273 @@ mod tests {
             assert!(create_response.is_err());
         }
     }
+
+    rusty_fork_test! {
+        #[test]
+        fn discover_accounts() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                manager
+                    .create_account(node.client_options())
+                    .signer_type(mock_signer_type())
+                    .initialise()
+                    .unwrap();
+
+                // the mock signer is deterministic, so we can fund the next account before it's discovered
+                let next_account = AccountInitialiser::new(node.client_options(), manager.storage_path(), manager.context().clone())
+                    .signer_type(mock_signer_type())
+                    .skip_persistance()
+                    .initialise()
+                    .unwrap();
+                let address = crate::address::get_iota_address(&next_account, 0, false).unwrap();
+                node.with_ledger(|ledger| ledger.fund(&address, 1_000_000));
+
+                let synced_accounts = manager.sync_accounts().await.unwrap();
+                assert_eq!(synced_accounts.len(), 2);
+
+                let accounts = manager.get_accounts().unwrap();
+                assert_eq!(accounts.len(), 2);
+                assert_eq!(accounts[1].id(), next_account.id());
+                assert_eq!(accounts[1].total_balance(), 1_000_000);
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn change_stronghold_password() {
+            let manager = crate::test_utils::get_account_manager();
+
+            let client_options = ClientOptionsBuilder::node("https://nodes.devnet.iota.org:443")
+                .expect("invalid node URL")
+                .build();
+            let account = manager
+                .create_account(client_options)
+                .alias("alias")
+                .initialise()
+                .expect("failed to add account");
+            let address = crate::address::get_iota_address(&account, 0, false).unwrap();
+
+            assert!(matches!(
+                manager.change_stronghold_password("wrong-password", "new-password"),
+                Err(crate::WalletError::InvalidStrongholdPassword)
+            ));
+            manager.change_stronghold_password("password", "new-password").unwrap();
+
+            // the account record and keys are kept on the re-encrypted snapshot
+            let account = manager.get_account(account.id()).unwrap();
+            assert_eq!(account.alias(), "alias");
+            assert_eq!(crate::address::get_iota_address(&account, 0, false).unwrap(), address);
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn backup_and_import_accounts() {
+            let (manager, node) = get_mock_account_manager(MockLedger::new());
+            let account = manager
+                .create_account(node.client_options())
+                .signer_type(mock_signer_type())
+                .alias("alias")
+                .initialise()
+                .unwrap();
+            let backup_path = manager.backup("./example-database/backups", "backup-password").unwrap();
+
+            let (restored_manager, _) = get_mock_account_manager(MockLedger::new());
+            assert!(matches!(
+                restored_manager.import_accounts(&backup_path, "wrong-password", RestoreOptions::new()),
+                Err(crate::WalletError::InvalidBackup(_))
+            ));
+            assert!(restored_manager.get_accounts().unwrap().is_empty());
+
+            restored_manager
+                .import_accounts(&backup_path, "backup-password", RestoreOptions::new())
+                .unwrap();
+            let restored_account = restored_manager.get_account(account.id()).unwrap();
+            assert_eq!(restored_account.alias(), "alias");
+            assert_eq!(restored_account.addresses(), account.addresses());
+            assert!(matches!(
+                restored_manager.import_accounts(&backup_path, "backup-password", RestoreOptions::new()),
+                Err(crate::WalletError::AccountAlreadyImported { .. })
+            ));
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn restore_options() {
+            let (manager, node) = get_mock_account_manager(MockLedger::new());
+            let mut account = manager
+                .create_account(node.client_options())
+                .signer_type(mock_signer_type())
+                .initialise()
+                .unwrap();
+            let first_backup_path = manager.backup("./example-database/backups/first", "backup-password").unwrap();
+            account.generate_address().unwrap();
+            let second_backup_path = manager.backup("./example-database/backups/second", "backup-password").unwrap();
+
+            let (restored_manager, _) = get_mock_account_manager(MockLedger::new());
+            let options = RestoreOptions::new().accounts(vec![AccountIdentifier::Id("unknown".to_string())]);
+            assert!(matches!(
+                restored_manager.import_accounts(&first_backup_path, "backup-password", options),
+                Err(crate::WalletError::AccountNotFound)
+            ));
+
+            let options = RestoreOptions::new().accounts(vec![account.id().clone()]);
+            let report = restored_manager
+                .import_accounts(&first_backup_path, "backup-password", options)
+                .unwrap();
+            assert_eq!(report.accounts()[0].status(), &AccountRestoreStatus::Imported);
+
+            let options = RestoreOptions::new().on_duplicate(DuplicateAccountStrategy::Skip);
+            let report = restored_manager
+                .import_accounts(&second_backup_path, "backup-password", options)
+                .unwrap();
+            assert_eq!(report.accounts()[0].status(), &AccountRestoreStatus::Skipped);
+            assert_eq!(restored_manager.get_account(account.id()).unwrap().addresses().len(), 1);
+
+            let options = RestoreOptions::new().on_duplicate(DuplicateAccountStrategy::Merge);
+            let report = restored_manager
+                .import_accounts(&second_backup_path, "backup-password", options)
+                .unwrap();
+            assert_eq!(report.accounts()[0].status(), &AccountRestoreStatus::Merged);
+            assert_eq!(restored_manager.get_account(account.id()).unwrap().addresses().len(), 2);
+
+            let options = RestoreOptions::new().on_duplicate(DuplicateAccountStrategy::Overwrite);
+            let report = restored_manager
+                .import_accounts(&first_backup_path, "backup-password", options)
+                .unwrap();
+            assert_eq!(report.accounts()[0].status(), &AccountRestoreStatus::Overwritten);
+            assert_eq!(restored_manager.get_account(account.id()).unwrap().addresses().len(), 1);
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn overwrite_with_used_index() {
+            let (manager, node) = get_mock_account_manager(MockLedger::new());
+            let stored_account = manager
+                .create_account(node.client_options())
+                .signer_type(mock_signer_type())
+                .initialise()
+                .unwrap();
+            let other_account = manager
+                .create_account(node.client_options())
+                .signer_type(mock_signer_type())
+                .index(1)
+                .skip_persistance()
+                .initialise()
+                .unwrap();
+            manager
+                .context()
+                .with_storage(|storage| crate::storage::set_account(storage, &other_account))
+                .unwrap();
+
+            // a backup account with another id, sharing the stored account addresses but using the other account index
+            let backup_account = manager
+                .create_account(node.client_options())
+                .signer_type(mock_signer_type())
+                .index(2)
+                .addresses(stored_account.addresses().clone())
+                .skip_persistance()
+                .initialise()
+                .unwrap();
+            let mut backup_account = serde_json::to_value(&backup_account).unwrap();
+            backup_account["index"] = serde_json::Value::from(1);
+            let backup_path = std::path::PathBuf::from("./example-database/backups/used-index");
+            std::fs::create_dir_all("./example-database/backups").unwrap();
+            BackupArchive::new(vec![backup_account.to_string()], None)
+                .unwrap()
+                .write(&backup_path, "backup-password")
+                .unwrap();
+
+            let options = RestoreOptions::new().on_duplicate(DuplicateAccountStrategy::Overwrite);
+            assert!(matches!(
+                manager.import_accounts(&backup_path, "backup-password", options),
+                Err(crate::WalletError::AccountIndexInUse { index: 1, .. })
+            ));
+            assert!(manager.get_account(stored_account.id()).is_ok());
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn stronghold_lock_timeout() {
+            let manager = crate::test_utils::get_account_manager();
+            let client_options = ClientOptionsBuilder::node("https://nodes.devnet.iota.org:443")
+                .expect("invalid node URL")
+                .build();
+            let account = manager
+                .create_account(client_options)
+                .initialise()
+                .expect("failed to add account");
+
+            manager.set_stronghold_lock_timeout(std::time::Duration::from_millis(50));
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            assert!(manager.is_stronghold_locked());
+            assert!(matches!(
+                crate::address::get_iota_address(&account, 0, false),
+                Err(crate::WalletError::StrongholdLocked)
+            ));
+
+            manager.set_stronghold_lock_timeout(std::time::Duration::from_secs(60));
+            manager.unlock_stronghold("password").unwrap();
+            assert!(!manager.is_stronghold_locked());
+            assert!(crate::address::get_iota_address(&account, 0, false).is_ok());
+
+            manager.lock_stronghold();
+            assert!(manager.is_stronghold_locked());
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn migrate_custom_adapter_on_load() {
+            let (manager, node) = get_mock_account_manager(MockLedger::new());
+            let account = manager
+                .create_account(node.client_options())
+                .signer_type(mock_signer_type())
+                .initialise()
+                .unwrap();
+
+            // an unversioned record on a custom adapter
+            let stored = manager.context().with_storage(|storage| storage.get(account.id())).unwrap();
+            let mut stored: serde_json::Value = serde_json::from_str(&stored).unwrap();
+            stored.as_object_mut().unwrap().remove(STORAGE_VERSION_KEY);
+            for address in stored["addresses"].as_array_mut().unwrap() {
+                address.as_object_mut().unwrap().remove(STORAGE_VERSION_KEY);
+            }
+            let adapter = MemoryStorageAdapter::new();
+            adapter.set(account.id(), stored.to_string()).unwrap();
+
+            let manager = AccountManager::with_storage_adapter(manager.storage_path().join("custom"), adapter).unwrap();
+            let stored = manager.context().with_storage(|storage| storage.get(account.id())).unwrap();
+            let stored: serde_json::Value = serde_json::from_str(&stored).unwrap();
+            assert_eq!(stored[STORAGE_VERSION_KEY], STORAGE_VERSION);
+            for address in stored["addresses"].as_array().unwrap() {
+                assert_eq!(address[STORAGE_VERSION_KEY], STORAGE_VERSION);
+            }
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn verify_accounts_integrity_signer_error() {
+            let (manager, node) = get_mock_account_manager(MockLedger::new());
+            let account = manager
+                .create_account(node.client_options())
+                .signer_type(mock_signer_type())
+                .initialise()
+                .unwrap();
+
+            let reports = manager.verify_accounts_integrity(IntegrityCheck::All).unwrap();
+            assert_eq!(reports.len(), 1);
+            assert!(reports[0].1.as_ref().unwrap().is_ok());
+
+            manager.context().unregister_signer(&mock_signer_type()).unwrap();
+            let reports = manager.verify_accounts_integrity(IntegrityCheck::All).unwrap();
+            assert_eq!(&reports[0].0, account.id());
+            assert!(matches!(reports[0].1, Err(crate::WalletError::SignerNotSet(_))));
+        }
+    }
 }
@@ -2,10 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    account::{Account, AccountIdentifier, SyncedAccount},
-    address::Address,
+    account::{
+        Account, AccountIdentifier, DerivationScheme, IntegrityCheck, IntegrityReport, PreparedTransfer,
+        SignedTransfer, SyncedAccount, TransferPreview,
+    },
+    address::{Address, InputSource},
+    backup::{RestoreOptions, RestoreReport},
     client::ClientOptions,
     message::{Message as WalletMessage, MessageType as WalletMessageType, Transfer},
+    signing::{DataSignature, WatchOnlyKeys},
     WalletError,
 };
 use serde::{ser::Serializer, Deserialize, Serialize};
@@ -19,11 +24,19 @@ pub struct AccountToCreate {
     pub client_options: ClientOptions,
     /// The account mnemonic.
     pub mnemonic: Option<String>,
+    /// The BIP-39 passphrase of the account seed.
+    pub passphrase: Option<String>,
     /// The account alias.
     pub alias: Option<String>,
     /// The account createdAt date string.
     #[serde(rename = "createdAt")]
     pub created_at: Option<String>,
+    /// The exported addresses or public keys of a watch-only account.
+    #[serde(rename = "watchOnly")]
+    pub watch_only: Option<WatchOnlyKeys>,
+    /// The coin type and path template used to derive the account keys.
+    #[serde(rename = "derivationScheme")]
+    pub derivation_scheme: Option<DerivationScheme>,
 }
 
 /// Each public account method.
@@ -68,6 +81,29 @@ pub enum AccountMethod {
         #[serde(rename = "skipPersistance")]
         skip_persistance: Option<bool>,
     },
+    /// Sign data with the key of an account address.
+    SignData {
+        /// The bech32 address.
+        address: String,
+        /// The hex encoded data.
+        data: String,
+    },
+    /// Re-derive the stored addresses with the account signer.
+    VerifyIntegrity {
+        /// Which addresses to check, a sample by default.
+        #[serde(default)]
+        check: IntegrityCheck,
+    },
+    /// Exclude an address or an output from every transfer.
+    FreezeInput {
+        /// The address or output to freeze.
+        source: InputSource,
+    },
+    /// Allow the transfers to spend a frozen address or output again.
+    UnfreezeInput {
+        /// The address or output to unfreeze.
+        source: InputSource,
+    },
 }
 
 /// The messages that can be sent to the actor.
@@ -101,10 +137,24 @@ pub enum MessageType {
         #[serde(rename = "message_id")]
         message_id: String,
     },
-    /// Backup storage.
-    Backup(String),
-    /// Import accounts from storage.
-    RestoreBackup(String),
+    /// Backup the accounts to an encrypted archive.
+    Backup {
+        /// The backup file or directory path.
+        destination: String,
+        /// The password used to encrypt the backup.
+        password: String,
+    },
+    /// Import accounts from a backup archive.
+    RestoreBackup {
+        /// The backup path.
+        #[serde(rename = "backupPath")]
+        backup_path: String,
+        /// The backup password.
+        password: String,
+        /// The accounts to restore and what to do with the ones already on the wallet.
+        #[serde(default)]
+        options: RestoreOptions,
+    },
     /// Set stronghold snapshot password.
     SetStrongholdPassword(String),
     /// Send funds.
@@ -126,6 +176,64 @@ pub enum MessageType {
         /// The transfer amount.
         amount: u64,
     },
+    /// Change the stronghold snapshot password.
+    ChangeStrongholdPassword {
+        /// The current password.
+        #[serde(rename = "currentPassword")]
+        current_password: String,
+        /// The new password.
+        #[serde(rename = "newPassword")]
+        new_password: String,
+    },
+    /// Lock the stronghold snapshot.
+    LockStronghold,
+    /// Unlock the stronghold snapshot with its password.
+    UnlockStronghold(String),
+    /// Check whether the stronghold snapshot is locked.
+    IsStrongholdLocked,
+    /// Set the inactivity period (in seconds) after which the stronghold snapshot is locked.
+    SetStrongholdLockTimeout(u64),
+    /// Select the inputs and build the unsigned transaction of a transfer.
+    PrepareTransfer {
+        /// The account identifier.
+        #[serde(rename = "accountId")]
+        account_id: AccountIdentifier,
+        /// The transfer details.
+        transfer: Transfer,
+    },
+    /// Compute the inputs, outputs and remainder of a transfer without sending it.
+    PreviewTransfer {
+        /// The account identifier.
+        #[serde(rename = "accountId")]
+        account_id: AccountIdentifier,
+        /// The transfer details.
+        transfer: Transfer,
+    },
+    /// Sign a prepared transfer.
+    SignPreparedTransfer {
+        /// The account identifier.
+        #[serde(rename = "accountId")]
+        account_id: AccountIdentifier,
+        /// The prepared transfer.
+        prepared: PreparedTransfer,
+    },
+    /// Send a signed transfer.
+    SubmitSignedTransfer {
+        /// The account identifier.
+        #[serde(rename = "accountId")]
+        account_id: AccountIdentifier,
+        /// The signed transfer.
+        signed: SignedTransfer,
+    },
+    /// Release the inputs of a prepared transfer that won't be sent.
+    CancelPreparedTransfer(PreparedTransfer),
+    /// Verify a data signature.
+    VerifySignature {
+        /// The hex encoded data.
+        data: String,
+        /// The signature.
+        signature: DataSignature,
+    },
 }
 
 impl Serialize for MessageType {
@@ -147,8 +255,15 @@ impl Serialize for MessageType {
                 account_id: _,
                 message_id: _,
             } => serializer.serialize_unit_variant("MessageType", 6, "Reattach"),
-            MessageType::Backup(_) => serializer.serialize_unit_variant("MessageType", 7, "Backup"),
-            MessageType::RestoreBackup(_) => serializer.serialize_unit_variant("MessageType", 8, "RestoreBackup"),
+            MessageType::Backup {
+                destination: _,
+                password: _,
+            } => serializer.serialize_unit_variant("MessageType", 7, "Backup"),
+            MessageType::RestoreBackup {
+                backup_path: _,
+                password: _,
+                options: _,
+            } => serializer.serialize_unit_variant("MessageType", 8, "RestoreBackup"),
             MessageType::SetStrongholdPassword(_) => {
                 serializer.serialize_unit_variant("MessageType", 9, "SetStrongholdPassword")
             }
@@ -161,6 +276,42 @@ impl Serialize for MessageType {
                 to_account_id: _,
                 amount: _,
             } => serializer.serialize_unit_variant("MessageType", 11, "InternalTransfer"),
+            MessageType::ChangeStrongholdPassword {
+                current_password: _,
+                new_password: _,
+            } => serializer.serialize_unit_variant("MessageType", 12, "ChangeStrongholdPassword"),
+            MessageType::LockStronghold => serializer.serialize_unit_variant("MessageType", 13, "LockStronghold"),
+            MessageType::UnlockStronghold(_) => {
+                serializer.serialize_unit_variant("MessageType", 14, "UnlockStronghold")
+            }
+            MessageType::IsStrongholdLocked => {
+                serializer.serialize_unit_variant("MessageType", 15, "IsStrongholdLocked")
+            }
+            MessageType::SetStrongholdLockTimeout(_) => {
+                serializer.serialize_unit_variant("MessageType", 16, "SetStrongholdLockTimeout")
+            }
+            MessageType::PrepareTransfer {
+                account_id: _,
+                transfer: _,
+            } => serializer.serialize_unit_variant("MessageType", 17, "PrepareTransfer"),
+            MessageType::SignPreparedTransfer {
+                account_id: _,
+                prepared: _,
+            } => serializer.serialize_unit_variant("MessageType", 18, "SignPreparedTransfer"),
+            MessageType::SubmitSignedTransfer {
+                account_id: _,
+                signed: _,
+            } => serializer.serialize_unit_variant("MessageType", 19, "SubmitSignedTransfer"),
+            MessageType::CancelPreparedTransfer(_) => {
+                serializer.serialize_unit_variant("MessageType", 20, "CancelPreparedTransfer")
+            }
+            MessageType::VerifySignature { data: _, signature: _ } => {
+                serializer.serialize_unit_variant("MessageType", 21, "VerifySignature")
+            }
+            MessageType::PreviewTransfer {
+                account_id: _,
+                transfer: _,
+            } => serializer.serialize_unit_variant("MessageType", 22, "PreviewTransfer"),
         }
     }
 }
@@ -222,12 +373,38 @@ pub enum ResponseType {
     Reattached(String),
     /// Backup response.
     BackupSuccessful,
-    /// ImportAccounts response.
-    BackupRestored,
+    /// RestoreBackup response.
+    BackupRestored(RestoreReport),
     /// SetStrongholdPassword response.
     StrongholdPasswordSet,
-    /// SendTransfer and InternalTransfer response.
+    /// ChangeStrongholdPassword response.
+    StrongholdPasswordChanged,
+    /// LockStronghold response.
+    LockedStronghold,
+    /// UnlockStronghold response.
+    UnlockedStronghold,
+    /// IsStrongholdLocked response.
+    StrongholdIsLocked(bool),
+    /// SetStrongholdLockTimeout response.
+    StrongholdLockTimeoutSet,
+    /// SendTransfer, InternalTransfer and SubmitSignedTransfer response.
     SentTransfer(WalletMessage),
+    /// PreviewTransfer response.
+    TransferPreview(TransferPreview),
+    /// PrepareTransfer response.
+    PreparedTransfer(PreparedTransfer),
+    /// SignPreparedTransfer response.
+    SignedTransfer(SignedTransfer),
+    /// CancelPreparedTransfer response.
+    CancelledPreparedTransfer,
+    /// SignData response.
+    DataSignature(DataSignature),
+    /// VerifySignature response.
+    VerifiedSignature(bool),
+    /// VerifyIntegrity response.
+    IntegrityReport(IntegrityReport),
+    /// FreezeInput and UnfreezeInput response.
+    FrozenInputs(Vec<InputSource>),
     /// An error occurred.
     Error(WalletError),
     /// A panic occurred.
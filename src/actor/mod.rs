@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    account::AccountIdentifier,
+    account::{AccountIdentifier, PreparedTransfer, SignedTransfer},
     account_manager::AccountManager,
+    backup::RestoreOptions,
     message::{Message as WalletMessage, Transfer},
     DateTime, Result, Utc,
 };
@@ -98,8 +99,12 @@ impl WalletMessageHandler {
             MessageType::Reattach { account_id, message_id } => {
                 convert_async_panics(|| async { self.reattach(account_id, message_id).await }).await
             }
-            MessageType::Backup(destination_path) => convert_panics(|| self.backup(destination_path)),
-            MessageType::RestoreBackup(backup_path) => convert_panics(|| self.restore_backup(backup_path)),
+            MessageType::Backup { destination, password } => convert_panics(|| self.backup(destination, password)),
+            MessageType::RestoreBackup {
+                backup_path,
+                password,
+                options,
+            } => convert_panics(|| self.restore_backup(backup_path, password, options)),
             MessageType::SetStrongholdPassword(password) => convert_panics(|| self.set_stronghold_password(password)),
             MessageType::SendTransfer { account_id, transfer } => {
                 convert_async_panics(|| async { self.send_transfer(account_id, transfer).await }).await
@@ -112,6 +117,38 @@ impl WalletMessageHandler {
                 convert_async_panics(|| async { self.internal_transfer(from_account_id, to_account_id, *amount).await })
                     .await
             }
+            MessageType::ChangeStrongholdPassword {
+                current_password,
+                new_password,
+            } => convert_panics(|| self.change_stronghold_password(current_password, new_password)),
+            MessageType::LockStronghold => convert_panics(|| self.lock_stronghold()),
+            MessageType::UnlockStronghold(password) => convert_panics(|| self.unlock_stronghold(password)),
+            MessageType::IsStrongholdLocked => convert_panics(|| self.is_stronghold_locked()),
+            MessageType::SetStrongholdLockTimeout(timeout) => {
+                convert_panics(|| self.set_stronghold_lock_timeout(*timeout))
+            }
+            MessageType::PrepareTransfer { account_id, transfer } => {
+                convert_async_panics(|| async { self.prepare_transfer(account_id, transfer).await }).await
+            }
+            MessageType::PreviewTransfer { account_id, transfer } => {
+                convert_async_panics(|| async { self.preview_transfer(account_id, transfer).await }).await
+            }
+            MessageType::SignPreparedTransfer { account_id, prepared } => {
+                convert_panics(|| self.sign_prepared_transfer(account_id, prepared))
+            }
+            MessageType::SubmitSignedTransfer { account_id, signed } => {
+                convert_async_panics(|| async { self.submit_signed_transfer(account_id, signed).await }).await
+            }
+            MessageType::VerifySignature { data, signature } => convert_panics(|| {
+                let data = hex::decode(data).map_err(|_| anyhow::anyhow!("data must be a hex string"))?;
+                Ok(ResponseType::VerifiedSignature(crate::signing::verify(
+                    &data, signature,
+                )))
+            }),
+            MessageType::CancelPreparedTransfer(prepared) => convert_panics(|| {
+                prepared.cancel();
+                Ok(ResponseType::CancelledPreparedTransfer)
+            }),
         };
 
         let response = match response {
@@ -123,14 +160,16 @@ impl WalletMessageHandler {
             .send(Response::new(message.id().to_string(), message.message_type, response));
     }
 
-    fn backup(&self, destination_path: &str) -> Result<ResponseType> {
-        self.account_manager.backup(destination_path)?;
+    fn backup(&self, destination_path: &str, password: &str) -> Result<ResponseType> {
+        self.account_manager.backup(destination_path, password)?;
         Ok(ResponseType::BackupSuccessful)
     }
 
-    fn restore_backup(&self, backup_path: &str) -> Result<ResponseType> {
-        self.account_manager.import_accounts(backup_path)?;
-        Ok(ResponseType::BackupRestored)
+    fn restore_backup(&self, backup_path: &str, password: &str, options: &RestoreOptions) -> Result<ResponseType> {
+        let report = self
+            .account_manager
+            .import_accounts(backup_path, password, options.clone())?;
+        Ok(ResponseType::BackupRestored(report))
     }
 
     async fn reattach(&self, account_id: &AccountIdentifier, message_id: &str) -> Result<ResponseType> {
@@ -164,11 +203,14 @@ impl WalletMessageHandler {
                 from,
                 message_type,
             } => {
-                let messages: Vec<WalletMessage> = account
-                    .list_messages(*count, *from, message_type.clone())
-                    .into_iter()
-                    .cloned()
-                    .collect();
+                let messages: Vec<WalletMessage> = match message_type {
+                    Some(message_type) => account
+                        .list_messages(*count, *from, Some(message_type.clone()))
+                        .into_iter()
+                        .cloned()
+                        .collect(),
+                    None => account.load_messages(*count, *from)?,
+                };
                 Ok(ResponseType::Messages(messages))
             }
             AccountMethod::ListAddresses { unspent } => {
@@ -198,6 +240,24 @@ impl WalletMessageHandler {
                 let synced = synchronizer.execute().await?;
                 Ok(ResponseType::SyncedAccount(synced))
             }
+            AccountMethod::SignData { address, data } => {
+                let address = crate::address::parse(address.clone())?;
+                let data = hex::decode(data).map_err(|_| anyhow::anyhow!("data must be a hex string"))?;
+                let signature = account.sign_data(&address, &data)?;
+                Ok(ResponseType::DataSignature(signature))
+            }
+            AccountMethod::VerifyIntegrity { check } => {
+                let report = account.verify_integrity(*check)?;
+                Ok(ResponseType::IntegrityReport(report))
+            }
+            AccountMethod::FreezeInput { source } => {
+                account.freeze(source.clone())?;
+                Ok(ResponseType::FrozenInputs(account.frozen_inputs().clone()))
+            }
+            AccountMethod::UnfreezeInput { source } => {
+                account.unfreeze(source)?;
+                Ok(ResponseType::FrozenInputs(account.frozen_inputs().clone()))
+            }
         }
     }
 
@@ -215,6 +275,9 @@ impl WalletMessageHandler {
         if let Some(mnemonic) = &account.mnemonic {
             builder = builder.mnemonic(mnemonic);
         }
+        if let Some(passphrase) = &account.passphrase {
+            builder = builder.passphrase(passphrase);
+        }
         if let Some(alias) = &account.alias {
             builder = builder.alias(alias);
         }
@@ -225,6 +288,12 @@ impl WalletMessageHandler {
                     .map_err(|e| anyhow::anyhow!(e.to_string()))?,
             );
         }
+        if let Some(watch_only) = &account.watch_only {
+            builder = builder.watch_only(watch_only.clone());
+        }
+        if let Some(derivation_scheme) = &account.derivation_scheme {
+            builder = builder.derivation_scheme(derivation_scheme.clone());
+        }
 
         builder.initialise().map(ResponseType::CreatedAccount)
     }
@@ -244,6 +313,34 @@ impl WalletMessageHandler {
         Ok(ResponseType::StrongholdPasswordSet)
     }
 
+    fn change_stronghold_password(&self, current_password: &str, new_password: &str) -> Result<ResponseType> {
+        self.account_manager
+            .change_stronghold_password(current_password, new_password)?;
+        Ok(ResponseType::StrongholdPasswordChanged)
+    }
+
+    fn lock_stronghold(&self) -> Result<ResponseType> {
+        self.account_manager.lock_stronghold();
+        Ok(ResponseType::LockedStronghold)
+    }
+
+    fn unlock_stronghold(&self, password: &str) -> Result<ResponseType> {
+        self.account_manager.unlock_stronghold(password)?;
+        Ok(ResponseType::UnlockedStronghold)
+    }
+
+    fn is_stronghold_locked(&self) -> Result<ResponseType> {
+        Ok(ResponseType::StrongholdIsLocked(
+            self.account_manager.is_stronghold_locked(),
+        ))
+    }
+
+    fn set_stronghold_lock_timeout(&self, timeout_secs: u64) -> Result<ResponseType> {
+        self.account_manager
+            .set_stronghold_lock_timeout(Duration::from_secs(timeout_secs));
+        Ok(ResponseType::StrongholdLockTimeoutSet)
+    }
+
     async fn send_transfer(&self, account_id: &AccountIdentifier, transfer: &Transfer) -> Result<ResponseType> {
         let mut account = self.account_manager.get_account(account_id)?;
         let synced = account.sync().execute().await?;
@@ -251,6 +348,41 @@ impl WalletMessageHandler {
         Ok(ResponseType::SentTransfer(message))
     }
 
+    async fn prepare_transfer(&self, account_id: &AccountIdentifier, transfer: &Transfer) -> Result<ResponseType> {
+        let mut account = self.account_manager.get_account(account_id)?;
+        let synced = account.sync().execute().await?;
+        let prepared = synced.prepare_transfer(transfer.clone()).await?;
+        Ok(ResponseType::PreparedTransfer(prepared))
+    }
+
+    async fn preview_transfer(&self, account_id: &AccountIdentifier, transfer: &Transfer) -> Result<ResponseType> {
+        let mut account = self.account_manager.get_account(account_id)?;
+        let synced = account.sync().execute().await?;
+        let preview = synced.preview_transfer(transfer.clone())?;
+        Ok(ResponseType::TransferPreview(preview))
+    }
+
+    fn sign_prepared_transfer(
+        &self,
+        account_id: &AccountIdentifier,
+        prepared: &PreparedTransfer,
+    ) -> Result<ResponseType> {
+        let account = self.account_manager.get_account(account_id)?;
+        let signed = account.sign_prepared(prepared)?;
+        Ok(ResponseType::SignedTransfer(signed))
+    }
+
+    async fn submit_signed_transfer(
+        &self,
+        account_id: &AccountIdentifier,
+        signed: &SignedTransfer,
+    ) -> Result<ResponseType> {
+        let mut account = self.account_manager.get_account(account_id)?;
+        let synced = account.sync().execute().await?;
+        let message = synced.submit_signed(signed.clone()).await?.message;
+        Ok(ResponseType::SentTransfer(message))
+    }
+
     async fn internal_transfer(
         &self,
         from_account_id: &AccountIdentifier,
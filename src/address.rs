@@ -33,6 +33,11 @@ pub struct AddressOutput {
 }
 
 impl AddressOutput {
+    /// Gets the output identifier.
+    pub fn id(&self) -> OutputId {
+        OutputId::new(self.transaction_id, self.index)
+    }
+
     /// Checks if the output is referenced on a pending message or a confirmed message
     pub(crate) fn is_used(&self, account: &Account) -> bool {
         let output_id = UTXOInput::new(self.transaction_id, self.index).unwrap();
@@ -147,6 +152,45 @@ impl AddressBuilder {
     }
 }
 
+/// The identifier of an address output: its transaction id and output index.
+#[derive(Debug, Getters, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct OutputId {
+    /// Transaction ID of the output.
+    #[serde(rename = "transactionId")]
+    transaction_id: TransactionId,
+    /// Output index.
+    index: u16,
+}
+
+impl OutputId {
+    /// Initialises a new output identifier.
+    pub fn new(transaction_id: TransactionId, index: u16) -> Self {
+        Self { transaction_id, index }
+    }
+}
+
+/// An account address or one of its outputs, used to include, exclude or freeze transfer inputs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum InputSource {
+    /// Every output of the address.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    Address(IotaAddress),
+    /// A single output.
+    Output(OutputId),
+}
+
+impl InputSource {
+    /// Whether the output held by the address belongs to this source.
+    pub fn contains(&self, address: &IotaAddress, output: &OutputId) -> bool {
+        match self {
+            Self::Address(source_address) => source_address == address,
+            Self::Output(source_output) => source_output == output,
+        }
+    }
+}
+
 /// An address.
 #[derive(Debug, Getters, Setters, Clone, Eq, Serialize, Deserialize)]
 #[getset(get = "pub")]
@@ -239,7 +283,7 @@ pub fn parse(address: String) -> crate::Result<IotaAddress> {
 }
 
 pub(crate) fn get_iota_address(account: &Account, address_index: usize, internal: bool) -> crate::Result<IotaAddress> {
-    crate::signing::with_signer(account.signer_type(), |signer| {
+    crate::signing::with_signer(account.storage_path(), account.signer_type(), |signer| {
         signer.generate_address(&account, address_index, internal)
     })
 }
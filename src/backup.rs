@@ -0,0 +1,379 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::account::{Account, AccountIdentifier};
+
+use blake2::{
+    digest::{Update, VariableOutput},
+    VarBlake2b,
+};
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use chrono::prelude::{DateTime, Utc};
+use getset::Getters;
+use hmac::Hmac;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// The current backup format version.
+pub const BACKUP_FORMAT_VERSION: u8 = 1;
+/// The bytes identifying a backup file.
+const BACKUP_MAGIC: &[u8; 8] = b"IOTAWBAK";
+const PBKDF2_ROUNDS: usize = 100_000;
+const SALT_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+const HEADER_LENGTH: usize = BACKUP_MAGIC.len() + 1 + SALT_LENGTH + NONCE_LENGTH;
+
+/// An account listed on the backup manifest.
+#[derive(Debug, Clone, Getters, Serialize, Deserialize, PartialEq)]
+#[getset(get = "pub")]
+pub struct BackupAccount {
+    /// The account identifier.
+    id: AccountIdentifier,
+    /// The account alias.
+    alias: String,
+}
+
+/// The backup manifest.
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct BackupManifest {
+    /// The backup format version.
+    #[serde(rename = "formatVersion")]
+    format_version: u8,
+    /// The time the backup was created.
+    #[serde(rename = "createdAt")]
+    created_at: DateTime<Utc>,
+    /// The backed up accounts.
+    accounts: Vec<BackupAccount>,
+    /// The hex encoded blake2b-256 checksum of the account records and the Stronghold snapshot.
+    checksum: String,
+}
+
+/// What to do with a backup account that's already on the wallet.
+/// An account is a duplicate if a stored account has the same id or shares any of its addresses.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DuplicateAccountStrategy {
+    /// Aborts the restore with `WalletError::AccountAlreadyImported`.
+    Abort,
+    /// Keeps the stored account.
+    Skip,
+    /// Replaces the stored account with the backup one.
+    Overwrite,
+    /// Adds the messages and addresses of the backup account to the stored account.
+    Merge,
+}
+
+impl Default for DuplicateAccountStrategy {
+    fn default() -> Self {
+        Self::Abort
+    }
+}
+
+/// The backup restore options.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RestoreOptions {
+    /// The accounts to restore. Every backup account is restored if it's not set.
+    #[serde(default)]
+    pub(crate) accounts: Option<Vec<AccountIdentifier>>,
+    /// What to do with the backup accounts that are already on the wallet.
+    #[serde(rename = "onDuplicate", default)]
+    pub(crate) on_duplicate: DuplicateAccountStrategy,
+}
+
+impl RestoreOptions {
+    /// Initialises the options to restore every backup account, aborting if any of them is already on the wallet.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Restores only the given backup accounts, identified by id or index.
+    pub fn accounts(mut self, accounts: Vec<AccountIdentifier>) -> Self {
+        self.accounts = Some(accounts);
+        self
+    }
+
+    /// Sets what to do with the backup accounts that are already on the wallet.
+    pub fn on_duplicate(mut self, strategy: DuplicateAccountStrategy) -> Self {
+        self.on_duplicate = strategy;
+        self
+    }
+
+    /// Whether the backup account was selected to be restored.
+    pub(crate) fn is_selected(&self, account: &Account) -> bool {
+        match &self.accounts {
+            Some(accounts) => accounts.iter().any(|account_id| is_account(account, account_id)),
+            None => true,
+        }
+    }
+}
+
+/// Whether the account is the one with the given identifier.
+pub(crate) fn is_account(account: &Account, account_id: &AccountIdentifier) -> bool {
+    match account_id {
+        AccountIdentifier::Id(_) => account.id() == account_id,
+        AccountIdentifier::Index(index) => account.index() == index,
+    }
+}
+
+/// How a backup account was restored.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub enum AccountRestoreStatus {
+    /// The account wasn't on the wallet and was imported.
+    Imported,
+    /// The account was already on the wallet and was kept.
+    Skipped,
+    /// The account replaced the stored one.
+    Overwritten,
+    /// The account history was merged into the stored one.
+    Merged,
+}
+
+/// The restore result of a backup account.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct AccountRestore {
+    /// The backup account identifier.
+    #[serde(rename = "accountId")]
+    account_id: AccountIdentifier,
+    /// The backup account alias.
+    alias: String,
+    /// How the account was restored.
+    status: AccountRestoreStatus,
+}
+
+impl AccountRestore {
+    pub(crate) fn new(account: &Account, status: AccountRestoreStatus) -> Self {
+        Self {
+            account_id: account.id().clone(),
+            alias: account.alias().to_string(),
+            status,
+        }
+    }
+}
+
+/// The backup restore report.
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct RestoreReport {
+    /// The restored backup accounts.
+    pub(crate) accounts: Vec<AccountRestore>,
+}
+
+/// The decrypted content of a backup file.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BackupArchive {
+    manifest: BackupManifest,
+    /// The account records, as stored on the storage adapter.
+    accounts: Vec<String>,
+    /// The hex encoded Stronghold snapshot, encrypted with the backup password.
+    #[serde(rename = "strongholdSnapshot")]
+    stronghold_snapshot: Option<String>,
+}
+
+impl BackupArchive {
+    /// Creates the archive of the given account records and Stronghold snapshot.
+    pub(crate) fn new(accounts: Vec<String>, stronghold_snapshot: Option<Vec<u8>>) -> crate::Result<Self> {
+        let manifest_accounts = accounts
+            .iter()
+            .map(|account| {
+                let account: Account = serde_json::from_str(&account)?;
+                Ok(BackupAccount {
+                    id: account.id().clone(),
+                    alias: account.alias().to_string(),
+                })
+            })
+            .collect::<crate::Result<Vec<BackupAccount>>>()?;
+        let stronghold_snapshot = stronghold_snapshot.map(hex::encode);
+        let checksum = checksum(&accounts, &stronghold_snapshot);
+        Ok(Self {
+            manifest: BackupManifest {
+                format_version: BACKUP_FORMAT_VERSION,
+                created_at: Utc::now(),
+                accounts: manifest_accounts,
+                checksum,
+            },
+            accounts,
+            stronghold_snapshot,
+        })
+    }
+
+    /// The archive manifest.
+    pub(crate) fn manifest(&self) -> &BackupManifest {
+        &self.manifest
+    }
+
+    /// The backed up account records.
+    pub(crate) fn accounts(&self) -> &[String] {
+        &self.accounts
+    }
+
+    /// The backed up Stronghold snapshot.
+    pub(crate) fn stronghold_snapshot(&self) -> crate::Result<Option<Vec<u8>>> {
+        match &self.stronghold_snapshot {
+            Some(snapshot) => hex::decode(snapshot)
+                .map(Some)
+                .map_err(|_| invalid_backup("the Stronghold snapshot isn't valid hex")),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks the format version, the checksum and the manifest account list.
+    fn validate(&self) -> crate::Result<()> {
+        if self.manifest.format_version != BACKUP_FORMAT_VERSION {
+            return Err(invalid_backup(format!(
+                "backup format version `{}` isn't supported",
+                self.manifest.format_version
+            )));
+        }
+        if checksum(&self.accounts, &self.stronghold_snapshot) != self.manifest.checksum {
+            return Err(invalid_backup("checksum mismatch"));
+        }
+        if self.accounts.len() != self.manifest.accounts.len() {
+            return Err(invalid_backup("the account records don't match the manifest"));
+        }
+        for (account, manifest_account) in self.accounts.iter().zip(&self.manifest.accounts) {
+            let account: Account =
+                serde_json::from_str(&account).map_err(|_| invalid_backup("invalid account record"))?;
+            if account.id() != manifest_account.id() {
+                return Err(invalid_backup("the account records don't match the manifest"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Encrypts the archive with the password and writes it to the destination file.
+    pub(crate) fn write(&self, destination: &Path, password: &str) -> crate::Result<()> {
+        let mut salt = [0u8; SALT_LENGTH];
+        let mut nonce = [0u8; NONCE_LENGTH];
+        getrandom::getrandom(&mut salt).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        getrandom::getrandom(&mut nonce).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        let mut header = BACKUP_MAGIC.to_vec();
+        header.push(BACKUP_FORMAT_VERSION);
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce);
+
+        let plaintext = serde_json::to_vec(&self)?;
+        let key = derive_key(password, &salt);
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(&key))
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &header,
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("failed to encrypt backup"))?;
+
+        let mut file = header;
+        file.extend(ciphertext);
+        fs::write(destination, file)?;
+        Ok(())
+    }
+
+    /// Reads, decrypts and validates the backup file.
+    /// Nothing is changed on the wallet if the backup is invalid, so every check happens here.
+    pub(crate) fn read(source: &Path, password: &str) -> crate::Result<Self> {
+        let file = fs::read(source)?;
+        if file.len() < HEADER_LENGTH || &file[..BACKUP_MAGIC.len()] != BACKUP_MAGIC {
+            return Err(invalid_backup("not a wallet backup file"));
+        }
+        let version = file[BACKUP_MAGIC.len()];
+        if version != BACKUP_FORMAT_VERSION {
+            return Err(invalid_backup(format!(
+                "backup format version `{}` isn't supported",
+                version
+            )));
+        }
+        let (header, ciphertext) = file.split_at(HEADER_LENGTH);
+        let salt = &header[BACKUP_MAGIC.len() + 1..BACKUP_MAGIC.len() + 1 + SALT_LENGTH];
+        let nonce = &header[BACKUP_MAGIC.len() + 1 + SALT_LENGTH..];
+
+        let key = derive_key(password, salt);
+        // the AEAD tag doesn't tell a wrong password from a corrupted file apart
+        let plaintext = XChaCha20Poly1305::new(Key::from_slice(&key))
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| invalid_backup("invalid backup password or corrupted file"))?;
+
+        let archive: Self =
+            serde_json::from_slice(&plaintext).map_err(|_| invalid_backup("the backup content is malformed"))?;
+        archive.validate()?;
+        Ok(archive)
+    }
+}
+
+fn invalid_backup<S: Into<String>>(reason: S) -> crate::WalletError {
+    crate::WalletError::InvalidBackup(reason.into())
+}
+
+/// Derives the backup key from the password with PBKDF2-HMAC-SHA512.
+fn derive_key(password: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<sha2::Sha512>>(password.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+fn checksum(accounts: &[String], stronghold_snapshot: &Option<String>) -> String {
+    let mut hasher = VarBlake2b::new(32).unwrap();
+    for account in accounts {
+        hasher.update(account.as_bytes());
+    }
+    if let Some(snapshot) = stronghold_snapshot {
+        hasher.update(snapshot.as_bytes());
+    }
+    let mut checksum = String::new();
+    hasher.finalize_variable(|hash| checksum = hex::encode(hash));
+    checksum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BackupArchive;
+    use crate::{account::AccountInitialiser, mock_node::MockLedger, test_utils};
+    use std::fs;
+
+    fn create_archive() -> BackupArchive {
+        let (manager, node) = test_utils::get_mock_account_manager(MockLedger::new());
+        let account = AccountInitialiser::new(node.client_options(), manager.storage_path(), manager.context().clone())
+            .signer_type(test_utils::mock_signer_type())
+            .alias("alias")
+            .skip_persistance()
+            .initialise()
+            .unwrap();
+        BackupArchive::new(vec![serde_json::to_string(&account).unwrap()], None).unwrap()
+    }
+
+    #[test]
+    fn corrupted_backup() {
+        let path = std::path::PathBuf::from("./example-database/corrupted-backup");
+        fs::create_dir_all("./example-database").unwrap();
+        let archive = create_archive();
+        archive.write(&path, "backup-password").unwrap();
+
+        let archive = BackupArchive::read(&path, "backup-password").unwrap();
+        assert_eq!(archive.manifest().accounts()[0].alias(), "alias");
+        assert!(matches!(
+            BackupArchive::read(&path, "wrong-password"),
+            Err(crate::WalletError::InvalidBackup(_))
+        ));
+
+        let mut file = fs::read(&path).unwrap();
+        let last = file.len() - 1;
+        file[last] ^= 1;
+        fs::write(&path, file).unwrap();
+        assert!(matches!(
+            BackupArchive::read(&path, "backup-password"),
+            Err(crate::WalletError::InvalidBackup(_))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -10,47 +10,64 @@ use url::Url;
 
 use std::{
     collections::HashMap,
+    path::PathBuf,
     sync::{Arc, Mutex, RwLock},
 };
 
-type ClientInstanceMap = Arc<Mutex<HashMap<ClientOptions, Arc<RwLock<Client>>>>>;
-
-/// Gets the client instances map.
-fn instances() -> &'static ClientInstanceMap {
-    static INSTANCES: Lazy<ClientInstanceMap> = Lazy::new(Default::default);
-    &INSTANCES
+/// A pool of clients, one for each set of client options.
+#[derive(Default)]
+pub(crate) struct ClientPool {
+    clients: Mutex<HashMap<ClientOptions, Arc<RwLock<Client>>>>,
 }
 
-pub(crate) fn get_client(options: &ClientOptions) -> Arc<RwLock<Client>> {
-    let mut map = instances()
-        .lock()
-        .expect("failed to lock client instances: get_client()");
-
-    if !map.contains_key(&options) {
-        let mut client_builder = ClientBuilder::new()
-            .broker_options(BrokerOptions::new().automatic_disconnect(false))
-            .local_pow(*options.local_pow());
-
-        // we validate the URL beforehand so it's safe to unwrap here
-        if let Some(node) = options.node() {
-            client_builder = client_builder.node(node.as_str()).unwrap();
-        } else if let Some(nodes) = options.nodes() {
-            client_builder = client_builder
-                .nodes(&nodes.iter().map(|url| url.as_str()).collect::<Vec<&str>>()[..])
-                .unwrap();
+impl ClientPool {
+    /// Gets the client for the given options, creating it if needed.
+    pub(crate) fn get(&self, options: &ClientOptions) -> Arc<RwLock<Client>> {
+        let mut map = self
+            .clients
+            .lock()
+            .expect("failed to lock client instances: get_client()");
+
+        if !map.contains_key(&options) {
+            let mut client_builder = ClientBuilder::new()
+                .broker_options(BrokerOptions::new().automatic_disconnect(false))
+                .local_pow(*options.local_pow());
+
+            // we validate the URL beforehand so it's safe to unwrap here
+            if let Some(node) = options.node() {
+                client_builder = client_builder.node(node.as_str()).unwrap();
+            } else if let Some(nodes) = options.nodes() {
+                client_builder = client_builder
+                    .nodes(&nodes.iter().map(|url| url.as_str()).collect::<Vec<&str>>()[..])
+                    .unwrap();
+            }
+
+            if let Some(network) = options.network() {
+                client_builder = client_builder.network(network.clone());
+            }
+
+            let client = client_builder.build().expect("failed to initialise ClientBuilder");
+
+            map.insert(options.clone(), Arc::new(RwLock::new(client)));
         }
 
-        if let Some(network) = options.network() {
-            client_builder = client_builder.network(network.clone());
-        }
+        let client = map.get(&options).expect("client not initialised");
+        client.clone()
+    }
+}
 
-        let client = client_builder.build().expect("failed to initialise ClientBuilder");
+/// Gets the default client pool, used when the storage path isn't associated with a wallet instance.
+fn default_pool() -> &'static ClientPool {
+    static POOL: Lazy<ClientPool> = Lazy::new(Default::default);
+    &POOL
+}
 
-        map.insert(options.clone(), Arc::new(RwLock::new(client)));
+/// Gets the client for the given options from the pool of the wallet instance associated with the storage path.
+pub(crate) fn get_client(storage_path: &PathBuf, options: &ClientOptions) -> Arc<RwLock<Client>> {
+    match crate::context::get(storage_path) {
+        Some(context) => context.client(options),
+        None => default_pool().get(options),
     }
-
-    let client = map.get(&options).expect("client not initialised");
-    client.clone()
 }
 
 /// The options builder for a client connected to a single node.
@@ -0,0 +1,708 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::AccountIdentifier,
+    client::{ClientOptions, ClientPool},
+    event::EventBus,
+    signing::{read_registry, write_registry, BoxedSigner, Signer, SignerCapability, SignerRegistry, SignerType},
+    storage::{Storage, StorageAdapter},
+};
+use getset::Getters;
+use iota::client::Client;
+use once_cell::sync::OnceCell;
+use stronghold::Stronghold;
+use zeroize::Zeroize;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, RwLock, Weak,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+type Contexts = Arc<RwLock<HashMap<PathBuf, RegisteredContext>>>;
+static CONTEXTS: OnceCell<Contexts> = OnceCell::new();
+
+/// A context registered by storage path.
+enum RegisteredContext {
+    /// A context owned by an account manager (and its accounts), released with them.
+    Owned(Weak<WalletContext>),
+    /// A context created by the path-based helpers (e.g. `storage::set_adapter`) without an account manager.
+    /// It's kept by the registry so the helpers can reach the default wallet instance of the path.
+    Default(Arc<WalletContext>),
+}
+
+impl RegisteredContext {
+    fn get(&self) -> Option<Arc<WalletContext>> {
+        match self {
+            Self::Owned(context) => context.upgrade(),
+            Self::Default(context) => Some(context.clone()),
+        }
+    }
+}
+
+/// The maximum interval between the checks for an expired Stronghold lock timeout.
+const STRONGHOLD_LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const STRONGHOLD_LOCK_MIN_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A loaded Stronghold snapshot and the password used to decrypt it.
+struct LoadedStronghold {
+    stronghold: Stronghold,
+    password: String,
+    last_access: Instant,
+}
+
+impl Drop for LoadedStronghold {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+/// The thread that locks the Stronghold instance after its lock timeout.
+struct StrongholdLockWatcher {
+    shutdown: Arc<AtomicBool>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl StrongholdLockWatcher {
+    /// Periodically locks the Stronghold instance of the context after its lock timeout, until it's stopped or the
+    /// context is dropped.
+    fn spawn(context: Weak<WalletContext>) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_ = shutdown.clone();
+        let handle = thread::spawn(move || {
+            while !shutdown_.load(Ordering::SeqCst) {
+                let interval = match context.upgrade() {
+                    Some(context) => {
+                        context.lock_expired_stronghold();
+                        let timeout = context.stronghold_lock_timeout.lock().unwrap().unwrap_or_default();
+                        timeout
+                            .min(STRONGHOLD_LOCK_CHECK_INTERVAL)
+                            .max(STRONGHOLD_LOCK_MIN_CHECK_INTERVAL)
+                    }
+                    None => break,
+                };
+                // woken up early by `stop`
+                thread::park_timeout(interval);
+            }
+        });
+        Self { shutdown, handle }
+    }
+
+    fn stop(self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.handle.thread().unpark();
+        // the watcher may be the one dropping the context, so it can't wait for itself
+        if self.handle.thread().id() != thread::current().id() {
+            let _ = self.handle.join();
+        }
+    }
+}
+
+/// A lock held by one thread at a time, which can take it again while holding it.
+/// It serializes the storage access of a wallet instance, so a storage transaction neither sees nor takes in the
+/// writes of other threads, which wait until it's committed or rolled back.
+#[derive(Default)]
+struct StorageLock {
+    /// The thread holding the lock and how many times it took it.
+    owner: Mutex<Option<(thread::ThreadId, usize)>>,
+    released: Condvar,
+}
+
+impl StorageLock {
+    fn lock(&self) -> StorageLockGuard<'_> {
+        let current = thread::current().id();
+        let mut owner = self.owner.lock().unwrap();
+        let depth = loop {
+            let state = *owner;
+            match state {
+                None => break 0,
+                Some((thread, depth)) if thread == current => break depth,
+                Some(_) => owner = self.released.wait(owner).unwrap(),
+            }
+        };
+        *owner = Some((current, depth + 1));
+        StorageLockGuard(self)
+    }
+}
+
+struct StorageLockGuard<'a>(&'a StorageLock);
+
+impl Drop for StorageLockGuard<'_> {
+    fn drop(&mut self) {
+        let mut owner = self.0.owner.lock().unwrap();
+        *owner = match *owner {
+            Some((thread, depth)) if depth > 1 => Some((thread, depth - 1)),
+            _ => None,
+        };
+        if owner.is_none() {
+            self.0.released.notify_all();
+        }
+    }
+}
+
+enum StrongholdState {
+    /// The password wasn't provided yet.
+    NotLoaded,
+    Loaded(LoadedStronghold),
+    /// The snapshot was evicted (by the lock timeout or an explicit lock) and the password must be provided again.
+    Locked,
+}
+
+impl Default for StrongholdState {
+    fn default() -> Self {
+        Self::NotLoaded
+    }
+}
+
+/// The resources owned by a wallet instance: its storage adapter, Stronghold snapshot, signers, clients and event
+/// listeners.
+///
+/// Each `AccountManager` owns the context of its storage path, so several isolated wallets can run in the same
+/// process, and its accounts keep a handle to it. The context is released with the manager and its accounts; until
+/// then, it's also registered by storage path for the accounts deserialized without the handle.
+#[derive(Getters)]
+pub struct WalletContext {
+    /// The storage path.
+    #[getset(get = "pub")]
+    storage_path: PathBuf,
+    storage: RwLock<Option<Storage>>,
+    /// Serializes the storage access, held for the whole span of a transaction.
+    storage_lock: StorageLock,
+    stronghold: Mutex<StrongholdState>,
+    stronghold_lock_timeout: Mutex<Option<Duration>>,
+    stronghold_lock_watcher: Mutex<Option<StrongholdLockWatcher>>,
+    /// The number of account managers owning the context.
+    managers: AtomicUsize,
+    signers: RwLock<SignerRegistry>,
+    clients: ClientPool,
+    /// Whether the last integrity check of the accounts found every checked address; the accounts that weren't
+    /// checked yet with their current signer aren't listed.
+    integrity_results: RwLock<HashMap<AccountIdentifier, bool>>,
+    /// The event listeners of the wallet instance.
+    #[getset(get = "pub")]
+    events: EventBus,
+}
+
+impl WalletContext {
+    fn new(storage_path: PathBuf) -> Self {
+        Self {
+            storage_path,
+            storage: Default::default(),
+            storage_lock: Default::default(),
+            stronghold: Default::default(),
+            stronghold_lock_timeout: Default::default(),
+            stronghold_lock_watcher: Default::default(),
+            managers: Default::default(),
+            signers: Default::default(),
+            clients: Default::default(),
+            integrity_results: Default::default(),
+            events: Default::default(),
+        }
+    }
+
+    /// Sets the storage adapter.
+    pub(crate) fn set_storage<S: StorageAdapter + Sync + Send + 'static>(&self, storage: S) {
+        self.storage.write().unwrap().replace(Box::new(storage));
+    }
+
+    /// Runs the callback with the storage adapter, waiting for the transaction of another thread to end.
+    pub(crate) fn with_storage<T, F: FnOnce(&Storage) -> crate::Result<T>>(&self, cb: F) -> crate::Result<T> {
+        let _storage_guard = self.storage_lock.lock();
+        let storage = self.storage.read().unwrap();
+        match storage.as_ref() {
+            Some(storage) => cb(storage),
+            None => Err(crate::WalletError::StorageAdapterNotSet(self.storage_path.clone())),
+        }
+    }
+
+    /// Runs the callback inside a storage transaction.
+    /// Every change made by the callback is committed at once if it succeeds, or rolled back if it returns an error.
+    pub(crate) fn transaction<T, F: FnOnce(&Storage) -> crate::Result<T>>(&self, cb: F) -> crate::Result<T> {
+        let _storage_guard = self.storage_lock.lock();
+        self.with_storage(|storage| {
+            storage.begin_transaction()?;
+            match cb(storage) {
+                Ok(value) => {
+                    storage.commit_transaction()?;
+                    Ok(value)
+                }
+                Err(e) => {
+                    // the callback error is more meaningful than a rollback failure
+                    let _ = storage.rollback_transaction();
+                    Err(e)
+                }
+            }
+        })
+    }
+
+    /// Sets the Stronghold instance and the password used to decrypt its snapshot.
+    pub(crate) fn set_stronghold(&self, stronghold: Stronghold, password: String) {
+        *self.stronghold.lock().unwrap() = StrongholdState::Loaded(LoadedStronghold {
+            stronghold,
+            password,
+            last_access: Instant::now(),
+        });
+    }
+
+    /// Unloads the Stronghold instance.
+    pub(crate) fn remove_stronghold(&self) {
+        *self.stronghold.lock().unwrap() = StrongholdState::NotLoaded;
+    }
+
+    /// Evicts the Stronghold instance; the password must be provided again to use it.
+    pub(crate) fn lock_stronghold(&self) {
+        let mut stronghold = self.stronghold.lock().unwrap();
+        if let StrongholdState::Loaded(_) = *stronghold {
+            *stronghold = StrongholdState::Locked;
+        }
+    }
+
+    /// Whether the Stronghold instance was locked and must be unlocked with the password.
+    pub(crate) fn is_stronghold_locked(&self) -> bool {
+        self.lock_expired_stronghold();
+        matches!(*self.stronghold.lock().unwrap(), StrongholdState::Locked)
+    }
+
+    /// Whether the Stronghold instance is loaded and unlocked.
+    pub(crate) fn is_stronghold_loaded(&self) -> bool {
+        self.lock_expired_stronghold();
+        matches!(*self.stronghold.lock().unwrap(), StrongholdState::Loaded(_))
+    }
+
+    /// Sets the inactivity period after which the Stronghold instance is locked.
+    pub(crate) fn set_stronghold_lock_timeout(self: &Arc<Self>, timeout: Duration) {
+        self.stronghold_lock_timeout.lock().unwrap().replace(timeout);
+        let mut watcher = self.stronghold_lock_watcher.lock().unwrap();
+        if watcher.is_none() {
+            watcher.replace(StrongholdLockWatcher::spawn(Arc::downgrade(self)));
+        }
+    }
+
+    /// Stops the thread that locks the Stronghold instance after its lock timeout.
+    /// The timeout is still checked when the instance is used.
+    pub(crate) fn stop_stronghold_lock_watcher(&self) {
+        let watcher = self.stronghold_lock_watcher.lock().unwrap().take();
+        if let Some(watcher) = watcher {
+            watcher.stop();
+        }
+    }
+
+    /// Releases the context from an account manager, stopping the lock watcher once no manager owns it.
+    /// The accounts may keep the context alive, but the watcher must not outlive the managers.
+    pub(crate) fn release_manager(&self) {
+        if self.managers.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.stop_stronghold_lock_watcher();
+        }
+    }
+
+    /// Locks the Stronghold instance if it wasn't used during the lock timeout.
+    fn lock_expired_stronghold(&self) {
+        let timeout = match *self.stronghold_lock_timeout.lock().unwrap() {
+            Some(timeout) => timeout,
+            None => return,
+        };
+        let mut stronghold = self.stronghold.lock().unwrap();
+        if let StrongholdState::Loaded(loaded) = &*stronghold {
+            if loaded.last_access.elapsed() >= timeout {
+                *stronghold = StrongholdState::Locked;
+            }
+        }
+    }
+
+    /// Runs the callback with the Stronghold instance.
+    pub(crate) fn with_stronghold<T, E: Into<crate::WalletError>, F: FnOnce(&Stronghold) -> Result<T, E>>(
+        &self,
+        cb: F,
+    ) -> crate::Result<T> {
+        self.with_stronghold_and_password(|stronghold, _| cb(stronghold))
+    }
+
+    /// Runs the callback with the Stronghold instance and its snapshot password.
+    pub(crate) fn with_stronghold_and_password<
+        T,
+        E: Into<crate::WalletError>,
+        F: FnOnce(&Stronghold, &str) -> Result<T, E>,
+    >(
+        &self,
+        cb: F,
+    ) -> crate::Result<T> {
+        self.lock_expired_stronghold();
+        let mut stronghold = self.stronghold.lock().unwrap();
+        match &mut *stronghold {
+            StrongholdState::Loaded(loaded) => {
+                loaded.last_access = Instant::now();
+                cb(&loaded.stronghold, &loaded.password).map_err(Into::into)
+            }
+            StrongholdState::Locked => Err(crate::WalletError::StrongholdLocked),
+            StrongholdState::NotLoaded => Err(crate::WalletError::StrongholdNotLoaded(self.storage_path.clone())),
+        }
+    }
+
+    /// Sets the signer interface for the given type on this wallet instance.
+    /// Signers registered with `signing::set_signer` are used if the instance doesn't have a signer for the type.
+    pub fn set_signer<S: Signer + Sync + Send + 'static>(
+        &self,
+        signer_type: SignerType,
+        signer: S,
+    ) -> crate::Result<()> {
+        write_registry(&self.signers)?.insert(signer_type, Box::new(signer));
+        self.clear_integrity_results();
+        Ok(())
+    }
+
+    /// Registers the signer interface for the given type on this wallet instance.
+    /// Fails if the instance already has a signer for the type.
+    pub fn register_signer<S: Signer + Sync + Send + 'static>(
+        &self,
+        signer_type: SignerType,
+        signer: S,
+    ) -> crate::Result<()> {
+        let mut signers = write_registry(&self.signers)?;
+        if signers.contains_key(&signer_type) {
+            return Err(crate::WalletError::SignerAlreadyRegistered(signer_type));
+        }
+        signers.insert(signer_type, Box::new(signer));
+        self.clear_integrity_results();
+        Ok(())
+    }
+
+    /// Unregisters the signer interface of the given type from this wallet instance.
+    pub fn unregister_signer(&self, signer_type: &SignerType) -> crate::Result<()> {
+        match write_registry(&self.signers)?.remove(signer_type) {
+            Some(_) => {
+                self.clear_integrity_results();
+                Ok(())
+            }
+            None => Err(crate::WalletError::SignerNotSet(signer_type.clone())),
+        }
+    }
+
+    /// Lists the signers available to this wallet instance and their capabilities,
+    /// including the default signers it doesn't override.
+    pub fn registered_signers(&self) -> crate::Result<Vec<(SignerType, SignerCapability)>> {
+        let mut signers: Vec<(SignerType, SignerCapability)> = read_registry(&self.signers)?
+            .iter()
+            .map(|(signer_type, signer)| (signer_type.clone(), signer.capability()))
+            .collect();
+        for (signer_type, capability) in crate::signing::registered_signers()? {
+            if !signers
+                .iter()
+                .any(|(registered_type, _)| registered_type == &signer_type)
+            {
+                signers.push((signer_type, capability));
+            }
+        }
+        Ok(signers)
+    }
+
+    /// Gets the capability of the signer used for the given type.
+    pub fn signer_capability(&self, signer_type: &SignerType) -> crate::Result<SignerCapability> {
+        self.with_signer(signer_type, |signer| Ok(signer.capability()))
+    }
+
+    /// Runs the callback with the signer interface of the given type.
+    pub(crate) fn with_signer<T, F: FnOnce(&BoxedSigner) -> crate::Result<T>>(
+        &self,
+        signer_type: &SignerType,
+        cb: F,
+    ) -> crate::Result<T> {
+        let signers = read_registry(&self.signers)?;
+        match signers.get(signer_type) {
+            Some(signer) => cb(signer),
+            None => crate::signing::with_default_signer(signer_type, cb),
+        }
+    }
+
+    /// Gets the client for the given options from the instance's client pool.
+    pub(crate) fn client(&self, options: &ClientOptions) -> Arc<RwLock<Client>> {
+        self.clients.get(options)
+    }
+
+    /// Records the result of an account integrity check.
+    pub(crate) fn set_integrity_result(&self, account_id: &AccountIdentifier, is_ok: bool) {
+        self.integrity_results
+            .write()
+            .unwrap()
+            .insert(account_id.clone(), is_ok);
+    }
+
+    /// Whether the last integrity check of the account found every checked address, or `None` if the account wasn't
+    /// checked since its signer was registered.
+    pub(crate) fn integrity_result(&self, account_id: &AccountIdentifier) -> Option<bool> {
+        self.integrity_results.read().unwrap().get(account_id).copied()
+    }
+
+    /// The accounts are checked again with the signers registered from now on.
+    fn clear_integrity_results(&self) {
+        self.integrity_results.write().unwrap().clear();
+    }
+}
+
+impl Drop for WalletContext {
+    fn drop(&mut self) {
+        self.stop_stronghold_lock_watcher();
+        remove(&self.storage_path);
+    }
+}
+
+/// The handle to the wallet instance of an account.
+/// It isn't serialized, so the accounts deserialized without it use the context registered for their storage path.
+#[derive(Clone, Default)]
+pub(crate) struct ContextHandle(Option<Arc<WalletContext>>);
+
+impl ContextHandle {
+    pub(crate) fn new(context: Arc<WalletContext>) -> Self {
+        Self(Some(context))
+    }
+
+    /// Gets the context of the handle, or the context registered for the storage path.
+    pub(crate) fn get(&self, storage_path: &Path) -> Option<Arc<WalletContext>> {
+        match &self.0 {
+            Some(context) => Some(context.clone()),
+            None => get(storage_path),
+        }
+    }
+}
+
+impl std::fmt::Debug for ContextHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ContextHandle")
+    }
+}
+
+// the handle doesn't take part in the account comparisons
+impl PartialEq for ContextHandle {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Creates the context owned by an account manager, taking over the context already registered for the storage
+/// path if there's one.
+/// The registry only keeps a weak reference, so the context is released with the manager and its accounts.
+/// The manager calls `WalletContext::release_manager` when it's dropped.
+pub(crate) fn register(storage_path: &Path) -> Arc<WalletContext> {
+    let mut contexts = CONTEXTS.get_or_init(Default::default).write().unwrap();
+    let context = contexts
+        .get(storage_path)
+        .and_then(RegisteredContext::get)
+        .unwrap_or_else(|| Arc::new(WalletContext::new(storage_path.to_path_buf())));
+    contexts.insert(
+        storage_path.to_path_buf(),
+        RegisteredContext::Owned(Arc::downgrade(&context)),
+    );
+    context.managers.fetch_add(1, Ordering::SeqCst);
+    context
+}
+
+/// Gets the context registered for the storage path, creating the default context of the path if needed.
+pub(crate) fn get_or_create(storage_path: &Path) -> Arc<WalletContext> {
+    let mut contexts = CONTEXTS.get_or_init(Default::default).write().unwrap();
+    if let Some(context) = contexts.get(storage_path).and_then(RegisteredContext::get) {
+        return context;
+    }
+    let context = Arc::new(WalletContext::new(storage_path.to_path_buf()));
+    contexts.insert(storage_path.to_path_buf(), RegisteredContext::Default(context.clone()));
+    context
+}
+
+/// Gets the context registered for the storage path.
+pub(crate) fn get(storage_path: &Path) -> Option<Arc<WalletContext>> {
+    let contexts = CONTEXTS.get_or_init(Default::default).read().unwrap();
+    contexts.get(storage_path).and_then(RegisteredContext::get)
+}
+
+/// Removes the registry entry of the storage path if its context was released.
+pub(crate) fn remove(storage_path: &Path) {
+    let mut contexts = CONTEXTS.get_or_init(Default::default).write().unwrap();
+    if let Some(RegisteredContext::Owned(context)) = contexts.get(storage_path) {
+        if context.upgrade().is_none() {
+            contexts.remove(storage_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WalletContext;
+    use crate::{
+        account::{Account, AccountIdentifier},
+        signing::{Signer, SignerCapability, SignerType, TransactionInput},
+        storage::memory::MemoryStorageAdapter,
+        WalletError,
+    };
+    use iota::message::prelude::Ed25519Address;
+    use std::{path::PathBuf, sync::Arc, thread, time::Duration};
+
+    struct NoopSigner;
+
+    impl Signer for NoopSigner {
+        fn init_account(&self, _account: &Account, _mnemonic: Option<String>) -> crate::Result<String> {
+            Ok(hex::encode([0; 32]))
+        }
+
+        fn generate_address(&self, _account: &Account, _index: usize, _internal: bool) -> crate::Result<iota::Address> {
+            Ok(iota::Address::Ed25519(Ed25519Address::new([0; 32])))
+        }
+
+        fn sign_message(
+            &self,
+            _account: &Account,
+            _essence: &iota::TransactionEssence,
+            _inputs: &mut Vec<TransactionInput>,
+        ) -> crate::Result<Vec<iota::UnlockBlock>> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn isolated_transactions() {
+        let context = Arc::new(WalletContext::new(PathBuf::from(
+            "./example-database/isolated-transactions",
+        )));
+        context.set_storage(MemoryStorageAdapter::new());
+        let transaction_id = AccountIdentifier::Id("transaction".to_string());
+        let writer_id = AccountIdentifier::Id("writer".to_string());
+
+        let mut writer = None;
+        let result: crate::Result<()> = context.transaction(|storage| {
+            storage.set(&transaction_id, "{}".to_string())?;
+            let context = context.clone();
+            let writer_id = writer_id.clone();
+            writer.replace(thread::spawn(move || {
+                context.with_storage(|storage| storage.set(&writer_id, "{}".to_string()))
+            }));
+            // the write of the other thread waits for the transaction to end, so the rollback doesn't discard it
+            thread::sleep(Duration::from_millis(100));
+            assert!(storage.get(&writer_id).is_err());
+            Err(WalletError::AccountNotFound)
+        });
+        assert!(result.is_err());
+        writer.unwrap().join().unwrap().unwrap();
+
+        context
+            .with_storage(|storage| {
+                assert!(storage.get(&transaction_id).is_err());
+                assert!(storage.get(&writer_id).is_ok());
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn uninitialised_lookups() {
+        let storage_path = PathBuf::from("./example-database/uninitialised");
+        assert!(matches!(
+            crate::storage::with_adapter(&storage_path, |storage| storage.get_all()),
+            Err(WalletError::StorageAdapterNotSet(_))
+        ));
+        assert!(matches!(
+            crate::with_stronghold_from_path(&storage_path, |stronghold| stronghold.record_list()),
+            Err(WalletError::StrongholdNotLoaded(_))
+        ));
+        assert!(matches!(
+            crate::signing::with_signer(&storage_path, &SignerType::Custom("unknown".to_string()), |_| Ok(())),
+            Err(WalletError::SignerNotSet(_))
+        ));
+    }
+
+    #[test]
+    fn isolated_signers() {
+        let context = super::get_or_create(&PathBuf::from("./example-database/isolated-signers"));
+        let other_storage_path = PathBuf::from("./example-database/other-isolated-signers");
+        super::get_or_create(&other_storage_path);
+
+        context
+            .set_signer(SignerType::Custom("custom".to_string()), NoopSigner)
+            .unwrap();
+        assert!(context
+            .with_signer(&SignerType::Custom("custom".to_string()), |_| Ok(()))
+            .is_ok());
+        assert!(
+            crate::signing::with_signer(&other_storage_path, &SignerType::Custom("custom".to_string()), |_| Ok(
+                ()
+            ))
+            .is_err()
+        );
+    }
+    #[test]
+    fn release_owned_context() {
+        let storage_path = PathBuf::from("./example-database/owned-context");
+        let context = super::register(&storage_path);
+        assert!(Arc::ptr_eq(&context, &super::get(&storage_path).unwrap()));
+        // the default context of the path is taken over by the owner
+        let default_storage_path = PathBuf::from("./example-database/default-context");
+        let default_context = super::get_or_create(&default_storage_path);
+        assert!(Arc::ptr_eq(&default_context, &super::register(&default_storage_path)));
+
+        drop(context);
+        assert!(super::get(&storage_path).is_none());
+        assert!(super::CONTEXTS
+            .get()
+            .unwrap()
+            .read()
+            .unwrap()
+            .get(&storage_path)
+            .is_none());
+    }
+
+    #[test]
+    fn stop_stronghold_lock_watcher() {
+        let context = super::register(&PathBuf::from("./example-database/lock-watcher"));
+        context.set_stronghold_lock_timeout(std::time::Duration::from_secs(60));
+        assert!(context.stronghold_lock_watcher.lock().unwrap().is_some());
+        // returns once the watcher thread exited
+        context.stop_stronghold_lock_watcher();
+        assert!(context.stronghold_lock_watcher.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn shared_lock_watcher() {
+        let storage_path = PathBuf::from("./example-database/shared-lock-watcher");
+        let context = super::register(&storage_path);
+        let other_context = super::register(&storage_path);
+        assert!(Arc::ptr_eq(&context, &other_context));
+        context.set_stronghold_lock_timeout(std::time::Duration::from_secs(60));
+
+        // the watcher runs until the last manager releases the context
+        other_context.release_manager();
+        assert!(context.stronghold_lock_watcher.lock().unwrap().is_some());
+        context.release_manager();
+        assert!(context.stronghold_lock_watcher.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn signer_registry() {
+        let context = super::get_or_create(&PathBuf::from("./example-database/signer-registry"));
+        let signer_type = SignerType::Custom("registry".to_string());
+
+        context.register_signer(signer_type.clone(), NoopSigner).unwrap();
+        assert!(matches!(
+            context.register_signer(signer_type.clone(), NoopSigner),
+            Err(WalletError::SignerAlreadyRegistered(_))
+        ));
+        let signers = context.registered_signers().unwrap();
+        assert!(signers.contains(&(signer_type.clone(), SignerCapability::SignTransactions)));
+        assert!(signers.contains(&(SignerType::WatchOnly, SignerCapability::DeriveOnly)));
+        assert_eq!(
+            context.signer_capability(&SignerType::WatchOnly).unwrap(),
+            SignerCapability::DeriveOnly
+        );
+
+        context.unregister_signer(&signer_type).unwrap();
+        assert!(matches!(
+            context.unregister_signer(&signer_type),
+            Err(WalletError::SignerNotSet(_))
+        ));
+        assert!(matches!(
+            context.signer_capability(&signer_type),
+            Err(WalletError::SignerNotSet(_))
+        ));
+    }
+}
@@ -8,6 +8,7 @@ use once_cell::sync::Lazy;
 use serde::Serialize;
 use std::{
     ops::Deref,
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 
@@ -97,27 +98,105 @@ struct TransactionConfirmationChangeEventHandler {
     on_event: Box<dyn Fn(&TransactionConfirmationChangeEvent<'_>) + Send>,
 }
 
-type BalanceListeners = Arc<Mutex<Vec<BalanceEventHandler>>>;
-type TransactionListeners = Arc<Mutex<Vec<TransactionEventHandler>>>;
-type TransactionConfirmationChangeListeners = Arc<Mutex<Vec<TransactionConfirmationChangeEventHandler>>>;
 type ErrorListeners = Arc<Mutex<Vec<ErrorHandler>>>;
 
-/// Gets the balance change listeners array.
-fn balance_listeners() -> &'static BalanceListeners {
-    static LISTENERS: Lazy<BalanceListeners> = Lazy::new(Default::default);
-    &LISTENERS
+/// The event listeners of a wallet instance.
+///
+/// The listeners added with the free functions of this module are notified of the events of every wallet instance.
+#[derive(Default)]
+pub struct EventBus {
+    balance_listeners: Mutex<Vec<BalanceEventHandler>>,
+    transaction_listeners: Mutex<Vec<TransactionEventHandler>>,
+    transaction_confirmation_change_listeners: Mutex<Vec<TransactionConfirmationChangeEventHandler>>,
 }
 
-/// Gets the transaction listeners array.
-fn transaction_listeners() -> &'static TransactionListeners {
-    static LISTENERS: Lazy<TransactionListeners> = Lazy::new(Default::default);
-    &LISTENERS
+impl EventBus {
+    /// Listen to balance changes.
+    pub fn on_balance_change<F: Fn(&BalanceEvent<'_>) + Send + 'static>(&self, cb: F) {
+        let mut l = self
+            .balance_listeners
+            .lock()
+            .expect("Failed to lock balance_listeners: on_balance_change()");
+        l.push(BalanceEventHandler { on_event: Box::new(cb) })
+    }
+
+    /// Listen to new messages.
+    pub fn on_new_transaction<F: Fn(&TransactionEvent<'_>) + Send + 'static>(&self, cb: F) {
+        self.add_transaction_listener(TransactionEventType::NewTransaction, cb);
+    }
+
+    /// Listen to transaction confirmation state change.
+    pub fn on_confirmation_state_change<F: Fn(&TransactionConfirmationChangeEvent<'_>) + Send + 'static>(&self, cb: F) {
+        let mut l = self
+            .transaction_confirmation_change_listeners
+            .lock()
+            .expect("Failed to lock transaction_confirmation_change_listeners: on_confirmation_state_change()");
+        l.push(TransactionConfirmationChangeEventHandler { on_event: Box::new(cb) })
+    }
+
+    /// Listen to transaction reattachment.
+    pub fn on_reattachment<F: Fn(&TransactionEvent<'_>) + Send + 'static>(&self, cb: F) {
+        self.add_transaction_listener(TransactionEventType::Reattachment, cb);
+    }
+
+    /// Listen to transaction broadcast.
+    pub fn on_broadcast<F: Fn(&TransactionEvent<'_>) + Send + 'static>(&self, cb: F) {
+        self.add_transaction_listener(TransactionEventType::Broadcast, cb);
+    }
+
+    /// Adds a transaction-related event listener.
+    fn add_transaction_listener<F: Fn(&TransactionEvent<'_>) + Send + 'static>(
+        &self,
+        event_type: TransactionEventType,
+        cb: F,
+    ) {
+        let mut l = self
+            .transaction_listeners
+            .lock()
+            .expect("Failed to lock transaction_listeners: add_transaction_listener()");
+        l.push(TransactionEventHandler {
+            event_type,
+            on_event: Box::new(cb),
+        })
+    }
+
+    fn emit_balance_change(&self, event: &BalanceEvent<'_>) {
+        let listeners = self
+            .balance_listeners
+            .lock()
+            .expect("Failed to lock balance_listeners: emit_balance_change()");
+        for listener in listeners.deref() {
+            (listener.on_event)(event);
+        }
+    }
+
+    fn emit_transaction_event(&self, event_type: &TransactionEventType, event: &TransactionEvent<'_>) {
+        let listeners = self
+            .transaction_listeners
+            .lock()
+            .expect("Failed to lock transaction_listeners: emit_transaction_event()");
+        for listener in listeners.deref() {
+            if &listener.event_type == event_type {
+                (listener.on_event)(event);
+            }
+        }
+    }
+
+    fn emit_confirmation_state_change(&self, event: &TransactionConfirmationChangeEvent<'_>) {
+        let listeners = self
+            .transaction_confirmation_change_listeners
+            .lock()
+            .expect("Failed to lock transaction_confirmation_change_listeners: emit_confirmation_state_change()");
+        for listener in listeners.deref() {
+            (listener.on_event)(event);
+        }
+    }
 }
 
-/// Gets the transaction confirmation change listeners array.
-fn transaction_confirmation_change_listeners() -> &'static TransactionConfirmationChangeListeners {
-    static LISTENERS: Lazy<TransactionConfirmationChangeListeners> = Lazy::new(Default::default);
-    &LISTENERS
+/// Gets the event listeners shared by every wallet instance.
+fn default_bus() -> &'static EventBus {
+    static BUS: Lazy<EventBus> = Lazy::new(Default::default);
+    &BUS
 }
 
 /// Gets the balance change listeners array.
@@ -126,96 +205,81 @@ fn error_listeners() -> &'static ErrorListeners {
     &LISTENERS
 }
 
+/// Runs the callback with the event bus of the wallet instance associated with the storage path and the default one.
+fn with_buses<F: Fn(&EventBus)>(storage_path: &PathBuf, cb: F) {
+    if let Some(context) = crate::context::get(storage_path) {
+        cb(context.events());
+    }
+    cb(default_bus());
+}
+
 /// Listen to balance changes.
 pub fn on_balance_change<F: Fn(&BalanceEvent<'_>) + Send + 'static>(cb: F) {
-    let mut l = balance_listeners()
-        .lock()
-        .expect("Failed to lock balance_listeners: on_balance_change()");
-    l.push(BalanceEventHandler { on_event: Box::new(cb) })
+    default_bus().on_balance_change(cb)
 }
 
 /// Emits a balance change event.
-pub(crate) fn emit_balance_change(account_id: &AccountIdentifier, address: &Address, balance: u64) {
-    let listeners = balance_listeners()
-        .lock()
-        .expect("Failed to lock balance_listeners: emit_balance_change()");
+pub(crate) fn emit_balance_change(
+    storage_path: &PathBuf,
+    account_id: &AccountIdentifier,
+    address: &Address,
+    balance: u64,
+) {
     let event = BalanceEvent {
         account_id,
         address: &address,
         balance,
     };
-    for listener in listeners.deref() {
-        (listener.on_event)(&event);
-    }
+    with_buses(storage_path, |bus| bus.emit_balance_change(&event));
 }
 
 /// Emits a transaction-related event.
 pub(crate) fn emit_transaction_event(
+    storage_path: &PathBuf,
     event_type: TransactionEventType,
     account_id: &AccountIdentifier,
     message: &Message,
 ) {
-    let listeners = transaction_listeners()
-        .lock()
-        .expect("Failed to lock balance_listeners: emit_balance_change()");
     let event = TransactionEvent {
         account_id,
         message: &message,
     };
-    for listener in listeners.deref() {
-        if listener.event_type == event_type {
-            (listener.on_event)(&event);
-        }
-    }
+    with_buses(storage_path, |bus| bus.emit_transaction_event(&event_type, &event));
 }
 
 /// Emits a transaction confirmation state change event.
-pub(crate) fn emit_confirmation_state_change(account_id: &AccountIdentifier, message: &Message, confirmed: bool) {
-    let listeners = transaction_confirmation_change_listeners()
-        .lock()
-        .expect("Failed to lock transaction_confirmation_change_listeners: emit_confirmation_state_change()");
+pub(crate) fn emit_confirmation_state_change(
+    storage_path: &PathBuf,
+    account_id: &AccountIdentifier,
+    message: &Message,
+    confirmed: bool,
+) {
     let event = TransactionConfirmationChangeEvent {
         account_id,
         message: &message,
         confirmed,
     };
-    for listener in listeners.deref() {
-        (listener.on_event)(&event);
-    }
-}
-
-/// Adds a transaction-related event listener.
-fn add_transaction_listener<F: Fn(&TransactionEvent<'_>) + Send + 'static>(event_type: TransactionEventType, cb: F) {
-    let mut l = transaction_listeners()
-        .lock()
-        .expect("Failed to lock transaction_listeners: add_transaction_listener()");
-    l.push(TransactionEventHandler {
-        event_type,
-        on_event: Box::new(cb),
-    })
+    with_buses(storage_path, |bus| bus.emit_confirmation_state_change(&event));
 }
 
 /// Listen to new messages.
 pub fn on_new_transaction<F: Fn(&TransactionEvent<'_>) + Send + 'static>(cb: F) {
-    add_transaction_listener(TransactionEventType::NewTransaction, cb);
+    default_bus().on_new_transaction(cb);
 }
 
 /// Listen to transaction confirmation state change.
 pub fn on_confirmation_state_change<F: Fn(&TransactionConfirmationChangeEvent<'_>) + Send + 'static>(cb: F) {
-    let mut l = transaction_confirmation_change_listeners()
-        .lock()
-        .expect("Failed to lock transaction_confirmation_change_listeners: on_confirmation_state_change()");
-    l.push(TransactionConfirmationChangeEventHandler { on_event: Box::new(cb) })
+    default_bus().on_confirmation_state_change(cb);
 }
 
 /// Listen to transaction reattachment.
 pub fn on_reattachment<F: Fn(&TransactionEvent<'_>) + Send + 'static>(cb: F) {
-    add_transaction_listener(TransactionEventType::Reattachment, cb);
+    default_bus().on_reattachment(cb);
 }
 
 /// Listen to transaction broadcast.
 pub fn on_broadcast<F: Fn(&TransactionEvent<'_>) + Send + 'static>(cb: F) {
-    add_transaction_listener(TransactionEventType::Broadcast, cb);
+    default_bus().on_broadcast(cb);
 }
 
 pub(crate) fn emit_error(error: &crate::WalletError) {
@@ -244,6 +308,7 @@ mod tests {
     };
     use iota::message::prelude::Ed25519Address;
     use rusty_fork::rusty_fork_test;
+    use std::path::PathBuf;
 
     fn _create_and_drop_error() {
         let _ = crate::WalletError::GenericError(anyhow::anyhow!("generic error"));
@@ -268,6 +333,7 @@ mod tests {
         });
 
         emit_balance_change(
+            &PathBuf::from("./example-database/events"),
             &AccountIdentifier::Id(hex::encode([1; 32])),
             &AddressBuilder::new()
                 .address(IotaAddress::Ed25519(Ed25519Address::new([0; 32])))
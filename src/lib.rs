@@ -14,12 +14,21 @@ pub mod account_manager;
 pub mod actor;
 /// The address module.
 pub mod address;
+/// The encrypted backup module.
+pub mod backup;
 /// The client module.
 pub mod client;
+/// The wallet context module.
+pub mod context;
 /// The event module.
 pub mod event;
 /// The message module.
 pub mod message;
+/// BIP39 mnemonic generation and validation.
+pub mod mnemonic;
+/// A local node serving a scripted ledger, to test the wallet without network access.
+#[cfg(any(test, feature = "mock-node"))]
+pub mod mock_node;
 /// The monitor module.
 pub mod monitor;
 pub(crate) mod serde;
@@ -32,16 +41,10 @@ pub mod storage;
 pub type Result<T> = std::result::Result<T, WalletError>;
 pub use chrono::prelude::{DateTime, Utc};
 use once_cell::sync::OnceCell;
-use std::{
-    collections::HashMap,
-    path::PathBuf,
-    sync::{Arc, Mutex},
-};
+use std::{path::PathBuf, sync::Mutex};
 use stronghold::Stronghold;
 use tokio::runtime::Runtime;
 
-static STRONGHOLD_INSTANCE: OnceCell<Arc<Mutex<HashMap<PathBuf, Stronghold>>>> = OnceCell::new();
-
 /// The wallet error type.
 #[derive(Debug, thiserror::Error)]
 pub enum WalletError {
@@ -125,6 +128,74 @@ pub enum WalletError {
     /// the address must belong to the account.
     #[error("the remainder value address doesn't belong to the account")]
     InvalidRemainderValueAddress,
+    /// The stored record was written by a newer version of the library.
+    #[error("storage version `{0}` isn't supported")]
+    UnsupportedStorageVersion(u64),
+    /// The storage is encrypted and the password wasn't set.
+    #[error("the storage is encrypted, the password must be set")]
+    StorageIsEncrypted,
+    /// The storage password is invalid.
+    #[error("invalid storage password")]
+    InvalidStoragePassword,
+    /// The storage adapter wasn't set for the storage path.
+    #[error("storage adapter not set for path `{0:?}`")]
+    StorageAdapterNotSet(PathBuf),
+    /// The Stronghold snapshot wasn't loaded for the storage path.
+    #[error("Stronghold not loaded for path `{0:?}`, the password must be set")]
+    StrongholdNotLoaded(PathBuf),
+    /// The signer wasn't set for the signer type.
+    #[error("signer not set for type `{0:?}`")]
+    SignerNotSet(signing::SignerType),
+    /// The Stronghold snapshot password is invalid.
+    #[error("invalid Stronghold password")]
+    InvalidStrongholdPassword,
+    /// The Stronghold snapshot was locked; the password must be provided again.
+    #[error("Stronghold is locked, the password must be provided again")]
+    StrongholdLocked,
+    /// The backup file is invalid, corrupted or the backup password is wrong.
+    #[error("invalid backup: {0}")]
+    InvalidBackup(String),
+    /// The external signer failed or answered with an invalid response.
+    #[error("external signer error: {0}")]
+    ExternalSignerError(String),
+    /// The account is watch-only, so it can't sign transactions or derive new addresses.
+    #[error("the account is watch-only, it can't sign transactions or derive new addresses")]
+    WatchOnlyAccount,
+    /// The mnemonic has unknown words or an invalid checksum.
+    #[error("invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+    /// A signer is already registered for the signer type.
+    #[error("a signer is already registered for type `{0:?}`")]
+    SignerAlreadyRegistered(signing::SignerType),
+    /// The signer the account is migrated to derives a different address.
+    #[error("the signer derives a different address than `{0}`")]
+    SignerAddressMismatch(String),
+    /// The account signer derives different addresses than the stored ones, so the account can't sign.
+    #[error("the signer of account `{0:?}` derives different addresses than the stored ones")]
+    AccountIntegrityMismatch(account::AccountIdentifier),
+    /// The transfer needs more outputs than a transaction can hold.
+    #[error("the transfer needs {0} outputs, but a transaction can hold at most {1}")]
+    TooManyOutputs(usize, usize),
+    /// The selected inputs exceed the transfer inputs limit.
+    #[error("the transfer needs {0} inputs, but the limit is {1}")]
+    TooManyInputs(usize, usize),
+    /// A transfer input option or a frozen input is invalid.
+    #[error("invalid transfer input: {0}")]
+    InvalidTransferInput(String),
+    /// The transfer has no outputs.
+    #[error("the transfer has no outputs")]
+    EmptyTransfer,
+    /// The sum of the transfer output amounts overflows.
+    #[error("the transfer amount overflows")]
+    AmountOverflow,
+    /// The account index is used by another account.
+    #[error("account index `{index}` is already used by account `{alias}`")]
+    AccountIndexInUse {
+        /// the account index.
+        index: usize,
+        /// the alias of the account using the index.
+        alias: String,
+    },
 }
 
 impl Drop for WalletError {
@@ -133,22 +204,42 @@ impl Drop for WalletError {
     }
 }
 
-pub(crate) fn init_stronghold(stronghold_path: &PathBuf, stronghold: Stronghold) {
-    let mut stronghold_map = STRONGHOLD_INSTANCE.get_or_init(Default::default).lock().unwrap();
-    stronghold_map.insert(stronghold_path.to_path_buf(), stronghold);
+pub(crate) fn init_stronghold(stronghold_path: &PathBuf, stronghold: Stronghold, password: String) {
+    context::get_or_create(stronghold_path).set_stronghold(stronghold, password);
 }
 
 pub(crate) fn remove_stronghold(stronghold_path: PathBuf) {
-    let mut stronghold_map = STRONGHOLD_INSTANCE.get_or_init(Default::default).lock().unwrap();
-    stronghold_map.remove(&stronghold_path);
+    if let Some(context) = context::get(&stronghold_path) {
+        context.remove_stronghold();
+    }
+}
+
+pub(crate) fn with_stronghold_from_path<
+    T,
+    E: Into<WalletError>,
+    F: FnOnce(&Stronghold) -> std::result::Result<T, E>,
+>(
+    path: &PathBuf,
+    cb: F,
+) -> crate::Result<T> {
+    match context::get(path) {
+        Some(context) => context.with_stronghold(cb),
+        None => Err(WalletError::StrongholdNotLoaded(path.clone())),
+    }
 }
 
-pub(crate) fn with_stronghold_from_path<T, F: FnOnce(&Stronghold) -> T>(path: &PathBuf, cb: F) -> T {
-    let stronghold_map = STRONGHOLD_INSTANCE.get_or_init(Default::default).lock().unwrap();
-    if let Some(stronghold) = stronghold_map.get(path) {
-        cb(stronghold)
-    } else {
-        panic!("should initialize stronghold instance before using it")
+/// Runs the callback with the Stronghold instance and the password of its snapshot.
+pub(crate) fn with_stronghold_and_password_from_path<
+    T,
+    E: Into<WalletError>,
+    F: FnOnce(&Stronghold, &str) -> std::result::Result<T, E>,
+>(
+    path: &PathBuf,
+    cb: F,
+) -> crate::Result<T> {
+    match context::get(path) {
+        Some(context) => context.with_stronghold_and_password(cb),
+        None => Err(WalletError::StrongholdNotLoaded(path.clone())),
     }
 }
 
@@ -160,7 +251,12 @@ pub(crate) fn block_on<C: futures::Future>(cb: C) -> C::Output {
 
 #[cfg(test)]
 mod test_utils {
-    use super::account_manager::AccountManager;
+    use super::{
+        account_manager::AccountManager,
+        mock_node::{MockLedger, MockNode, MockSigner},
+        signing::SignerType,
+        storage::memory::MemoryStorageAdapter,
+    };
     use once_cell::sync::OnceCell;
     use rand::{thread_rng, Rng};
     use std::path::PathBuf;
@@ -176,4 +272,24 @@ mod test_utils {
             manager
         })
     }
+
+    /// The signer type of the accounts created with `get_mock_account_manager`.
+    pub fn mock_signer_type() -> SignerType {
+        SignerType::Custom("mock".to_string())
+    }
+
+    /// Creates an account manager backed by the in-memory storage and the mock signer, and starts a mock node serving
+    /// the given ledger.
+    pub fn get_mock_account_manager(ledger: MockLedger) -> (AccountManager, MockNode) {
+        let storage_path: String = thread_rng().gen_ascii_chars().take(10).collect();
+        let storage_path = PathBuf::from(format!("./example-database/{}", storage_path));
+
+        let manager = AccountManager::with_storage_adapter(storage_path, MemoryStorageAdapter::new()).unwrap();
+        manager
+            .context()
+            .set_signer(mock_signer_type(), MockSigner::default())
+            .unwrap();
+        let node = MockNode::start(ledger).unwrap();
+        (manager, node)
+    }
 }
@@ -1,7 +1,10 @@
 // Copyright 2020 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::address::{Address, IotaAddress};
+use crate::{
+    account::CoinSelection,
+    address::{Address, InputSource, IotaAddress, OutputId},
+};
 use chrono::prelude::{DateTime, Utc};
 use getset::{Getters, Setters};
 pub use iota::message::prelude::{Message as IotaMessage, MessageId, Output, Payload};
@@ -56,42 +59,177 @@ pub enum RemainderValueStrategy {
     AccountAddress(IotaAddress),
 }
 
-/// A transfer to make a transaction.
-#[derive(Debug, Clone, Deserialize)]
-pub struct Transfer {
-    /// The transfer value.
+/// The maximum number of outputs of a transaction, including the remainder output.
+/// A protocol constant of the transaction payload format; nodes don't report it in their info.
+pub const MAX_OUTPUT_COUNT: usize = 127;
+/// The maximum number of inputs of a transaction.
+/// A protocol constant of the transaction payload format; nodes don't report it in their info.
+pub const MAX_INPUT_COUNT: usize = 127;
+
+/// An output of a transfer: the value sent to an address.
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct TransferOutput {
+    /// The output value.
     pub(crate) amount: u64,
-    /// The transfer address.
+    /// The output address.
     #[serde(with = "crate::serde::iota_address_serde")]
     pub(crate) address: IotaAddress,
+}
+
+impl TransferOutput {
+    /// Initialises a new output to the given address.
+    pub fn new(address: IotaAddress, amount: u64) -> Self {
+        Self { address, amount }
+    }
+}
+
+/// The outputs of a serialized transfer: a single `address` and `amount` pair or a list of `outputs`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedTransferOutputs {
+    Single(TransferOutput),
+    Batch { outputs: Vec<TransferOutput> },
+}
+
+#[derive(Deserialize)]
+struct SerializedTransfer {
+    #[serde(flatten)]
+    outputs: SerializedTransferOutputs,
+    data: Option<String>,
+    remainder_value_strategy: RemainderValueStrategy,
+    #[serde(default)]
+    coin_selection: CoinSelection,
+    max_inputs: Option<usize>,
+    #[serde(default)]
+    inputs: Vec<OutputId>,
+    #[serde(default)]
+    include: Vec<InputSource>,
+    #[serde(default)]
+    exclude: Vec<InputSource>,
+}
+
+impl From<SerializedTransfer> for Transfer {
+    fn from(transfer: SerializedTransfer) -> Self {
+        let outputs = match transfer.outputs {
+            SerializedTransferOutputs::Single(output) => vec![output],
+            SerializedTransferOutputs::Batch { outputs } => outputs,
+        };
+        Self {
+            outputs,
+            data: transfer.data,
+            remainder_value_strategy: transfer.remainder_value_strategy,
+            coin_selection: transfer.coin_selection,
+            max_inputs: transfer.max_inputs,
+            inputs: transfer.inputs,
+            include: transfer.include,
+            exclude: transfer.exclude,
+        }
+    }
+}
+
+/// A transfer to make a transaction.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "SerializedTransfer")]
+pub struct Transfer {
+    /// The transfer outputs.
+    pub(crate) outputs: Vec<TransferOutput>,
     /// (Optional) transfer data.
     pub(crate) data: Option<String>,
     /// The strategy to use for the remainder value.
     pub(crate) remainder_value_strategy: RemainderValueStrategy,
+    /// The strategy to use for the input selection.
+    pub(crate) coin_selection: CoinSelection,
+    /// The maximum number of transaction inputs.
+    pub(crate) max_inputs: Option<usize>,
+    /// The outputs to spend, skipping the coin selection if not empty.
+    pub(crate) inputs: Vec<OutputId>,
+    /// The addresses and outputs the coin selection is restricted to, if not empty.
+    pub(crate) include: Vec<InputSource>,
+    /// The addresses and outputs the coin selection never picks.
+    pub(crate) exclude: Vec<InputSource>,
 }
 
 impl Transfer {
     /// Initialises a new transfer to the given address.
     pub fn new(address: IotaAddress, amount: u64) -> Self {
+        Self::batch(vec![TransferOutput::new(address, amount)])
+    }
+
+    /// Initialises a new transfer sending each output value to its address in a single transaction.
+    pub fn batch(outputs: Vec<TransferOutput>) -> Self {
         Self {
-            address,
-            amount,
+            outputs,
             data: None,
             remainder_value_strategy: RemainderValueStrategy::ChangeAddress,
+            coin_selection: CoinSelection::default(),
+            max_inputs: None,
+            inputs: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
         }
     }
 
+    /// Adds an output to the transfer.
+    pub fn output(mut self, address: IotaAddress, amount: u64) -> Self {
+        self.outputs.push(TransferOutput::new(address, amount));
+        self
+    }
+
     /// Sets the remainder value strategy for the transfer.
     pub fn remainder_value_strategy(mut self, strategy: RemainderValueStrategy) -> Self {
         self.remainder_value_strategy = strategy;
         self
     }
 
+    /// Sets the coin selection strategy for the transfer.
+    pub fn coin_selection(mut self, coin_selection: CoinSelection) -> Self {
+        self.coin_selection = coin_selection;
+        self
+    }
+
+    /// Limits the number of transaction inputs, at most `MAX_INPUT_COUNT`.
+    pub fn max_inputs(mut self, max_inputs: usize) -> Self {
+        self.max_inputs = Some(max_inputs);
+        self
+    }
+
+    /// Spends exactly the given outputs, bypassing the coin selection, e.g. the inputs of a `TransferPreview`.
+    pub fn inputs(mut self, inputs: Vec<OutputId>) -> Self {
+        self.inputs = inputs;
+        self
+    }
+
+    /// Restricts the coin selection to the given address or output. Can be called multiple times.
+    pub fn include(mut self, source: InputSource) -> Self {
+        self.include.push(source);
+        self
+    }
+
+    /// Prevents the coin selection from spending the given address or output.
+    pub fn exclude(mut self, source: InputSource) -> Self {
+        self.exclude.push(source);
+        self
+    }
+
     /// (Optional) transfer data.
     pub fn data(mut self, data: String) -> Self {
         self.data = Some(data);
         self
     }
+
+    /// The total value of the transfer outputs, failing if it overflows.
+    pub(crate) fn amount(&self) -> crate::Result<u64> {
+        self.outputs
+            .iter()
+            .try_fold(0u64, |total, output| total.checked_add(output.amount))
+            .ok_or(crate::WalletError::AmountOverflow)
+    }
+
+    /// Whether one of the transfer outputs sends value to the address.
+    pub(crate) fn sends_to(&self, address: &IotaAddress) -> bool {
+        self.outputs.iter().any(|output| &output.address == address)
+    }
 }
 
 /// Possible Value units.
@@ -0,0 +1,112 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use bip39::{Language, Mnemonic, MnemonicType};
+use serde::{Deserialize, Serialize};
+
+/// The BIP39 wordlists.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MnemonicLanguage {
+    /// English wordlist.
+    English,
+    /// Simplified chinese wordlist.
+    ChineseSimplified,
+    /// Traditional chinese wordlist.
+    ChineseTraditional,
+    /// French wordlist.
+    French,
+    /// Italian wordlist.
+    Italian,
+    /// Japanese wordlist.
+    Japanese,
+    /// Korean wordlist.
+    Korean,
+    /// Spanish wordlist.
+    Spanish,
+}
+
+impl Default for MnemonicLanguage {
+    fn default() -> Self {
+        Self::English
+    }
+}
+
+impl MnemonicLanguage {
+    const ALL: [MnemonicLanguage; 8] = [
+        Self::English,
+        Self::ChineseSimplified,
+        Self::ChineseTraditional,
+        Self::French,
+        Self::Italian,
+        Self::Japanese,
+        Self::Korean,
+        Self::Spanish,
+    ];
+}
+
+impl From<MnemonicLanguage> for Language {
+    fn from(language: MnemonicLanguage) -> Self {
+        match language {
+            MnemonicLanguage::English => Language::English,
+            MnemonicLanguage::ChineseSimplified => Language::ChineseSimplified,
+            MnemonicLanguage::ChineseTraditional => Language::ChineseTraditional,
+            MnemonicLanguage::French => Language::French,
+            MnemonicLanguage::Italian => Language::Italian,
+            MnemonicLanguage::Japanese => Language::Japanese,
+            MnemonicLanguage::Korean => Language::Korean,
+            MnemonicLanguage::Spanish => Language::Spanish,
+        }
+    }
+}
+
+/// Generates a mnemonic from `entropy_bits` bits of entropy (128, 160, 192, 224 or 256; 12 to 24 words).
+pub fn generate_mnemonic(entropy_bits: usize, language: MnemonicLanguage) -> crate::Result<String> {
+    let mnemonic_type = MnemonicType::for_key_size(entropy_bits)
+        .map_err(|_| crate::WalletError::InvalidMnemonic(format!("unsupported entropy size {}", entropy_bits)))?;
+    Ok(Mnemonic::new(mnemonic_type, language.into()).into_phrase())
+}
+
+/// Checks the words and the checksum of a mnemonic written on the given wordlist.
+pub fn validate_mnemonic(mnemonic: &str, language: MnemonicLanguage) -> crate::Result<()> {
+    Mnemonic::validate(mnemonic, language.into()).map_err(|e| crate::WalletError::InvalidMnemonic(e.to_string()))
+}
+
+/// Finds the wordlist of a valid mnemonic.
+pub fn mnemonic_language(mnemonic: &str) -> crate::Result<MnemonicLanguage> {
+    MnemonicLanguage::ALL
+        .iter()
+        .find(|language| validate_mnemonic(mnemonic, **language).is_ok())
+        .copied()
+        .ok_or_else(|| crate::WalletError::InvalidMnemonic("unknown words or invalid checksum".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_mnemonic, mnemonic_language, validate_mnemonic, MnemonicLanguage};
+    use crate::WalletError;
+
+    #[test]
+    fn generate_and_validate() {
+        for (entropy_bits, words) in &[(128, 12), (256, 24)] {
+            let mnemonic = generate_mnemonic(*entropy_bits, MnemonicLanguage::English).unwrap();
+            assert_eq!(mnemonic.split_whitespace().count(), *words);
+            validate_mnemonic(&mnemonic, MnemonicLanguage::English).unwrap();
+        }
+        assert!(matches!(
+            generate_mnemonic(100, MnemonicLanguage::English),
+            Err(WalletError::InvalidMnemonic(_))
+        ));
+
+        let mnemonic = generate_mnemonic(256, MnemonicLanguage::Spanish).unwrap();
+        assert_eq!(mnemonic_language(&mnemonic).unwrap(), MnemonicLanguage::Spanish);
+        assert!(validate_mnemonic(&mnemonic, MnemonicLanguage::English).is_err());
+
+        // the BIP39 test vector is valid, but every word being `abandon` breaks the checksum
+        let valid_mnemonic = format!("{} about", ["abandon"; 11].join(" "));
+        assert_eq!(mnemonic_language(&valid_mnemonic).unwrap(), MnemonicLanguage::English);
+        assert!(matches!(
+            validate_mnemonic(&["abandon"; 12].join(" "), MnemonicLanguage::English),
+            Err(WalletError::InvalidMnemonic(_))
+        ));
+    }
+}
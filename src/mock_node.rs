@@ -0,0 +1,592 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::Account,
+    address::IotaAddress,
+    client::{ClientOptions, ClientOptionsBuilder},
+    signing::{hash, signed_data_payload, DataSignature, Signer, SignerCapability, TransactionInput},
+};
+
+use bech32::FromBase32;
+use bee_signing_ext::{binary::ed25519, Signer as _};
+use futures::channel::oneshot;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use iota::{
+    message::prelude::{Ed25519Address, MessageId},
+    UnlockBlock,
+};
+use serde_json::{json, Value};
+use slip10::BIP32Path;
+use url::Url;
+
+use std::{
+    convert::{Infallible, TryInto},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+const TRANSACTION_PAYLOAD_TYPE: u64 = 0;
+
+/// The ledger inclusion state of a message on the mock ledger.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InclusionState {
+    /// The message isn't referenced by a milestone yet.
+    Pending,
+    /// The message is included on the ledger.
+    Included,
+    /// The message conflicts with the ledger.
+    Conflicting,
+}
+
+struct MockMessage {
+    id: [u8; 32],
+    parents: ([u8; 32], [u8; 32]),
+    data: Value,
+    inclusion_state: InclusionState,
+}
+
+struct MockOutput {
+    message_id: [u8; 32],
+    transaction_id: [u8; 32],
+    index: u16,
+    address: IotaAddress,
+    amount: u64,
+    is_spent: bool,
+}
+
+/// The scripted ledger served by the mock node.
+pub struct MockLedger {
+    messages: Vec<MockMessage>,
+    outputs: Vec<MockOutput>,
+    posted_inclusion_state: InclusionState,
+}
+
+impl Default for MockLedger {
+    fn default() -> Self {
+        Self {
+            messages: Vec::new(),
+            outputs: Vec::new(),
+            posted_inclusion_state: InclusionState::Included,
+        }
+    }
+}
+
+fn decode_id(id: &str) -> Option<[u8; 32]> {
+    hex::decode(id).ok()?.as_slice().try_into().ok()
+}
+
+/// Gets the hex encoded Ed25519 address.
+fn address_hex(address: &IotaAddress) -> String {
+    // the bech32 data is the address type byte followed by the address bytes
+    let decoded = bech32::decode(&address.to_bech32()).expect("invalid bech32 address");
+    let bytes = Vec::from_base32(&decoded.1).expect("invalid bech32 address");
+    hex::encode(&bytes[1..])
+}
+
+/// Parses an address given as bech32 or hex encoded Ed25519 address.
+fn parse_address(address: &str) -> Option<IotaAddress> {
+    match decode_id(address) {
+        Some(bytes) => Some(IotaAddress::Ed25519(Ed25519Address::new(bytes))),
+        None => crate::address::parse(address.to_string()).ok(),
+    }
+}
+
+fn not_found(message: &str) -> (StatusCode, Value) {
+    (
+        StatusCode::NOT_FOUND,
+        json!({ "error": { "code": "404", "message": message } }),
+    )
+}
+
+fn bad_request(message: &str) -> (StatusCode, Value) {
+    (
+        StatusCode::BAD_REQUEST,
+        json!({ "error": { "code": "400", "message": message } }),
+    )
+}
+
+impl MockLedger {
+    /// Initialises an empty ledger.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sends `amount` to the address with a new confirmed transaction, returning its message id.
+    pub fn fund(&mut self, address: &IotaAddress, amount: u64) -> MessageId {
+        let funding_index = self.messages.len() as u64;
+        let essence = json!({
+            "type": 0,
+            "inputs": [{
+                "type": 0,
+                "transactionId": hex::encode(hash(&funding_index.to_le_bytes())),
+                "transactionOutputIndex": 0,
+            }],
+            "outputs": [{
+                "type": 0,
+                "address": { "type": 1, "address": address_hex(address) },
+                "amount": amount,
+            }],
+            "payload": null,
+        });
+        let payload = json!({
+            "type": TRANSACTION_PAYLOAD_TYPE,
+            "essence": essence,
+            "unlockBlocks": [{
+                "type": 0,
+                "signature": {
+                    "type": 1,
+                    "publicKey": hex::encode([0; 32]),
+                    "signature": hex::encode(&[0; 64][..]),
+                },
+            }],
+        });
+        let (parent1, parent2) = self.tips();
+        let data = json!({
+            "networkId": "0",
+            "parent1MessageId": hex::encode(parent1),
+            "parent2MessageId": hex::encode(parent2),
+            "payload": payload,
+            "nonce": "0",
+        });
+        let id = self.add_message(data, InclusionState::Included);
+        MessageId::new(id)
+    }
+
+    /// Sets the inclusion state of the messages posted to the node.
+    /// Posted messages are included by default; use `InclusionState::Pending` to test promotions and reattachments.
+    pub fn set_posted_inclusion_state(&mut self, inclusion_state: InclusionState) {
+        self.posted_inclusion_state = inclusion_state;
+    }
+
+    /// Sets the inclusion state of a message.
+    pub fn set_inclusion_state(
+        &mut self,
+        message_id: &MessageId,
+        inclusion_state: InclusionState,
+    ) -> crate::Result<()> {
+        let message = self
+            .messages
+            .iter_mut()
+            .find(|message| message.id == message_id.as_ref())
+            .ok_or(crate::WalletError::MessageNotFound)?;
+        message.inclusion_state = inclusion_state;
+        Ok(())
+    }
+
+    /// The balance of the address.
+    pub fn balance(&self, address: &IotaAddress) -> u64 {
+        self.outputs
+            .iter()
+            .filter(|output| &output.address == address && !output.is_spent)
+            .fold(0, |acc, output| acc + output.amount)
+    }
+
+    /// The ids of every message on the ledger, in the order they were added.
+    pub fn messages(&self) -> Vec<MessageId> {
+        self.messages.iter().map(|message| MessageId::new(message.id)).collect()
+    }
+
+    fn tips(&self) -> ([u8; 32], [u8; 32]) {
+        let mut messages = self.messages.iter().rev();
+        let parent1 = messages.next().map(|message| message.id).unwrap_or([0; 32]);
+        let parent2 = messages.next().map(|message| message.id).unwrap_or(parent1);
+        (parent1, parent2)
+    }
+
+    /// Adds the message to the ledger, applying its transaction payload.
+    fn add_message(&mut self, data: Value, inclusion_state: InclusionState) -> [u8; 32] {
+        let id = hash(data.to_string().as_bytes());
+        let parent = |key: &str| data[key].as_str().and_then(decode_id).unwrap_or([0; 32]);
+        let parents = (parent("parent1MessageId"), parent("parent2MessageId"));
+
+        let payload = &data["payload"];
+        if payload["type"].as_u64() == Some(TRANSACTION_PAYLOAD_TYPE) {
+            let essence = &payload["essence"];
+            let transaction_id = hash(essence.to_string().as_bytes());
+            // reattachments carry a transaction that is already on the ledger
+            if !self
+                .outputs
+                .iter()
+                .any(|output| output.transaction_id == transaction_id)
+            {
+                for input in essence["inputs"].as_array().cloned().unwrap_or_default() {
+                    let input_transaction_id = input["transactionId"].as_str().and_then(decode_id);
+                    let input_index = input["transactionOutputIndex"].as_u64();
+                    if let Some(output) = self.outputs.iter_mut().find(|output| {
+                        Some(output.transaction_id) == input_transaction_id && Some(output.index as u64) == input_index
+                    }) {
+                        output.is_spent = true;
+                    }
+                }
+                for (index, output) in essence["outputs"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default()
+                    .iter()
+                    .enumerate()
+                {
+                    let address = output["address"]["address"].as_str().and_then(parse_address);
+                    if let (Some(address), Some(amount)) = (address, output["amount"].as_u64()) {
+                        self.outputs.push(MockOutput {
+                            message_id: id,
+                            transaction_id,
+                            index: index as u16,
+                            address,
+                            amount,
+                            is_spent: false,
+                        });
+                    }
+                }
+            }
+        }
+
+        self.messages.push(MockMessage {
+            id,
+            parents,
+            data,
+            inclusion_state,
+        });
+        id
+    }
+
+    fn output_json(output: &MockOutput) -> Value {
+        json!({
+            "messageId": hex::encode(output.message_id),
+            "transactionId": hex::encode(output.transaction_id),
+            "outputIndex": output.index,
+            "isSpent": output.is_spent,
+            "output": {
+                "type": 0,
+                "address": { "type": 1, "address": address_hex(&output.address) },
+                "amount": output.amount,
+            },
+        })
+    }
+
+    fn metadata_json(message: &MockMessage) -> Value {
+        let ledger_inclusion_state = match message.inclusion_state {
+            InclusionState::Pending => Value::Null,
+            InclusionState::Included => json!("included"),
+            InclusionState::Conflicting => json!("conflicting"),
+        };
+        let is_pending = message.inclusion_state == InclusionState::Pending;
+        json!({
+            "messageId": hex::encode(message.id),
+            "parent1MessageId": hex::encode(message.parents.0),
+            "parent2MessageId": hex::encode(message.parents.1),
+            "isSolid": true,
+            "referencedByMilestoneIndex": if is_pending { Value::Null } else { json!(1) },
+            "ledgerInclusionState": ledger_inclusion_state,
+            "shouldPromote": false,
+            "shouldReattach": is_pending,
+        })
+    }
+
+    /// Answers a node API request.
+    fn respond(&mut self, method: &str, path: &str, body: &[u8]) -> (StatusCode, Value) {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let (status, data) = match (method, &segments[..]) {
+            ("GET", ["health"]) => return (StatusCode::OK, Value::Null),
+            ("GET", ["api", "v1", "info"]) => (
+                StatusCode::OK,
+                json!({
+                    "name": "mock-node",
+                    "version": "0.1.0",
+                    "isHealthy": true,
+                    "networkId": "mock",
+                    "latestMilestoneIndex": 1,
+                    "solidMilestoneIndex": 1,
+                    "pruningIndex": 0,
+                    "features": [],
+                    "minPowScore": 4000,
+                }),
+            ),
+            ("GET", ["api", "v1", "tips"]) => {
+                let (parent1, parent2) = self.tips();
+                (
+                    StatusCode::OK,
+                    json!({ "tip1MessageId": hex::encode(parent1), "tip2MessageId": hex::encode(parent2) }),
+                )
+            }
+            ("POST", ["api", "v1", "messages"]) => match serde_json::from_slice(body) {
+                Ok(data) => {
+                    let id = self.add_message(data, self.posted_inclusion_state);
+                    (StatusCode::CREATED, json!({ "messageId": hex::encode(id) }))
+                }
+                Err(e) => return bad_request(&e.to_string()),
+            },
+            ("GET", ["api", "v1", "messages", message_id]) => match self.find_message(message_id) {
+                Some(message) => (StatusCode::OK, message.data.clone()),
+                None => return not_found("message not found"),
+            },
+            ("GET", ["api", "v1", "messages", message_id, "metadata"]) => match self.find_message(message_id) {
+                Some(message) => (StatusCode::OK, Self::metadata_json(message)),
+                None => return not_found("message not found"),
+            },
+            ("GET", ["api", "v1", "outputs", output_id]) => match self.find_output(output_id) {
+                Some(output) => (StatusCode::OK, Self::output_json(output)),
+                None => return not_found("output not found"),
+            },
+            ("GET", ["api", "v1", "addresses", address]) | ("GET", ["api", "v1", "addresses", "ed25519", address]) => {
+                match parse_address(address) {
+                    Some(address) => (
+                        StatusCode::OK,
+                        json!({
+                            "addressType": 1,
+                            "address": address_hex(&address),
+                            "balance": self.balance(&address),
+                        }),
+                    ),
+                    None => return bad_request("invalid address"),
+                }
+            }
+            ("GET", ["api", "v1", "addresses", address, "outputs"])
+            | ("GET", ["api", "v1", "addresses", "ed25519", address, "outputs"]) => match parse_address(address) {
+                Some(address) => {
+                    let output_ids: Vec<String> = self
+                        .outputs
+                        .iter()
+                        .filter(|output| output.address == address)
+                        .map(|output| {
+                            format!(
+                                "{}{}",
+                                hex::encode(output.transaction_id),
+                                hex::encode(output.index.to_le_bytes())
+                            )
+                        })
+                        .collect();
+                    (
+                        StatusCode::OK,
+                        json!({
+                            "addressType": 1,
+                            "address": address_hex(&address),
+                            "maxResults": 1000,
+                            "count": output_ids.len(),
+                            "outputIds": output_ids,
+                        }),
+                    )
+                }
+                None => return bad_request("invalid address"),
+            },
+            _ => return not_found("endpoint not found"),
+        };
+        (status, json!({ "data": data }))
+    }
+
+    fn find_message(&self, message_id: &str) -> Option<&MockMessage> {
+        let message_id = decode_id(message_id)?;
+        self.messages.iter().find(|message| message.id == message_id)
+    }
+
+    fn find_output(&self, output_id: &str) -> Option<&MockOutput> {
+        if output_id.len() != 68 {
+            return None;
+        }
+        let transaction_id = decode_id(&output_id[..64])?;
+        let index: [u8; 2] = hex::decode(&output_id[64..]).ok()?.as_slice().try_into().ok()?;
+        let index = u16::from_le_bytes(index);
+        self.outputs
+            .iter()
+            .find(|output| output.transaction_id == transaction_id && output.index == index)
+    }
+}
+
+async fn handle_request(
+    ledger: Arc<Mutex<MockLedger>>,
+    request: Request<Body>,
+) -> std::result::Result<Response<Body>, Infallible> {
+    let method = request.method().as_str().to_string();
+    let path = request.uri().path().to_string();
+    let body = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+    let (status, data) = ledger.lock().unwrap().respond(&method, &path, &body);
+    let response = Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(data.to_string()))
+        .expect("failed to build the mock node response");
+    Ok(response)
+}
+
+/// A node running on localhost that serves a `MockLedger`.
+/// The node is stopped when dropped.
+pub struct MockNode {
+    url: Url,
+    ledger: Arc<Mutex<MockLedger>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockNode {
+    /// Starts the node on a random local port, serving the given ledger.
+    pub fn start(ledger: MockLedger) -> crate::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        let ledger = Arc::new(Mutex::new(ledger));
+        let (shutdown, shutdown_receiver) = oneshot::channel::<()>();
+
+        let ledger_ = ledger.clone();
+        thread::spawn(move || {
+            let mut runtime = tokio::runtime::Runtime::new().expect("failed to create the mock node runtime");
+            runtime.block_on(async move {
+                let make_service = make_service_fn(move |_| {
+                    let ledger = ledger_.clone();
+                    async move { Ok::<_, Infallible>(service_fn(move |request| handle_request(ledger.clone(), request))) }
+                });
+                let server = Server::from_tcp(listener)
+                    .expect("failed to start the mock node")
+                    .serve(make_service)
+                    .with_graceful_shutdown(async {
+                        let _ = shutdown_receiver.await;
+                    });
+                let _ = server.await;
+            });
+        });
+
+        Ok(Self {
+            url,
+            ledger,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// The node URL.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// The client options to connect to the node.
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptionsBuilder::node(self.url.as_str())
+            .expect("invalid mock node URL")
+            .build()
+    }
+
+    /// Runs the callback with the node's ledger, e.g. to fund addresses or change the inclusion state of messages.
+    pub fn with_ledger<T, F: FnOnce(&mut MockLedger) -> T>(&self, cb: F) -> T {
+        let mut ledger = self.ledger.lock().unwrap();
+        cb(&mut ledger)
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+/// A signer deriving the account keys from a fixed seed, so the accounts and addresses are reproducible across test
+/// runs.
+pub struct MockSigner {
+    seed: [u8; 32],
+}
+
+impl Default for MockSigner {
+    fn default() -> Self {
+        Self::new([0; 32])
+    }
+}
+
+impl MockSigner {
+    /// Initialises the signer with the given seed.
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self { seed }
+    }
+
+    fn get_private_key(&self, derivation_path: &BIP32Path) -> crate::Result<ed25519::Ed25519PrivateKey> {
+        let seed = ed25519::Ed25519Seed::from_bytes(&self.seed).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let private_key = ed25519::Ed25519PrivateKey::generate_from_seed(&seed, derivation_path)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        Ok(private_key)
+    }
+}
+
+fn derivation_path(account: &Account, index: usize, internal: bool) -> crate::Result<BIP32Path> {
+    let derivation_path =
+        BIP32Path::from_str(&account.derivation_path(index, internal)).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(derivation_path)
+}
+
+impl Signer for MockSigner {
+    fn init_account(&self, account: &Account, _mnemonic: Option<String>) -> crate::Result<String> {
+        let mut id_seed = self.seed.to_vec();
+        id_seed.extend(&(*account.index() as u64).to_le_bytes());
+        Ok(hex::encode(hash(&id_seed)))
+    }
+
+    fn generate_address(&self, account: &Account, index: usize, internal: bool) -> crate::Result<IotaAddress> {
+        let derivation_path = derivation_path(account, index, internal)?;
+        let public_key = self.get_private_key(&derivation_path)?.generate_public_key().to_bytes();
+        Ok(IotaAddress::Ed25519(Ed25519Address::new(hash(&public_key))))
+    }
+
+    fn sign_data(
+        &self,
+        account: &Account,
+        address_index: usize,
+        internal: bool,
+        data: &[u8],
+    ) -> crate::Result<DataSignature> {
+        let private_key = self.get_private_key(&derivation_path(account, address_index, internal)?)?;
+        let public_key = private_key.generate_public_key().to_bytes();
+        let signature = private_key.sign(&signed_data_payload(data)).to_bytes();
+        Ok(DataSignature::new(public_key, signature))
+    }
+
+    fn sign_raw(
+        &self,
+        _account: &Account,
+        address_path: &BIP32Path,
+        message: &[u8],
+    ) -> crate::Result<([u8; 32], [u8; 64])> {
+        let private_key = self.get_private_key(address_path)?;
+        let public_key = private_key.generate_public_key().to_bytes();
+        Ok((public_key, private_key.sign(message).to_bytes()))
+    }
+
+    fn sign_message(
+        &self,
+        account: &Account,
+        essence: &iota::TransactionEssence,
+        inputs: &mut Vec<TransactionInput>,
+    ) -> crate::Result<Vec<UnlockBlock>> {
+        crate::signing::build_unlock_blocks(essence, inputs, |address_path, message| {
+            self.sign_raw(account, address_path, message)
+        })
+    }
+
+    fn capability(&self) -> SignerCapability {
+        SignerCapability::Sign
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockLedger;
+    use crate::address::IotaAddress;
+    use hyper::StatusCode;
+    use iota::message::prelude::Ed25519Address;
+
+    #[test]
+    fn funded_address_outputs() {
+        let address = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+        let mut ledger = MockLedger::new();
+        let message_id = ledger.fund(&address, 500);
+        assert_eq!(ledger.balance(&address), 500);
+
+        let path = format!("/api/v1/addresses/{}/outputs", address.to_bech32());
+        let (status, response) = ledger.respond("GET", &path, &[]);
+        assert_eq!(status, StatusCode::OK);
+        let output_id = response["data"]["outputIds"][0].as_str().unwrap().to_string();
+
+        let (status, response) = ledger.respond("GET", &format!("/api/v1/outputs/{}", output_id), &[]);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response["data"]["messageId"], message_id.to_string());
+        assert_eq!(response["data"]["output"]["amount"], 500);
+    }
+}
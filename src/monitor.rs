@@ -43,7 +43,7 @@ struct AddressOutputPayloadAddress {
 
 /// Unsubscribe from all topics associated with the account.
 pub fn unsubscribe(account: &Account) -> crate::Result<()> {
-    let client = crate::client::get_client(account.client_options());
+    let client = crate::client::get_client(account.storage_path(), account.client_options());
     let mut client = client.write().unwrap();
     client.subscriber().unsubscribe()?;
     Ok(())
@@ -61,11 +61,11 @@ fn mutate_account<F: FnOnce(&mut Account)>(
 }
 
 fn subscribe_to_topic<C: Fn(&TopicEvent) + Send + Sync + 'static>(
-    client_options: &ClientOptions,
+    account: &Account,
     topic: String,
     handler: C,
 ) -> crate::Result<()> {
-    let client = crate::client::get_client(&client_options);
+    let client = crate::client::get_client(account.storage_path(), account.client_options());
     let mut client = client.write().unwrap();
     client.subscriber().topic(Topic::new(topic)?).subscribe(handler)?;
     Ok(())
@@ -88,7 +88,7 @@ pub fn monitor_address_balance(account: &Account, address: &IotaAddress) -> crat
     let address_bech32 = address.to_bech32();
 
     subscribe_to_topic(
-        account.client_options(),
+        &account,
         format!("addresses/{}/outputs", address_bech32),
         move |topic_event| {
             let topic_event = topic_event.clone();
@@ -138,7 +138,7 @@ async fn process_output(
     let message_id_ = *message_id;
 
     let message = {
-        let client = crate::client::get_client(&client_options_);
+        let client = crate::client::get_client(&storage_path, &client_options_);
         let client = client.read().unwrap();
         client.get_message().data(&message_id_).await?
     };
@@ -149,7 +149,12 @@ async fn process_output(
             let addresses = account.addresses_mut();
             let address_to_update = addresses.iter_mut().find(|a| a.address() == &address).unwrap();
             address_to_update.handle_new_output(address_output);
-            crate::event::emit_balance_change(&account_id, &address_to_update, *address_to_update.balance());
+            crate::event::emit_balance_change(
+                &storage_path,
+                &account_id,
+                &address_to_update,
+                *address_to_update.balance(),
+            );
         }
 
         match account.messages_mut().iter().position(|m| m.id() == &message_id_) {
@@ -161,6 +166,7 @@ async fn process_output(
                 let message =
                     Message::from_iota_message(message_id_, account.addresses(), &message, Some(true)).unwrap();
                 crate::event::emit_transaction_event(
+                    &storage_path,
                     crate::event::TransactionEventType::NewTransaction,
                     &account_id,
                     &message,
@@ -193,7 +199,7 @@ pub fn monitor_confirmation_state_change(account: &Account, message_id: &Message
     let message_id = *message_id;
 
     subscribe_to_topic(
-        account.client_options(),
+        &account,
         format!("messages/{}/metadata", message_id.to_string()),
         move |topic_event| {
             let account_id = account_id.clone();
@@ -229,7 +235,7 @@ fn process_metadata(
                     *message.id()
                 };
 
-                crate::event::emit_confirmation_state_change(&account_id, &message, confirmed);
+                crate::event::emit_confirmation_state_change(&storage_path, &account_id, &message, confirmed);
             })?;
         }
     }
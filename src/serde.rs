@@ -138,6 +138,76 @@ impl serde::Serialize for crate::WalletError {
             Self::ZeroAmount => serialize_variant(serializer, "ZeroAmount", None),
             Self::AccountNotFound => serialize_variant(serializer, "AccountNotFound", None),
             Self::InvalidRemainderValueAddress => serialize_variant(serializer, "InvalidRemainderValueAddress", None),
+            Self::UnsupportedStorageVersion(version) => serialize_variant(
+                serializer,
+                "UnsupportedStorageVersion",
+                Some(&format!("storage version {} isn't supported", version)),
+            ),
+            Self::StorageIsEncrypted => serialize_variant(serializer, "StorageIsEncrypted", None),
+            Self::InvalidStoragePassword => serialize_variant(serializer, "InvalidStoragePassword", None),
+            Self::StorageAdapterNotSet(path) => serialize_variant(
+                serializer,
+                "StorageAdapterNotSet",
+                Some(&format!("storage adapter not set for path {:?}", path)),
+            ),
+            Self::StrongholdNotLoaded(path) => serialize_variant(
+                serializer,
+                "StrongholdNotLoaded",
+                Some(&format!("Stronghold not loaded for path {:?}", path)),
+            ),
+            Self::SignerNotSet(signer_type) => serialize_variant(
+                serializer,
+                "SignerNotSet",
+                Some(&format!("signer not set for type {:?}", signer_type)),
+            ),
+            Self::InvalidStrongholdPassword => serialize_variant(serializer, "InvalidStrongholdPassword", None),
+            Self::StrongholdLocked => serialize_variant(serializer, "StrongholdLocked", None),
+            Self::InvalidBackup(reason) => serialize_variant(serializer, "InvalidBackup", Some(reason)),
+            Self::ExternalSignerError(error) => serialize_variant(serializer, "ExternalSignerError", Some(error)),
+            Self::WatchOnlyAccount => serialize_variant(serializer, "WatchOnlyAccount", None),
+            Self::InvalidMnemonic(reason) => serialize_variant(serializer, "InvalidMnemonic", Some(reason)),
+            Self::SignerAlreadyRegistered(signer_type) => serialize_variant(
+                serializer,
+                "SignerAlreadyRegistered",
+                Some(&format!("a signer is already registered for type {:?}", signer_type)),
+            ),
+            Self::SignerAddressMismatch(address) => serialize_variant(
+                serializer,
+                "SignerAddressMismatch",
+                Some(&format!("the signer derives a different address than {}", address)),
+            ),
+            Self::AccountIntegrityMismatch(account_id) => serialize_variant(
+                serializer,
+                "AccountIntegrityMismatch",
+                Some(&format!(
+                    "the signer of account {:?} derives different addresses than the stored ones",
+                    account_id
+                )),
+            ),
+            Self::TooManyOutputs(count, max) => serialize_variant(
+                serializer,
+                "TooManyOutputs",
+                Some(&format!(
+                    "the transfer needs {} outputs, but a transaction can hold at most {}",
+                    count, max
+                )),
+            ),
+            Self::TooManyInputs(count, max) => serialize_variant(
+                serializer,
+                "TooManyInputs",
+                Some(&format!(
+                    "the transfer needs {} inputs, but the limit is {}",
+                    count, max
+                )),
+            ),
+            Self::InvalidTransferInput(reason) => serialize_variant(serializer, "InvalidTransferInput", Some(reason)),
+            Self::EmptyTransfer => serialize_variant(serializer, "EmptyTransfer", None),
+            Self::AmountOverflow => serialize_variant(serializer, "AmountOverflow", None),
+            Self::AccountIndexInUse { index, alias } => serialize_variant(
+                serializer,
+                "AccountIndexInUse",
+                Some(&format!("account index {} is already used by account {}", index, alias)),
+            ),
         }
     }
 }
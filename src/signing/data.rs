@@ -0,0 +1,121 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::address::{Ed25519Address, IotaAddress};
+
+use bee_signing_ext::{binary::ed25519, Verifier};
+use blake2::{
+    digest::{Update, VariableOutput},
+    VarBlake2b,
+};
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+/// Prefixed to the signed data, so a data signature can't be used as a transaction signature.
+const SIGNED_DATA_PREFIX: &[u8] = b"IOTA Signed Data:\n";
+
+/// The blake2b-256 hash of `bytes`, e.g. the address of an ed25519 public key.
+pub(crate) fn hash(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = VarBlake2b::new(32).unwrap();
+    hasher.update(bytes);
+    let mut hash = [0u8; 32];
+    hasher.finalize_variable(|res| hash.copy_from_slice(res));
+    hash
+}
+
+/// The message actually signed by `Signer::sign_data`: the blake2b-256 hash of the prefixed data.
+pub(crate) fn signed_data_payload(data: &[u8]) -> [u8; 32] {
+    let mut payload = SIGNED_DATA_PREFIX.to_vec();
+    payload.extend_from_slice(data);
+    hash(&payload)
+}
+
+/// A signature of arbitrary data made with the key of an account address.
+#[derive(Debug, Clone, PartialEq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct DataSignature {
+    /// The address of the signing key.
+    #[serde(with = "crate::serde::iota_address_serde")]
+    address: IotaAddress,
+    /// The hex encoded ed25519 public key.
+    #[serde(rename = "publicKey")]
+    public_key: String,
+    /// The hex encoded ed25519 signature.
+    signature: String,
+}
+
+impl DataSignature {
+    /// Initialises the signature from the signing public key and the signature bytes.
+    pub fn new(public_key: [u8; 32], signature: [u8; 64]) -> Self {
+        Self {
+            address: IotaAddress::Ed25519(Ed25519Address::new(hash(&public_key))),
+            public_key: hex::encode(public_key),
+            signature: hex::encode(&signature[..]),
+        }
+    }
+}
+
+/// Checks that `signature` is a signature of `data` made with the key of `signature.address()`.
+pub fn verify(data: &[u8], signature: &DataSignature) -> bool {
+    let public_key: [u8; 32] = match hex::decode(&signature.public_key)
+        .ok()
+        .and_then(|public_key| public_key.try_into().ok())
+    {
+        Some(public_key) => public_key,
+        None => return false,
+    };
+    if IotaAddress::Ed25519(Ed25519Address::new(hash(&public_key))) != signature.address {
+        return false;
+    }
+    let ed25519_signature = hex::decode(&signature.signature)
+        .ok()
+        .and_then(|signature| ed25519::Ed25519Signature::from_bytes(&signature).ok());
+    match (ed25519::Ed25519PublicKey::from_bytes(&public_key), ed25519_signature) {
+        (Ok(public_key), Some(ed25519_signature)) => public_key.verify(&signed_data_payload(data), &ed25519_signature),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify, DataSignature};
+    use crate::{
+        address::{Ed25519Address, IotaAddress},
+        mock_node::MockLedger,
+        test_utils::get_mock_account_manager,
+    };
+    use rusty_fork::rusty_fork_test;
+
+    rusty_fork_test! {
+        #[test]
+        fn sign_and_verify_data() {
+            let (manager, node) = get_mock_account_manager(MockLedger::new());
+            let account = manager
+                .create_account(node.client_options())
+                .signer_type(crate::test_utils::mock_signer_type())
+                .initialise()
+                .unwrap();
+            let address = account.addresses()[0].address().clone();
+
+            let signature = account.sign_data(&address, b"login challenge").unwrap();
+            assert_eq!(signature.address(), &address);
+            assert!(verify(b"login challenge", &signature));
+            assert!(!verify(b"another challenge", &signature));
+
+            let signature: DataSignature = serde_json::from_str(&serde_json::to_string(&signature).unwrap()).unwrap();
+            assert!(verify(b"login challenge", &signature));
+
+            // the signature doesn't prove the ownership of another address
+            let mut forged = serde_json::to_value(&signature).unwrap();
+            forged["address"] = serde_json::Value::from(
+                IotaAddress::Ed25519(Ed25519Address::new([1; 32])).to_bech32(),
+            );
+            let forged: DataSignature = serde_json::from_value(forged).unwrap();
+            assert!(!verify(b"login challenge", &forged));
+
+            let unknown_address = IotaAddress::Ed25519(Ed25519Address::new([2; 32]));
+            assert!(account.sign_data(&unknown_address, b"login challenge").is_err());
+        }
+    }
+}
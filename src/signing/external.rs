@@ -0,0 +1,630 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{DataSignature, Signer, SignerCapability, TransactionInput};
+use crate::{
+    account::Account,
+    address::{Ed25519Address, IotaAddress},
+};
+
+use bee_signing_ext::{binary::ed25519, Verifier};
+use iota::{TransactionEssence, UnlockBlock};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use slip10::BIP32Path;
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::{
+    convert::TryInto,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+/// How the external signer is reached.
+#[derive(Debug, Clone)]
+pub enum ExternalSignerTransport {
+    /// A Unix domain socket the signer process listens on.
+    #[cfg(unix)]
+    UnixSocket(PathBuf),
+    /// A signer subprocess, reading the requests from its stdin and writing the responses to its stdout.
+    Process {
+        /// The signer executable.
+        program: PathBuf,
+        /// The executable arguments.
+        args: Vec<String>,
+    },
+}
+
+/// An open connection to the external signer.
+struct Connection {
+    reader: Box<dyn BufRead + Send>,
+    writer: Box<dyn Write + Send>,
+    /// The signer subprocess, killed when the connection is dropped.
+    child: Option<Child>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Connection {
+    fn open(transport: &ExternalSignerTransport) -> crate::Result<Self> {
+        match transport {
+            #[cfg(unix)]
+            ExternalSignerTransport::UnixSocket(path) => {
+                let stream = UnixStream::connect(path)?;
+                Ok(Self {
+                    reader: Box::new(BufReader::new(stream.try_clone()?)),
+                    writer: Box::new(stream),
+                    child: None,
+                })
+            }
+            ExternalSignerTransport::Process { program, args } => {
+                let mut child = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()?;
+                let stdin = child.stdin.take().expect("the child stdin is piped");
+                let stdout = child.stdout.take().expect("the child stdout is piped");
+                Ok(Self {
+                    reader: Box::new(BufReader::new(stdout)),
+                    writer: Box::new(stdin),
+                    child: Some(child),
+                })
+            }
+        }
+    }
+
+    fn send(&mut self, request: &str) -> crate::Result<String> {
+        writeln!(self.writer, "{}", request)?;
+        self.writer.flush()?;
+        let mut response = String::new();
+        if self.reader.read_line(&mut response)? == 0 {
+            return Err(crate::WalletError::ExternalSignerError(
+                "the signer closed the connection".to_string(),
+            ));
+        }
+        Ok(response)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    id: u64,
+    method: String,
+    params: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Response {
+    id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct InitAccountParams {
+    account: Value,
+    mnemonic: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GenerateAddressParams {
+    account: Value,
+    index: usize,
+    internal: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignParams {
+    account: Value,
+    /// The BIP32 derivation path of the signing key.
+    path: String,
+    /// The hex encoded message.
+    message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawSignature {
+    /// The hex encoded ed25519 public key.
+    #[serde(rename = "publicKey")]
+    public_key: String,
+    /// The hex encoded ed25519 signature.
+    signature: String,
+}
+
+/// A signer running outside of the wallet process, e.g. a hardware wallet bridge.
+///
+/// The wallet and the signer exchange newline delimited JSON messages. Every request has a numeric `id`, a
+/// `method` and its `params`; the signer answers each request in order with a response carrying the same `id` and
+/// either a `result` or an `error` message:
+///
+/// ```text
+/// -> {"id":1,"method":"generate_address","params":{"account":{...},"index":0,"internal":false}}
+/// <- {"id":1,"result":"iot1q..."}
+/// ```
+///
+/// The `account` param is the account record without its messages and addresses. The methods are:
+///
+/// - `init_account` with the `account` and an optional `mnemonic`; the result is the account id.
+/// - `generate_address` with the `account`, the address `index` and the `internal` flag; the result is the bech32
+///   address.
+/// - `sign` with the `account`, the BIP32 derivation `path` of the key and the hex encoded `message`; the result is the
+///   `{"publicKey":"<hex>","signature":"<hex>"}` ed25519 signature of the message.
+///
+/// The signer only signs raw messages: the wallet sorts the transaction inputs and builds the unlock blocks itself
+/// with `build_unlock_blocks`, and signs data with the `signed_data_payload` as message. Every signature is checked
+/// before it's used: it must verify over the message, and its public key must hash to the account address on the
+/// requested path. The reference unlock blocks never come from the signer, so they always point to a signature block.
+///
+/// The private keys never leave the signer. `serve` implements the signer side of the protocol for any `Signer`
+/// implementing `sign_raw`.
+pub struct ExternalSigner {
+    transport: ExternalSignerTransport,
+    connection: Mutex<Option<Connection>>,
+    next_request_id: AtomicU64,
+}
+
+impl ExternalSigner {
+    /// Initialises the signer. The connection is opened on the first request.
+    pub fn new(transport: ExternalSignerTransport) -> Self {
+        Self {
+            transport,
+            connection: Mutex::new(None),
+            next_request_id: AtomicU64::new(1),
+        }
+    }
+
+    fn call<P: Serialize, T: DeserializeOwned>(&self, method: &str, params: P) -> crate::Result<T> {
+        let request = Request {
+            id: self.next_request_id.fetch_add(1, Ordering::SeqCst),
+            method: method.to_string(),
+            params: serde_json::to_value(params)?,
+        };
+        let mut connection = self.connection.lock().unwrap();
+        if connection.is_none() {
+            connection.replace(Connection::open(&self.transport)?);
+        }
+        let response = match connection.as_mut().unwrap().send(&serde_json::to_string(&request)?) {
+            Ok(response) => response,
+            Err(e) => {
+                // reconnect on the next request
+                connection.take();
+                return Err(e);
+            }
+        };
+
+        let response: Response = serde_json::from_str(&response)
+            .map_err(|_| crate::WalletError::ExternalSignerError("malformed response".to_string()))?;
+        if response.id != request.id {
+            connection.take();
+            return Err(crate::WalletError::ExternalSignerError(format!(
+                "expected the response to request `{}`, got `{}`",
+                request.id, response.id
+            )));
+        }
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(crate::WalletError::ExternalSignerError(error)),
+            (Some(result), None) => serde_json::from_value(result)
+                .map_err(|_| crate::WalletError::ExternalSignerError(format!("malformed `{}` result", method))),
+            (None, None) => Err(crate::WalletError::ExternalSignerError(format!(
+                "missing `{}` result",
+                method
+            ))),
+        }
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn init_account(&self, account: &Account, mnemonic: Option<String>) -> crate::Result<String> {
+        self.call(
+            "init_account",
+            InitAccountParams {
+                account: account_param(account)?,
+                mnemonic,
+            },
+        )
+    }
+
+    fn generate_address(&self, account: &Account, index: usize, internal: bool) -> crate::Result<iota::Address> {
+        let address: String = self.call(
+            "generate_address",
+            GenerateAddressParams {
+                account: account_param(account)?,
+                index,
+                internal,
+            },
+        )?;
+        crate::address::parse(address)
+    }
+
+    fn sign_raw(
+        &self,
+        account: &Account,
+        address_path: &BIP32Path,
+        message: &[u8],
+    ) -> crate::Result<([u8; 32], [u8; 64])> {
+        let params = SignParams {
+            account: account_param(account)?,
+            path: address_path.to_string(),
+            message: hex::encode(message),
+        };
+        let signature: RawSignature = self.call("sign", params)?;
+        let invalid = |field: &str| crate::WalletError::ExternalSignerError(format!("invalid {}", field));
+        let public_key: [u8; 32] = hex::decode(signature.public_key)
+            .map_err(|_| invalid("public key"))?
+            .try_into()
+            .map_err(|_| invalid("public key"))?;
+        let signature: [u8; 64] = hex::decode(signature.signature)
+            .map_err(|_| invalid("signature"))?
+            .try_into()
+            .map_err(|_| invalid("signature"))?;
+
+        let verified = match (
+            ed25519::Ed25519PublicKey::from_bytes(&public_key),
+            ed25519::Ed25519Signature::from_bytes(&signature),
+        ) {
+            (Ok(key), Ok(ed25519_signature)) => key.verify(message, &ed25519_signature),
+            _ => false,
+        };
+        if !verified {
+            return Err(crate::WalletError::ExternalSignerError(format!(
+                "the signature doesn't verify with the `{}` key",
+                address_path
+            )));
+        }
+        Ok((public_key, signature))
+    }
+
+    fn sign_message(
+        &self,
+        account: &Account,
+        essence: &TransactionEssence,
+        inputs: &mut Vec<TransactionInput>,
+    ) -> crate::Result<Vec<UnlockBlock>> {
+        super::build_unlock_blocks(essence, inputs, |address_path, message| {
+            let (public_key, signature) = self.sign_raw(account, address_path, message)?;
+            check_signing_key(account, address_path, &public_key)?;
+            Ok((public_key, signature))
+        })
+    }
+
+    fn sign_data(
+        &self,
+        account: &Account,
+        address_index: usize,
+        internal: bool,
+        data: &[u8],
+    ) -> crate::Result<DataSignature> {
+        let address_path = BIP32Path::from_str(&account.derivation_path(address_index, internal))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let (public_key, signature) = self.sign_raw(account, &address_path, &super::signed_data_payload(data))?;
+        check_signing_key(account, &address_path, &public_key)?;
+        Ok(DataSignature::new(public_key, signature))
+    }
+
+    fn capability(&self) -> SignerCapability {
+        SignerCapability::Sign
+    }
+}
+
+/// Checks that the public key returned by the signer is the key of the account address on the derivation path.
+fn check_signing_key(account: &Account, address_path: &BIP32Path, public_key: &[u8; 32]) -> crate::Result<()> {
+    let address = account
+        .addresses()
+        .iter()
+        .find(|address| {
+            BIP32Path::from_str(&account.derivation_path(*address.key_index(), *address.internal()))
+                .map(|path| &path == address_path)
+                .unwrap_or(false)
+        })
+        .ok_or_else(|| {
+            crate::WalletError::ExternalSignerError(format!("no account address on the path `{}`", address_path))
+        })?;
+    if address.address() != &IotaAddress::Ed25519(Ed25519Address::new(super::hash(public_key))) {
+        return Err(crate::WalletError::ExternalSignerError(format!(
+            "the signing key doesn't match the address {}",
+            address.address().to_bech32()
+        )));
+    }
+    Ok(())
+}
+
+/// The account record sent to the signer, without the transaction history.
+fn account_param(account: &Account) -> crate::Result<Value> {
+    let mut account = serde_json::to_value(account)?;
+    account["messages"] = Value::Array(vec![]);
+    account["addresses"] = Value::Array(vec![]);
+    Ok(account)
+}
+
+fn handle_request<S: Signer>(signer: &S, request: Request) -> crate::Result<Value> {
+    let parse_account = |account: Value| serde_json::from_value::<Account>(account).map_err(crate::WalletError::from);
+    match request.method.as_str() {
+        "init_account" => {
+            let params: InitAccountParams = serde_json::from_value(request.params)?;
+            let id = signer.init_account(&parse_account(params.account)?, params.mnemonic)?;
+            Ok(Value::from(id))
+        }
+        "generate_address" => {
+            let params: GenerateAddressParams = serde_json::from_value(request.params)?;
+            let address = signer.generate_address(&parse_account(params.account)?, params.index, params.internal)?;
+            Ok(Value::from(address.to_bech32()))
+        }
+        "sign" => {
+            let params: SignParams = serde_json::from_value(request.params)?;
+            let address_path = BIP32Path::from_str(&params.path).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            let message = hex::decode(&params.message).map_err(|_| anyhow::anyhow!("invalid message hex"))?;
+            let (public_key, signature) = signer.sign_raw(&parse_account(params.account)?, &address_path, &message)?;
+            Ok(serde_json::to_value(RawSignature {
+                public_key: hex::encode(public_key),
+                signature: hex::encode(&signature[..]),
+            })?)
+        }
+        method => Err(anyhow::anyhow!("unknown method `{}`", method).into()),
+    }
+}
+
+/// Serves the external signer protocol with the given signer until the reader is closed.
+///
+/// Reading from stdin and writing to stdout turns the signer into a subprocess usable with
+/// `ExternalSignerTransport::Process`; a Unix socket stream can be served the same way.
+pub fn serve<S: Signer, R: BufRead, W: Write>(signer: &S, reader: R, mut writer: W) -> crate::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let id = request.id;
+                match handle_request(signer, request) {
+                    Ok(result) => Response {
+                        id,
+                        result: Some(result),
+                        error: None,
+                    },
+                    Err(e) => Response {
+                        id,
+                        result: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            }
+            Err(e) => Response {
+                id: 0,
+                result: None,
+                error: Some(format!("malformed request: {}", e)),
+            },
+        };
+        writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{serve, ExternalSigner, ExternalSignerTransport};
+    use crate::{
+        account::Account,
+        address::IotaAddress,
+        message::Transfer,
+        mock_node::{MockLedger, MockSigner},
+        signing::{Signer, SignerType, TransactionInput},
+        test_utils::get_mock_account_manager,
+    };
+    use iota::message::prelude::Ed25519Address;
+    use rand::{thread_rng, Rng};
+    use rusty_fork::rusty_fork_test;
+    use slip10::BIP32Path;
+    use std::{
+        fs::{File, OpenOptions},
+        io::BufReader,
+        os::unix::net::UnixListener,
+        path::PathBuf,
+        process::Command,
+        thread,
+    };
+
+    fn temp_path(prefix: &str) -> PathBuf {
+        let name: String = thread_rng().gen_ascii_chars().take(10).collect();
+        std::env::temp_dir().join(format!("{}-{}", prefix, name))
+    }
+
+    /// Serves the signer to a `sh` subprocess, bridging its stdin and stdout to a pair of FIFOs.
+    fn process_transport<S: Signer + Send + 'static>(signer: S) -> ExternalSignerTransport {
+        let requests = temp_path("wallet-signer-requests");
+        let responses = temp_path("wallet-signer-responses");
+        for fifo in &[&requests, &responses] {
+            assert!(Command::new("mkfifo").arg(fifo).status().unwrap().success());
+        }
+        let transport = ExternalSignerTransport::Process {
+            program: PathBuf::from("sh"),
+            args: vec![
+                "-c".to_string(),
+                r#"cat < "$1" & exec cat > "$0""#.to_string(),
+                requests.to_string_lossy().to_string(),
+                responses.to_string_lossy().to_string(),
+            ],
+        };
+        thread::spawn(move || {
+            let reader = BufReader::new(File::open(&requests).unwrap());
+            let writer = OpenOptions::new().write(true).open(&responses).unwrap();
+            let _ = serve(&signer, reader, writer);
+            let _ = std::fs::remove_file(&requests);
+            let _ = std::fs::remove_file(&responses);
+        });
+        transport
+    }
+
+    /// Derives the addresses of the mock signer, but signs with another seed.
+    struct ForgingSigner {
+        signer: MockSigner,
+        forger: MockSigner,
+    }
+
+    impl Signer for ForgingSigner {
+        fn init_account(&self, account: &Account, mnemonic: Option<String>) -> crate::Result<String> {
+            self.signer.init_account(account, mnemonic)
+        }
+
+        fn generate_address(&self, account: &Account, index: usize, internal: bool) -> crate::Result<IotaAddress> {
+            self.signer.generate_address(account, index, internal)
+        }
+
+        fn sign_raw(
+            &self,
+            account: &Account,
+            address_path: &BIP32Path,
+            message: &[u8],
+        ) -> crate::Result<([u8; 32], [u8; 64])> {
+            self.forger.sign_raw(account, address_path, message)
+        }
+
+        fn sign_message(
+            &self,
+            account: &Account,
+            essence: &iota::TransactionEssence,
+            inputs: &mut Vec<TransactionInput>,
+        ) -> crate::Result<Vec<iota::UnlockBlock>> {
+            self.forger.sign_message(account, essence, inputs)
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn external_signer_transfer() {
+            crate::block_on(async move {
+                let socket_path = temp_path("wallet-signer").with_extension("sock");
+                let listener = UnixListener::bind(&socket_path).unwrap();
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        let stream = stream.unwrap();
+                        let reader = BufReader::new(stream.try_clone().unwrap());
+                        let _ = serve(&MockSigner::default(), reader, stream);
+                    }
+                });
+
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let signer_type = SignerType::Custom("external".to_string());
+                manager
+                    .context()
+                    .set_signer(
+                        signer_type.clone(),
+                        ExternalSigner::new(ExternalSignerTransport::UnixSocket(socket_path.clone())),
+                    )
+                    .unwrap();
+                let mut account = manager
+                    .create_account(node.client_options())
+                    .signer_type(signer_type)
+                    .initialise()
+                    .unwrap();
+
+                // the stand-in signer derives the same addresses as the mock signer
+                let address = crate::address::get_iota_address(&account, 0, false).unwrap();
+                assert_eq!(MockSigner::default().generate_address(&account, 0, false).unwrap(), address);
+
+                node.with_ledger(|ledger| ledger.fund(&address, 1_000_000));
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+                let synced_account = account.sync().execute().await.unwrap();
+                synced_account
+                    .transfer(Transfer::new(recipient.clone(), 400_000))
+                    .await
+                    .unwrap();
+                node.with_ledger(|ledger| assert_eq!(ledger.balance(&recipient), 400_000));
+
+                let signature = account.sign_data(&address, b"challenge").unwrap();
+                assert!(crate::signing::verify(b"challenge", &signature));
+
+                let _ = std::fs::remove_file(&socket_path);
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn external_signer_process() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let signer_type = SignerType::Custom("external-process".to_string());
+                manager
+                    .context()
+                    .set_signer(
+                        signer_type.clone(),
+                        ExternalSigner::new(process_transport(MockSigner::default())),
+                    )
+                    .unwrap();
+                let mut account = manager
+                    .create_account(node.client_options())
+                    .signer_type(signer_type)
+                    .initialise()
+                    .unwrap();
+                let address = account.addresses()[0].address().clone();
+                assert_eq!(MockSigner::default().generate_address(&account, 0, false).unwrap(), address);
+
+                node.with_ledger(|ledger| ledger.fund(&address, 1_000_000));
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+                let synced_account = account.sync().execute().await.unwrap();
+                synced_account
+                    .transfer(Transfer::new(recipient.clone(), 400_000))
+                    .await
+                    .unwrap();
+                node.with_ledger(|ledger| assert_eq!(ledger.balance(&recipient), 400_000));
+
+                let signature = account.sign_data(&address, b"challenge").unwrap();
+                assert!(crate::signing::verify(b"challenge", &signature));
+            });
+        }
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn reject_foreign_signatures() {
+            crate::block_on(async move {
+                let (manager, node) = get_mock_account_manager(MockLedger::new());
+                let signer_type = SignerType::Custom("external-forging".to_string());
+                let signer = ForgingSigner {
+                    signer: MockSigner::default(),
+                    forger: MockSigner::new([1; 32]),
+                };
+                manager
+                    .context()
+                    .set_signer(signer_type.clone(), ExternalSigner::new(process_transport(signer)))
+                    .unwrap();
+                let mut account = manager
+                    .create_account(node.client_options())
+                    .signer_type(signer_type)
+                    .initialise()
+                    .unwrap();
+                let address = account.addresses()[0].address().clone();
+
+                // the signatures are valid, but made with keys that don't belong to the account addresses
+                assert!(account.sign_data(&address, b"challenge").is_err());
+
+                node.with_ledger(|ledger| ledger.fund(&address, 1_000_000));
+                let recipient = IotaAddress::Ed25519(Ed25519Address::new([1; 32]));
+                let synced_account = account.sync().execute().await.unwrap();
+                assert!(synced_account
+                    .transfer(Transfer::new(recipient.clone(), 400_000))
+                    .await
+                    .is_err());
+                node.with_ledger(|ledger| assert_eq!(ledger.balance(&recipient), 0));
+            });
+        }
+    }
+}
@@ -0,0 +1,407 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::account::Account;
+
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use bech32::ToBase32;
+use blake2::{
+    digest::{Update, VariableOutput},
+    VarBlake2b,
+};
+use hmac::Hmac;
+use rand::{thread_rng, Rng};
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroize;
+
+use bee_signing_ext::{
+    binary::{ed25519, BIP32Path},
+    Signer,
+};
+
+const PBKDF2_ROUNDS: usize = 2048;
+const PBKDF2_BYTES: usize = 32; // 64 for secp256k1 , 32 for ed25
+
+/// The environment variable holding the mnemonic of the default `SignerType::EnvMnemonic` signer.
+pub const MNEMONIC_ENV_VAR: &str = "IOTA_WALLET_MNEMONIC";
+/// The environment variable holding the BIP39 passphrase of the default `SignerType::EnvMnemonic` signer.
+pub const MNEMONIC_PASSPHRASE_ENV_VAR: &str = "IOTA_WALLET_MNEMONIC_PASSWORD";
+
+/// The BIP39 passphrase the `EnvMnemonic` signer used when `IOTA_WALLET_MNEMONIC_PASSWORD` wasn't set.
+const LEGACY_PASSPHRASE: &str = "password";
+
+/// A mnemonic and its BIP39 passphrase, wiped from memory when dropped.
+pub struct MnemonicSecret {
+    mnemonic: String,
+    passphrase: Option<String>,
+}
+
+impl MnemonicSecret {
+    /// Initialises the secret; a missing passphrase is the empty BIP39 passphrase, or the legacy `password` one for
+    /// the accounts flagged with `Account::legacy_mnemonic_passphrase`.
+    pub fn new<M: Into<String>>(mnemonic: M, passphrase: Option<String>) -> Self {
+        Self {
+            mnemonic: mnemonic.into(),
+            passphrase,
+        }
+    }
+
+    fn seed(&self, legacy_passphrase: bool) -> SeedBuffer {
+        let default_passphrase = if legacy_passphrase { LEGACY_PASSPHRASE } else { "" };
+        let passphrase = self.passphrase.as_deref().unwrap_or(default_passphrase);
+        let mut salt = format!("mnemonic{}", passphrase).nfkd().to_string();
+        let mut seed = [0u8; PBKDF2_BYTES];
+        let mut mnemonic = self.mnemonic.nfkd().to_string();
+        pbkdf2::pbkdf2::<Hmac<sha2::Sha512>>(mnemonic.as_bytes(), salt.as_bytes(), PBKDF2_ROUNDS, &mut seed);
+        mnemonic.zeroize();
+        salt.zeroize();
+        SeedBuffer(seed)
+    }
+}
+
+impl Drop for MnemonicSecret {
+    fn drop(&mut self) {
+        self.mnemonic.zeroize();
+        if let Some(passphrase) = self.passphrase.as_mut() {
+            passphrase.zeroize();
+        }
+    }
+}
+
+/// Where the `MnemonicSigner` reads its mnemonic from.
+#[derive(Clone)]
+pub enum SecretSource {
+    /// Environment variables (also read from a `.env` file) holding the mnemonic and, optionally, the passphrase.
+    Env {
+        /// The mnemonic variable name.
+        mnemonic: String,
+        /// The passphrase variable name.
+        passphrase: String,
+    },
+    /// A file with the mnemonic on its first line and, optionally, the passphrase on the second line.
+    File(PathBuf),
+    /// A callback returning the secret, e.g. after prompting the user.
+    Callback(Arc<dyn Fn() -> crate::Result<MnemonicSecret> + Send + Sync>),
+}
+
+impl Default for SecretSource {
+    fn default() -> Self {
+        Self::Env {
+            mnemonic: MNEMONIC_ENV_VAR.to_string(),
+            passphrase: MNEMONIC_PASSPHRASE_ENV_VAR.to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env { mnemonic, passphrase } => f
+                .debug_struct("Env")
+                .field("mnemonic", mnemonic)
+                .field("passphrase", passphrase)
+                .finish(),
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Callback(_) => f.write_str("Callback"),
+        }
+    }
+}
+
+impl SecretSource {
+    /// Reads the secret; `None` if the source doesn't have a mnemonic.
+    fn read(&self) -> crate::Result<Option<MnemonicSecret>> {
+        match self {
+            Self::Env { mnemonic, passphrase } => {
+                let _ = dotenv::dotenv();
+                Ok(env::var(mnemonic)
+                    .ok()
+                    .map(|mnemonic| MnemonicSecret::new(mnemonic, env::var(passphrase).ok())))
+            }
+            Self::File(path) => {
+                if !path.exists() {
+                    return Ok(None);
+                }
+                let mut contents = fs::read_to_string(path)?;
+                let secret = {
+                    let mut lines = contents.lines().map(str::trim);
+                    lines
+                        .next()
+                        .filter(|mnemonic| !mnemonic.is_empty())
+                        .map(|mnemonic| MnemonicSecret::new(mnemonic, lines.next().map(str::to_string)))
+                };
+                contents.zeroize();
+                Ok(secret)
+            }
+            Self::Callback(callback) => callback().map(Some),
+        }
+    }
+}
+
+/// A seed buffer wiped from memory when dropped.
+struct SeedBuffer([u8; PBKDF2_BYTES]);
+
+impl SeedBuffer {
+    fn ed25519_seed(&self) -> crate::Result<ed25519::Ed25519Seed> {
+        ed25519::Ed25519Seed::from_bytes(&self.0).map_err(|e| anyhow::anyhow!(e.to_string()).into())
+    }
+}
+
+impl Drop for SeedBuffer {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[derive(Default)]
+struct Cache {
+    /// The seeds by whether they take the legacy passphrase.
+    seeds: HashMap<bool, SeedBuffer>,
+    /// Derived addresses by legacy passphrase usage and BIP32 path.
+    addresses: HashMap<(bool, String), iota::Address>,
+}
+
+/// A signer deriving the account keys from a BIP39 mnemonic read from a `SecretSource`.
+///
+/// The seed is derived once and kept in memory, along with the derived addresses, until `clear_cache` is called.
+/// The signer never prints nor persists the mnemonic.
+#[derive(Default)]
+pub struct MnemonicSigner {
+    source: SecretSource,
+    cache: Mutex<Cache>,
+}
+
+impl MnemonicSigner {
+    /// Initialises the signer with the given secret source.
+    pub fn new(source: SecretSource) -> Self {
+        Self {
+            source,
+            cache: Default::default(),
+        }
+    }
+
+    /// Wipes the cached seed and derived addresses; the secret is read from the source again on the next use.
+    pub fn clear_cache(&self) {
+        *self.cache.lock().unwrap() = Default::default();
+    }
+
+    /// Runs the callback with the seed of the account, reading the secret from the source if it's not cached.
+    fn with_seed<T, F: FnOnce(&ed25519::Ed25519Seed, &mut Cache) -> crate::Result<T>>(
+        &self,
+        account: &Account,
+        cb: F,
+    ) -> crate::Result<T> {
+        let legacy_passphrase = *account.legacy_mnemonic_passphrase();
+        let mut cache = self.cache.lock().unwrap();
+        if !cache.seeds.contains_key(&legacy_passphrase) {
+            let secret = self
+                .source
+                .read()?
+                .ok_or_else(|| anyhow::anyhow!("the mnemonic wasn't found on the secret source {:?}", self.source))?;
+            cache.seeds.insert(legacy_passphrase, secret.seed(legacy_passphrase));
+        }
+        let seed = cache.seeds[&legacy_passphrase].ed25519_seed()?;
+        cb(&seed, &mut cache)
+    }
+}
+
+/// Signs `data` with the key derived from the seed on the given path.
+fn sign_data_with_seed(
+    seed: &ed25519::Ed25519Seed,
+    derivation_path: &str,
+    data: &[u8],
+) -> crate::Result<super::DataSignature> {
+    let private_key = ed25519::Ed25519PrivateKey::generate_from_seed(
+        seed,
+        &BIP32Path::from_str(derivation_path).map_err(|e| anyhow::anyhow!(e.to_string()))?,
+    )
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let public_key = private_key.generate_public_key().to_bytes();
+    let signature = private_key.sign(&super::signed_data_payload(data)).to_bytes();
+    Ok(super::DataSignature::new(public_key, signature))
+}
+
+fn derive_into_address(private_key: ed25519::Ed25519PrivateKey) -> String {
+    let public_key = private_key.generate_public_key().to_bytes();
+    // Hash the public key to get the address
+    let mut hasher = VarBlake2b::new(32).unwrap();
+    hasher.update(public_key);
+    let mut result = vec![1];
+    hasher.finalize_variable(|res| {
+        result.extend(res.to_vec());
+    });
+
+    bech32::encode("iot", result.to_base32()).unwrap()
+}
+
+impl super::Signer for MnemonicSigner {
+    fn init_account(&self, account: &Account, mnemonic: Option<String>) -> crate::Result<String> {
+        if let Some(mnemonic) = mnemonic {
+            let legacy_passphrase = *account.legacy_mnemonic_passphrase();
+            let source_secret = self.source.read()?;
+            let passphrase = source_secret.as_ref().and_then(|secret| secret.passphrase.clone());
+            let provided_seed = MnemonicSecret::new(mnemonic, passphrase).seed(legacy_passphrase);
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(secret) = source_secret {
+                if !cache.seeds.contains_key(&legacy_passphrase) {
+                    cache.seeds.insert(legacy_passphrase, secret.seed(legacy_passphrase));
+                }
+            }
+            match cache.seeds.get(&legacy_passphrase) {
+                Some(seed) if seed.0 != provided_seed.0 => {
+                    return Err(anyhow::anyhow!("the mnemonic doesn't match the signer's mnemonic").into());
+                }
+                Some(_) => {}
+                // the signer never keeps a mnemonic its source can't give back after a restart
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "the mnemonic wasn't found on the secret source {:?}, it must be stored there first",
+                        self.source
+                    )
+                    .into());
+                }
+            }
+        } else {
+            // fail early if the source doesn't have a mnemonic
+            self.with_seed(account, |_, _| Ok(()))?;
+        }
+        Ok(thread_rng().gen_ascii_chars().take(10).collect())
+    }
+
+    fn generate_address(
+        &self,
+        account: &Account,
+        address_index: usize,
+        internal: bool,
+    ) -> crate::Result<iota::Address> {
+        let derivation_path = account.derivation_path(address_index, internal);
+        let cache_key = (*account.legacy_mnemonic_passphrase(), derivation_path.clone());
+        self.with_seed(account, |seed, cache| {
+            if let Some(address) = cache.addresses.get(&cache_key) {
+                return Ok(address.clone());
+            }
+            let private_key = ed25519::Ed25519PrivateKey::generate_from_seed(
+                seed,
+                &BIP32Path::from_str(&derivation_path).map_err(|e| anyhow::anyhow!(e.to_string()))?,
+            )
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            let address = crate::address::parse(derive_into_address(private_key))?;
+            cache.addresses.insert(cache_key, address.clone());
+            Ok(address)
+        })
+    }
+
+    fn sign_data(
+        &self,
+        account: &Account,
+        address_index: usize,
+        internal: bool,
+        data: &[u8],
+    ) -> crate::Result<super::DataSignature> {
+        let derivation_path = account.derivation_path(address_index, internal);
+        self.with_seed(account, |seed, _| sign_data_with_seed(seed, &derivation_path, data))
+    }
+
+    fn sign_raw(
+        &self,
+        account: &Account,
+        address_path: &BIP32Path,
+        message: &[u8],
+    ) -> crate::Result<([u8; 32], [u8; 64])> {
+        self.with_seed(account, |seed, _| {
+            let private_key = ed25519::Ed25519PrivateKey::generate_from_seed(seed, address_path)
+                .map_err(|_| anyhow::anyhow!("invalid parameter: seed inputs"))?;
+            let public_key = private_key.generate_public_key().to_bytes();
+            Ok((public_key, private_key.sign(message).to_bytes()))
+        })
+    }
+
+    fn sign_message(
+        &self,
+        account: &Account,
+        essence: &iota::TransactionEssence,
+        inputs: &mut Vec<super::TransactionInput>,
+    ) -> crate::Result<Vec<iota::UnlockBlock>> {
+        super::build_unlock_blocks(essence, inputs, |address_path, message| {
+            self.sign_raw(account, address_path, message)
+        })
+    }
+
+    fn capability(&self) -> super::SignerCapability {
+        super::SignerCapability::Sign
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MnemonicSecret, MnemonicSigner, SecretSource};
+    use crate::{
+        mock_node::MockLedger,
+        signing::{Signer, SignerType},
+        test_utils::get_mock_account_manager,
+    };
+    use rusty_fork::rusty_fork_test;
+    use std::sync::Arc;
+
+    const MNEMONIC: &str = "giant dynamic museum toddler six deny defense ostrich bomb access mercy blood explain muscle shoot shallow glad autumn author calm heavy hawk abuse rally";
+
+    rusty_fork_test! {
+        #[test]
+        fn secret_sources() {
+            let (manager, node) = get_mock_account_manager(MockLedger::new());
+            let secret_path = manager.storage_path().join("mnemonic");
+            std::fs::create_dir_all(manager.storage_path()).unwrap();
+            std::fs::write(&secret_path, format!("{}\npassphrase\n", MNEMONIC)).unwrap();
+
+            let signer_type = SignerType::Custom("file-mnemonic".to_string());
+            manager
+                .context()
+                .set_signer(signer_type.clone(), MnemonicSigner::new(SecretSource::File(secret_path)))
+                .unwrap();
+            let account = manager
+                .create_account(node.client_options())
+                .signer_type(signer_type)
+                .initialise()
+                .unwrap();
+
+            let callback_signer = MnemonicSigner::new(SecretSource::Callback(Arc::new(|| {
+                Ok(MnemonicSecret::new(MNEMONIC, Some("passphrase".to_string())))
+            })));
+            let address = callback_signer.generate_address(&account, 0, false).unwrap();
+            assert_eq!(account.addresses()[0].address(), &address);
+            // the second derivation is served from the cache
+            assert_eq!(callback_signer.generate_address(&account, 0, false).unwrap(), address);
+
+            assert!(callback_signer
+                .init_account(&account, Some("another mnemonic".to_string()))
+                .is_err());
+
+            // the accounts flagged by the storage migration take the legacy passphrase if the secret has none
+            let no_passphrase_signer =
+                MnemonicSigner::new(SecretSource::Callback(Arc::new(|| Ok(MnemonicSecret::new(MNEMONIC, None)))));
+            let legacy_passphrase_signer = MnemonicSigner::new(SecretSource::Callback(Arc::new(|| {
+                Ok(MnemonicSecret::new(MNEMONIC, Some("password".to_string())))
+            })));
+            let mut legacy_account = account.clone();
+            legacy_account.set_legacy_mnemonic_passphrase(true);
+            let legacy_address = no_passphrase_signer.generate_address(&legacy_account, 0, false).unwrap();
+            assert_eq!(
+                legacy_address,
+                legacy_passphrase_signer.generate_address(&account, 0, false).unwrap()
+            );
+            assert_ne!(
+                legacy_address,
+                no_passphrase_signer.generate_address(&account, 0, false).unwrap()
+            );
+
+            let empty_signer = MnemonicSigner::new(SecretSource::File(manager.storage_path().join("missing")));
+            assert!(empty_signer.init_account(&account, None).is_err());
+            assert!(empty_signer.init_account(&account, Some(MNEMONIC.to_string())).is_err());
+        }
+    }
+}
@@ -3,7 +3,8 @@
 
 use std::{
     collections::HashMap,
-    sync::{Arc, RwLock},
+    path::PathBuf,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
 
 use crate::account::Account;
@@ -12,13 +13,24 @@ use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use slip10::BIP32Path;
 
+mod data;
+pub(crate) use data::{hash, signed_data_payload};
+pub use data::{verify, DataSignature};
+/// Signer running on a separate process, reached through a documented protocol.
+pub mod external;
 mod stronghold;
 use self::stronghold::StrongholdSigner;
-mod env_mnemonic;
-use env_mnemonic::EnvMnemonicSigner;
+mod mnemonic;
+pub use mnemonic::{MnemonicSecret, MnemonicSigner, SecretSource, MNEMONIC_ENV_VAR, MNEMONIC_PASSPHRASE_ENV_VAR};
+mod unlock;
+pub use unlock::build_unlock_blocks;
+mod watch_only;
+use watch_only::WatchOnlySigner;
+pub use watch_only::{WatchOnlyKey, WatchOnlyKeys, WatchedKey};
 
-type BoxedSigner = Box<dyn Signer + Sync + Send>;
-type Signers = Arc<RwLock<HashMap<SignerType, BoxedSigner>>>;
+pub(crate) type BoxedSigner = Box<dyn Signer + Sync + Send>;
+pub(crate) type SignerRegistry = HashMap<SignerType, BoxedSigner>;
+type Signers = Arc<RwLock<SignerRegistry>>;
 static SIGNERS_INSTANCE: OnceCell<Signers> = OnceCell::new();
 
 /// The signer types.
@@ -28,12 +40,27 @@ pub enum SignerType {
     /// Stronghold signer.
     #[cfg(feature = "stronghold")]
     Stronghold,
-    /// Mnemonic through environment variable.
+    /// Mnemonic through environment variable, read by a `MnemonicSigner` with the default `SecretSource`.
     EnvMnemonic,
+    /// Watch-only account, without keys.
+    WatchOnly,
     /// Custom signer with its identifier.
     Custom(String),
 }
 
+/// What a signer can do with the accounts it manages.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SignerCapability {
+    /// Derives addresses and signs transactions and data.
+    Sign,
+    /// Derives addresses and signs transactions, but can't sign arbitrary data.
+    SignTransactions,
+    /// Only derives addresses; signing always fails.
+    DeriveOnly,
+    /// Derives addresses and signs transactions once the user confirms them, e.g. on a hardware device.
+    SignWithConfirmation,
+}
+
 /// One of the transaction inputs and its address information needed for signing it.
 pub struct TransactionInput {
     /// The input.
@@ -48,15 +75,61 @@ pub struct TransactionInput {
 pub trait Signer {
     /// Initialises an account.
     fn init_account(&self, account: &Account, mnemonic: Option<String>) -> crate::Result<String>;
+    /// Initialises an account whose seed takes the given BIP-39 passphrase.
+    /// Defaults to `init_account`, failing if a passphrase is provided.
+    fn init_account_with_passphrase(
+        &self,
+        account: &Account,
+        mnemonic: Option<String>,
+        passphrase: Option<String>,
+    ) -> crate::Result<String> {
+        match passphrase {
+            Some(_) => Err(anyhow::anyhow!("the signer doesn't take a BIP-39 passphrase").into()),
+            None => self.init_account(account, mnemonic),
+        }
+    }
     /// Generates an address.
     fn generate_address(&self, account: &Account, index: usize, internal: bool) -> crate::Result<iota::Address>;
     /// Signs message.
+    /// Signers that hold the ed25519 keys can build the unlock blocks with `build_unlock_blocks`.
     fn sign_message(
         &self,
         account: &Account,
         essence: &iota::TransactionEssence,
         inputs: &mut Vec<TransactionInput>,
     ) -> crate::Result<Vec<iota::UnlockBlock>>;
+    /// Signs arbitrary data with the key of an account address, to prove its ownership.
+    /// The signed message is `signed_data_payload(data)`, which `verify` checks. Signers that can't sign data report
+    /// it with their `capability`.
+    fn sign_data(
+        &self,
+        account: &Account,
+        address_index: usize,
+        internal: bool,
+        data: &[u8],
+    ) -> crate::Result<DataSignature> {
+        Err(anyhow::anyhow!("the signer can't sign arbitrary data").into())
+    }
+    /// Signs a raw message with the ed25519 key on the given derivation path, returning the public key and the
+    /// signature. Needed to serve the signer with `external::serve`.
+    fn sign_raw(
+        &self,
+        account: &Account,
+        address_path: &BIP32Path,
+        message: &[u8],
+    ) -> crate::Result<([u8; 32], [u8; 64])> {
+        Err(anyhow::anyhow!("the signer can't sign raw messages").into())
+    }
+    /// Removes what the signer stored for the account when initialising it, e.g. after a failed signer migration.
+    /// Defaults to doing nothing, for the signers that don't store anything per account.
+    fn remove_account(&self, account: &Account) -> crate::Result<()> {
+        Ok(())
+    }
+    /// What the signer can do; defaults to `SignerCapability::SignTransactions`, so the signers overriding
+    /// `sign_data` report `SignerCapability::Sign`.
+    fn capability(&self) -> SignerCapability {
+        SignerCapability::SignTransactions
+    }
 }
 
 fn default_signers() -> Signers {
@@ -72,24 +145,89 @@ fn default_signers() -> Signers {
 
     signers.insert(
         SignerType::EnvMnemonic,
-        Box::new(EnvMnemonicSigner::default()) as Box<dyn Signer + Sync + Send>,
+        Box::new(MnemonicSigner::default()) as Box<dyn Signer + Sync + Send>,
+    );
+
+    signers.insert(
+        SignerType::WatchOnly,
+        Box::new(WatchOnlySigner::default()) as Box<dyn Signer + Sync + Send>,
     );
 
     Arc::new(RwLock::new(signers))
 }
 
-/// Sets the signer interface for the given type.
-pub fn set_signer<S: Signer + Sync + Send + 'static>(signer_type: SignerType, signer: S) {
-    let mut instances = SIGNERS_INSTANCE.get_or_init(default_signers).write().unwrap();
+/// Locks a signer registry for reading, failing if a panic poisoned it.
+pub(crate) fn read_registry(signers: &RwLock<SignerRegistry>) -> crate::Result<RwLockReadGuard<'_, SignerRegistry>> {
+    signers
+        .read()
+        .map_err(|_| anyhow::anyhow!("the signer registry is poisoned").into())
+}
+
+/// Locks a signer registry for writing, failing if a panic poisoned it.
+pub(crate) fn write_registry(signers: &RwLock<SignerRegistry>) -> crate::Result<RwLockWriteGuard<'_, SignerRegistry>> {
+    signers
+        .write()
+        .map_err(|_| anyhow::anyhow!("the signer registry is poisoned").into())
+}
+
+fn default_registry() -> &'static RwLock<SignerRegistry> {
+    SIGNERS_INSTANCE.get_or_init(default_signers)
+}
+
+/// Sets the default signer interface for the given type, replacing the registered one.
+/// The default signers are shared by every wallet instance that doesn't set its own signer for the type.
+pub fn set_signer<S: Signer + Sync + Send + 'static>(signer_type: SignerType, signer: S) -> crate::Result<()> {
+    write_registry(default_registry())?.insert(signer_type, Box::new(signer));
+    Ok(())
+}
+
+/// Registers the default signer interface for the given type.
+/// Fails if a signer is already registered for the type.
+pub fn register_signer<S: Signer + Sync + Send + 'static>(signer_type: SignerType, signer: S) -> crate::Result<()> {
+    let mut instances = write_registry(default_registry())?;
+    if instances.contains_key(&signer_type) {
+        return Err(crate::WalletError::SignerAlreadyRegistered(signer_type));
+    }
     instances.insert(signer_type, Box::new(signer));
+    Ok(())
+}
+
+/// Unregisters the default signer interface of the given type.
+pub fn unregister_signer(signer_type: &SignerType) -> crate::Result<()> {
+    match write_registry(default_registry())?.remove(signer_type) {
+        Some(_) => Ok(()),
+        None => Err(crate::WalletError::SignerNotSet(signer_type.clone())),
+    }
+}
+
+/// Lists the registered default signers and their capabilities.
+pub fn registered_signers() -> crate::Result<Vec<(SignerType, SignerCapability)>> {
+    Ok(read_registry(default_registry())?
+        .iter()
+        .map(|(signer_type, signer)| (signer_type.clone(), signer.capability()))
+        .collect())
+}
+
+/// Gets the default signer interface.
+pub(crate) fn with_default_signer<T, F: FnOnce(&BoxedSigner) -> crate::Result<T>>(
+    signer_type: &SignerType,
+    cb: F,
+) -> crate::Result<T> {
+    let instances = read_registry(default_registry())?;
+    match instances.get(signer_type) {
+        Some(instance) => cb(instance),
+        None => Err(crate::WalletError::SignerNotSet(signer_type.clone())),
+    }
 }
 
-/// Gets the signer interface.
-pub(crate) fn with_signer<T, F: FnOnce(&BoxedSigner) -> T>(signer_type: &SignerType, cb: F) -> T {
-    let instances = SIGNERS_INSTANCE.get_or_init(default_signers).read().unwrap();
-    if let Some(instance) = instances.get(signer_type) {
-        cb(instance)
-    } else {
-        panic!(format!("signer not initialized for type {:?}", signer_type))
+/// Gets the signer interface of the wallet instance associated with the storage path.
+pub(crate) fn with_signer<T, F: FnOnce(&BoxedSigner) -> crate::Result<T>>(
+    storage_path: &PathBuf,
+    signer_type: &SignerType,
+    cb: F,
+) -> crate::Result<T> {
+    match crate::context::get(storage_path) {
+        Some(context) => context.with_signer(signer_type, cb),
+        None => with_default_signer(signer_type, cb),
     }
 }
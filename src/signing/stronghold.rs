@@ -5,26 +5,53 @@ use crate::account::{account_id_to_stronghold_record_id, Account};
 
 use std::convert::TryInto;
 
+/// The BIP-39 passphrase of the accounts created without one.
+/// The accounts were always created with it before the passphrase was configurable, so it can't change.
+const DEFAULT_PASSPHRASE: &str = "password";
+
 #[derive(Default)]
 pub struct StrongholdSigner;
 
+/// Stronghold derives the account addresses on the default IOTA path, so it can't use custom schemes.
+fn check_derivation_scheme(account: &Account) -> crate::Result<()> {
+    if account.derivation_scheme().is_default() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("the Stronghold signer only supports the default derivation scheme").into())
+    }
+}
+
 impl super::Signer for StrongholdSigner {
     fn init_account(&self, account: &Account, mnemonic: Option<String>) -> crate::Result<String> {
-        let stronghold_account_res: crate::Result<stronghold::Account> =
-            crate::with_stronghold_from_path(account.storage_path(), |stronghold| {
-                let created_at_timestamp: u128 = account.created_at().timestamp().try_into().unwrap(); // safe to unwrap since it's > 0;
+        self.init_account_with_passphrase(account, mnemonic, None)
+    }
+
+    fn init_account_with_passphrase(
+        &self,
+        account: &Account,
+        mnemonic: Option<String>,
+        passphrase: Option<String>,
+    ) -> crate::Result<String> {
+        check_derivation_scheme(account)?;
+        // the snapshot password only encrypts the snapshot; the seed takes its own passphrase
+        let passphrase = passphrase.unwrap_or_else(|| DEFAULT_PASSPHRASE.to_string());
+        let stronghold_account = crate::with_stronghold_from_path(
+            account.storage_path(),
+            |stronghold| -> crate::Result<stronghold::Account> {
+                // safe to unwrap since it's > 0
+                let created_at_timestamp: u128 = account.created_at().timestamp().try_into().unwrap();
                 let account = match mnemonic {
                     Some(mnemonic) => stronghold.account_import(
                         Some(created_at_timestamp),
                         Some(created_at_timestamp),
                         mnemonic,
-                        Some("password"),
+                        Some(passphrase.as_str()),
                     )?,
-                    None => stronghold.account_create(Some("password".to_string()))?,
+                    None => stronghold.account_create(Some(passphrase.clone()))?,
                 };
                 Ok(account)
-            });
-        let stronghold_account = stronghold_account_res?;
+            },
+        )?;
         let id = stronghold_account.id();
         Ok(hex::encode(id))
     }
@@ -35,6 +62,7 @@ impl super::Signer for StrongholdSigner {
         address_index: usize,
         internal: bool,
     ) -> crate::Result<iota::Address> {
+        check_derivation_scheme(account)?;
         crate::with_stronghold_from_path(account.storage_path(), |stronghold| {
             let address_str = stronghold.address_get(
                 &account_id_to_stronghold_record_id(account.id())?,
@@ -69,4 +97,11 @@ impl super::Signer for StrongholdSigner {
         })
         .map_err(|e| e.into())
     }
+
+    fn remove_account(&self, account: &Account) -> crate::Result<()> {
+        crate::with_stronghold_from_path(account.storage_path(), |stronghold| -> crate::Result<()> {
+            stronghold.account_remove(&account_id_to_stronghold_record_id(account.id())?)?;
+            Ok(())
+        })
+    }
 }
@@ -0,0 +1,105 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::TransactionInput;
+
+use bee_signing_ext::binary::BIP32Path;
+use iota::{
+    common::packable::Packable, Ed25519Signature, ReferenceUnlock, SignatureUnlock, TransactionEssence, UnlockBlock,
+};
+
+/// Builds the unlock blocks of a transaction essence, so a signer only has to provide raw ed25519 signing.
+///
+/// The inputs are sorted in the essence order. The first input of each address gets a signature unlock block and the
+/// next inputs of the same address reference it. `sign` signs the serialized essence with the key on the given path
+/// and returns the public key and the signature.
+pub fn build_unlock_blocks<F>(
+    essence: &TransactionEssence,
+    inputs: &mut Vec<TransactionInput>,
+    mut sign: F,
+) -> crate::Result<Vec<UnlockBlock>>
+where
+    F: FnMut(&BIP32Path, &[u8]) -> crate::Result<([u8; 32], [u8; 64])>,
+{
+    let serialized_essence = essence.pack_new();
+    inputs.sort_by(|a, b| a.input.cmp(&b.input));
+
+    let mut unlock_blocks = vec![];
+    // the signature block index of each signed address path
+    let mut signature_indexes: Vec<(&BIP32Path, usize)> = vec![];
+    for input in inputs.iter() {
+        match signature_indexes.iter().find(|(path, _)| *path == &input.address_path) {
+            Some((_, block_index)) => unlock_blocks.push(UnlockBlock::Reference(
+                ReferenceUnlock::new(*block_index as u16)
+                    .map_err(|_| anyhow::anyhow!("failed to create reference unlock block"))?,
+            )),
+            None => {
+                let (public_key, signature) = sign(&input.address_path, &serialized_essence)?;
+                signature_indexes.push((&input.address_path, unlock_blocks.len()));
+                unlock_blocks.push(UnlockBlock::Signature(SignatureUnlock::Ed25519(Ed25519Signature::new(
+                    public_key,
+                    Box::new(signature),
+                ))));
+            }
+        }
+    }
+    Ok(unlock_blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build_unlock_blocks;
+    use crate::signing::TransactionInput;
+
+    use bee_signing_ext::binary::BIP32Path;
+    use iota::{
+        message::prelude::{Ed25519Address, Input, SignatureLockedSingleOutput, TransactionId, UTXOInput},
+        Address, TransactionEssence, UnlockBlock,
+    };
+    use std::num::NonZeroU64;
+
+    fn input(transaction_id: u8, index: u16, address_path: &str) -> TransactionInput {
+        TransactionInput {
+            input: Input::from(UTXOInput::new(TransactionId::new([transaction_id; 32]), index).unwrap()),
+            address_index: 0,
+            address_path: BIP32Path::from_str(address_path).unwrap(),
+        }
+    }
+
+    #[test]
+    fn unlock_blocks() {
+        let mut inputs = vec![
+            input(2, 0, "m/44H/4218H/0H/0H/0H"),
+            input(1, 1, "m/44H/4218H/0H/1H/0H"),
+            input(1, 0, "m/44H/4218H/0H/0H/0H"),
+        ];
+        let mut essence_builder = TransactionEssence::builder();
+        for input in &inputs {
+            essence_builder = essence_builder.add_input(input.input.clone());
+        }
+        let essence = essence_builder
+            .add_output(
+                SignatureLockedSingleOutput::new(
+                    Address::Ed25519(Ed25519Address::new([0; 32])),
+                    NonZeroU64::new(1).unwrap(),
+                )
+                .into(),
+            )
+            .finish()
+            .unwrap();
+
+        let mut signed_paths = vec![];
+        let unlock_blocks = build_unlock_blocks(&essence, &mut inputs, |path, _| {
+            signed_paths.push(path.clone());
+            Ok(([0; 32], [0; 64]))
+        })
+        .unwrap();
+
+        // the internal and the public address share the key index but not the key
+        assert_eq!(signed_paths.len(), 2);
+        assert_eq!(inputs[0].input, input(1, 0, "m/44H/4218H/0H/0H/0H").input);
+        assert!(matches!(unlock_blocks[0], UnlockBlock::Signature(_)));
+        assert!(matches!(unlock_blocks[1], UnlockBlock::Signature(_)));
+        assert!(matches!(&unlock_blocks[2], UnlockBlock::Reference(reference) if reference.index() == 0));
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{hash, DataSignature, Signer, SignerCapability, TransactionInput};
+use crate::{
+    account::Account,
+    address::{Ed25519Address, IotaAddress},
+};
+
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+/// The exported public material a watch-only account is created from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchOnlyKeys {
+    /// The index of the watched account on its signer, used to build the key derivation paths.
+    #[serde(rename = "accountIndex")]
+    pub account_index: usize,
+    /// The watched keys.
+    pub keys: Vec<WatchOnlyKey>,
+}
+
+/// One of the watched keys and its position on the derivation path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchOnlyKey {
+    /// The key index.
+    #[serde(rename = "keyIndex")]
+    pub key_index: usize,
+    /// Whether the key is on the internal (change) chain.
+    #[serde(default)]
+    pub internal: bool,
+    /// The key material.
+    pub key: WatchedKey,
+}
+
+/// The exported material of a watched key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum WatchedKey {
+    /// A bech32 address.
+    Address(String),
+    /// A hex encoded ed25519 public key.
+    PublicKey(String),
+}
+
+impl WatchedKey {
+    /// Gets the watched address.
+    fn address(&self) -> crate::Result<IotaAddress> {
+        match self {
+            Self::Address(address) => crate::address::parse(address.to_string()),
+            Self::PublicKey(public_key) => {
+                let public_key: [u8; 32] = hex::decode(public_key)
+                    .ok()
+                    .and_then(|public_key| public_key.try_into().ok())
+                    .ok_or_else(|| anyhow::anyhow!("invalid ed25519 public key `{}`", public_key))?;
+                Ok(IotaAddress::Ed25519(Ed25519Address::new(hash(&public_key))))
+            }
+        }
+    }
+}
+
+impl WatchOnlyKeys {
+    /// Gets the watched addresses with their key index and internal flag.
+    pub(crate) fn addresses(&self) -> crate::Result<Vec<(usize, bool, IotaAddress)>> {
+        if self.keys.is_empty() {
+            return Err(anyhow::anyhow!("a watch-only account needs at least one address").into());
+        }
+        let mut addresses: Vec<(usize, bool, IotaAddress)> = vec![];
+        for key in &self.keys {
+            if addresses
+                .iter()
+                .any(|(key_index, internal, _)| *key_index == key.key_index && *internal == key.internal)
+            {
+                return Err(anyhow::anyhow!("duplicated watch-only key path {}", key.key_index).into());
+            }
+            addresses.push((key.key_index, key.internal, key.key.address()?));
+        }
+        Ok(addresses)
+    }
+}
+
+/// The signer of the watch-only accounts.
+/// It doesn't hold any key: the addresses are the ones the account was created with, and signing always fails.
+#[derive(Default)]
+pub struct WatchOnlySigner;
+
+impl Signer for WatchOnlySigner {
+    fn init_account(&self, account: &Account, _mnemonic: Option<String>) -> crate::Result<String> {
+        if account.addresses().is_empty() {
+            return Err(anyhow::anyhow!("a watch-only account needs at least one address").into());
+        }
+        let addresses: String = account
+            .addresses()
+            .iter()
+            .map(|address| address.address().to_bech32())
+            .collect();
+        Ok(hex::encode(hash(addresses.as_bytes())))
+    }
+
+    fn generate_address(&self, account: &Account, index: usize, internal: bool) -> crate::Result<IotaAddress> {
+        account
+            .addresses()
+            .iter()
+            .find(|address| *address.key_index() == index && *address.internal() == internal)
+            .map(|address| address.address().clone())
+            .ok_or(crate::WalletError::WatchOnlyAccount)
+    }
+
+    fn sign_message(
+        &self,
+        _account: &Account,
+        _essence: &iota::TransactionEssence,
+        _inputs: &mut Vec<TransactionInput>,
+    ) -> crate::Result<Vec<iota::UnlockBlock>> {
+        Err(crate::WalletError::WatchOnlyAccount)
+    }
+
+    fn sign_data(
+        &self,
+        _account: &Account,
+        _address_index: usize,
+        _internal: bool,
+        _data: &[u8],
+    ) -> crate::Result<DataSignature> {
+        Err(crate::WalletError::WatchOnlyAccount)
+    }
+
+    fn capability(&self) -> SignerCapability {
+        SignerCapability::DeriveOnly
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::StorageAdapter;
+use crate::account::AccountIdentifier;
+use std::sync::{Mutex, RwLock};
+
+type Accounts = Vec<(AccountIdentifier, String)>;
+
+/// In-memory storage adapter.
+/// The accounts are lost when the adapter is dropped, so it's meant to be used on tests and ephemeral wallets.
+#[derive(Default)]
+pub struct MemoryStorageAdapter {
+    accounts: RwLock<Accounts>,
+    /// The accounts before the transaction in progress, restored on rollback.
+    transaction_snapshot: Mutex<Option<Accounts>>,
+}
+
+impl MemoryStorageAdapter {
+    /// Initialises the storage adapter.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl StorageAdapter for MemoryStorageAdapter {
+    fn get(&self, account_id: &AccountIdentifier) -> crate::Result<String> {
+        let accounts = self.accounts.read().unwrap();
+        let account = match account_id {
+            AccountIdentifier::Id(_) => accounts.iter().find(|(id, _)| id == account_id),
+            AccountIdentifier::Index(index) => accounts.get(*index),
+        };
+        account
+            .map(|(_, account)| account.clone())
+            .ok_or(crate::WalletError::AccountNotFound)
+    }
+
+    fn get_all(&self) -> crate::Result<Vec<String>> {
+        let accounts = self.accounts.read().unwrap();
+        Ok(accounts.iter().map(|(_, account)| account.clone()).collect())
+    }
+
+    fn set(&self, account_id: &AccountIdentifier, account: String) -> crate::Result<()> {
+        if let AccountIdentifier::Index(_) = account_id {
+            return Err(anyhow::anyhow!("only Id is supported").into());
+        }
+        let mut accounts = self.accounts.write().unwrap();
+        match accounts.iter().position(|(id, _)| id == account_id) {
+            Some(position) => accounts[position].1 = account,
+            None => accounts.push((account_id.clone(), account)),
+        }
+        Ok(())
+    }
+
+    fn remove(&self, account_id: &AccountIdentifier) -> crate::Result<()> {
+        let mut accounts = self.accounts.write().unwrap();
+        let position = match account_id {
+            AccountIdentifier::Id(_) => accounts.iter().position(|(id, _)| id == account_id),
+            AccountIdentifier::Index(index) if *index < accounts.len() => Some(*index),
+            AccountIdentifier::Index(_) => None,
+        };
+        match position {
+            Some(position) => {
+                accounts.remove(position);
+                Ok(())
+            }
+            None => Err(crate::WalletError::AccountNotFound),
+        }
+    }
+
+    fn begin_transaction(&self) -> crate::Result<()> {
+        let mut transaction_snapshot = self.transaction_snapshot.lock().unwrap();
+        if transaction_snapshot.is_some() {
+            return Err(anyhow::anyhow!("a storage transaction is already in progress").into());
+        }
+        transaction_snapshot.replace(self.accounts.read().unwrap().clone());
+        Ok(())
+    }
+
+    fn commit_transaction(&self) -> crate::Result<()> {
+        self.transaction_snapshot
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("no storage transaction in progress"))?;
+        Ok(())
+    }
+
+    fn rollback_transaction(&self) -> crate::Result<()> {
+        let snapshot = self
+            .transaction_snapshot
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("no storage transaction in progress"))?;
+        *self.accounts.write().unwrap() = snapshot;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryStorageAdapter;
+    use crate::{account::AccountIdentifier, storage::StorageAdapter};
+
+    #[test]
+    fn rollback_transaction() {
+        let adapter = MemoryStorageAdapter::new();
+        let account_id = AccountIdentifier::Id("id".to_string());
+        adapter.set(&account_id, "account".to_string()).unwrap();
+
+        adapter.begin_transaction().unwrap();
+        adapter.set(&account_id, "updated account".to_string()).unwrap();
+        adapter.remove(&account_id).unwrap();
+        adapter.rollback_transaction().unwrap();
+
+        assert_eq!(adapter.get(&account_id).unwrap(), "account".to_string());
+        assert_eq!(
+            adapter.get(&AccountIdentifier::Index(0)).unwrap(),
+            "account".to_string()
+        );
+    }
+}
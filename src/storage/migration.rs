@@ -0,0 +1,327 @@
+// Copyright 2020 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::account::AccountIdentifier;
+use getset::Getters;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// The key of the storage version stamp on the stored account records.
+pub(crate) const STORAGE_VERSION_KEY: &str = "storageVersion";
+/// The version of the stored records written by this library.
+pub const STORAGE_VERSION: u64 = 3;
+
+/// A migration step.
+struct Migration {
+    /// The storage version the step migrates the record to.
+    version: u64,
+    /// The step description, listed on the migration report.
+    description: &'static str,
+    /// Updates the account record (including its addresses, outputs and messages).
+    /// The nested records carry their own version stamp (e.g. messages merged from an older record), so the step must
+    /// only change the records that are below its version.
+    migrate: fn(&mut Value) -> crate::Result<()>,
+}
+
+/// The migration steps, sorted by version.
+/// Records without the version stamp are considered to be on version 0.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "add the storage version stamp to the account record",
+        migrate: add_storage_version,
+    },
+    Migration {
+        version: 2,
+        description: "add the storage version stamp to the addresses, outputs and messages",
+        migrate: add_storage_version,
+    },
+    Migration {
+        version: 3,
+        description: "keep the legacy mnemonic passphrase on the EnvMnemonic accounts",
+        migrate: flag_legacy_mnemonic_passphrase,
+    },
+];
+
+/// The version stamps are added by the migration runner, so there's nothing else to change.
+fn add_storage_version(_account: &mut Value) -> crate::Result<()> {
+    Ok(())
+}
+
+/// The `EnvMnemonic` signer used the `password` BIP39 passphrase when `IOTA_WALLET_MNEMONIC_PASSWORD` wasn't set,
+/// so the accounts created before the empty passphrase became the default keep it to derive the same addresses.
+fn flag_legacy_mnemonic_passphrase(account: &mut Value) -> crate::Result<()> {
+    if record_version(account) < 3 && account["signer_type"]["type"] == "EnvMnemonic" {
+        if let Value::Object(account) = account {
+            account.insert("legacyMnemonicPassphrase".to_string(), Value::Bool(true));
+        }
+    }
+    Ok(())
+}
+
+/// Gets the storage version of a record. Records without the version stamp are on version 0.
+fn record_version(record: &Value) -> u64 {
+    record[STORAGE_VERSION_KEY].as_u64().unwrap_or(0)
+}
+
+/// Runs the callback with every nested record of the account: its addresses, their outputs and its messages.
+fn for_each_nested_record<F: FnMut(&mut Value)>(account: &mut Value, mut cb: F) {
+    if let Some(Value::Array(addresses)) = account.get_mut("addresses") {
+        for address in addresses {
+            if let Some(Value::Array(outputs)) = address.get_mut("outputs") {
+                outputs.iter_mut().for_each(&mut cb);
+            }
+            cb(address);
+        }
+    }
+    if let Some(Value::Array(messages)) = account.get_mut("messages") {
+        messages.iter_mut().for_each(&mut cb);
+    }
+}
+
+/// The migration of an account record.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct AccountMigration {
+    /// The account identifier.
+    #[serde(rename = "accountId")]
+    account_id: AccountIdentifier,
+    /// The storage version of the stored record.
+    #[serde(rename = "fromVersion")]
+    from_version: u64,
+    /// The descriptions of the applied steps.
+    steps: Vec<String>,
+}
+
+/// The result of a storage migration.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct MigrationReport {
+    /// Whether the changes were persisted or not.
+    #[serde(rename = "dryRun")]
+    dry_run: bool,
+    /// The storage version the records were migrated to.
+    #[serde(rename = "toVersion")]
+    to_version: u64,
+    /// The migrated accounts. Records that are already on the latest version aren't listed.
+    accounts: Vec<AccountMigration>,
+}
+
+/// Adds the storage version stamp to the account record and its addresses, outputs and messages.
+pub(crate) fn stamp(account: &mut Value) {
+    stamp_version(account, STORAGE_VERSION);
+}
+
+fn stamp_version(account: &mut Value, version: u64) {
+    let stamp_record = |record: &mut Value| {
+        if let Value::Object(record) = record {
+            record.insert(STORAGE_VERSION_KEY.to_string(), Value::from(version));
+        }
+    };
+    for_each_nested_record(account, stamp_record);
+    stamp_record(account);
+}
+
+/// Applies the pending migration steps to the account record.
+/// Returns the version of the record before the migration and the applied steps.
+fn migrate_record(account: &mut Value) -> crate::Result<(u64, Vec<String>)> {
+    migrate_record_with(account, MIGRATIONS, STORAGE_VERSION)
+}
+
+/// Applies the given migration steps to the account record, up to `to_version`.
+/// The record version is the lowest version of the account and its nested records; fails if any of them is newer
+/// than `to_version`, since stamping it would downgrade its data.
+fn migrate_record_with(
+    account: &mut Value,
+    migrations: &[Migration],
+    to_version: u64,
+) -> crate::Result<(u64, Vec<String>)> {
+    let mut version = record_version(account);
+    let mut newest_version = version;
+    for_each_nested_record(account, |record| {
+        let nested_version = record_version(record);
+        version = version.min(nested_version);
+        newest_version = newest_version.max(nested_version);
+    });
+    if newest_version > to_version {
+        return Err(crate::WalletError::UnsupportedStorageVersion(newest_version));
+    }
+    let mut steps = vec![];
+    for migration in migrations.iter().filter(|migration| migration.version > version) {
+        (migration.migrate)(account)?;
+        steps.push(migration.description.to_string());
+    }
+    stamp_version(account, to_version);
+    Ok((version, steps))
+}
+
+/// Migrates an account record that isn't on the storage yet (e.g. from a backup) to the latest storage version.
+pub(crate) fn migrate_account(account: &str) -> crate::Result<String> {
+    let mut account: Value = serde_json::from_str(account)?;
+    migrate_record(&mut account)?;
+    Ok(account.to_string())
+}
+
+/// Migrates the stored accounts to the latest storage version on a single storage transaction.
+/// If `dry_run` is true, the storage isn't changed and the report lists what would be migrated.
+pub(crate) fn migrate(storage_path: &PathBuf, dry_run: bool) -> crate::Result<MigrationReport> {
+    super::transaction(storage_path, |storage| {
+        let mut accounts = vec![];
+        for account in storage.get_all()? {
+            let mut account: Value = serde_json::from_str(&account)?;
+            let (from_version, steps) = migrate_record(&mut account)?;
+            if steps.is_empty() {
+                continue;
+            }
+            let account_id: AccountIdentifier = serde_json::from_value(account["id"].clone())?;
+            if !dry_run {
+                storage.set(&account_id, account.to_string())?;
+            }
+            accounts.push(AccountMigration {
+                account_id,
+                from_version,
+                steps,
+            });
+        }
+        Ok(MigrationReport {
+            dry_run,
+            to_version: STORAGE_VERSION,
+            accounts,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        migrate_record, migrate_record_with, record_version, Migration, MIGRATIONS, STORAGE_VERSION,
+        STORAGE_VERSION_KEY,
+    };
+    use serde_json::{json, Value};
+
+    #[test]
+    fn migrate_unversioned_record() {
+        let mut account = json!({
+            "id": "id",
+            "messages": [{ "id": "message" }],
+            "addresses": [{ "address": "address", "outputs": [{ "amount": 1 }] }],
+        });
+        let (from_version, steps) = migrate_record(&mut account).unwrap();
+        assert_eq!(from_version, 0);
+        assert_eq!(steps.len(), MIGRATIONS.len());
+        assert_eq!(account[STORAGE_VERSION_KEY], STORAGE_VERSION);
+        assert_eq!(account["messages"][0][STORAGE_VERSION_KEY], STORAGE_VERSION);
+        assert_eq!(account["addresses"][0][STORAGE_VERSION_KEY], STORAGE_VERSION);
+        assert_eq!(
+            account["addresses"][0]["outputs"][0][STORAGE_VERSION_KEY],
+            STORAGE_VERSION
+        );
+    }
+
+    #[test]
+    fn migrate_outdated_nested_record() {
+        // a message merged from an older record
+        let mut account = json!({
+            "id": "id",
+            "storageVersion": STORAGE_VERSION,
+            "messages": [{ "id": "message" }],
+            "addresses": [],
+        });
+        let (from_version, steps) = migrate_record(&mut account).unwrap();
+        assert_eq!(from_version, 0);
+        assert!(!steps.is_empty());
+        assert_eq!(account["messages"][0][STORAGE_VERSION_KEY], STORAGE_VERSION);
+    }
+
+    /// Moves the message `value` field to `payload.value`, keeping the messages that were already migrated.
+    fn nest_message_value(account: &mut Value) -> crate::Result<()> {
+        if let Some(Value::Array(messages)) = account.get_mut("messages") {
+            for message in messages
+                .iter_mut()
+                .filter(|message| record_version(message) <= STORAGE_VERSION)
+            {
+                if let Value::Object(message) = message {
+                    if let Some(value) = message.remove("value") {
+                        message.insert("payload".to_string(), json!({ "value": value }));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_record_shape() {
+        let migrations = &[Migration {
+            version: STORAGE_VERSION + 1,
+            description: "nest the message value on the payload",
+            migrate: nest_message_value,
+        }];
+        let mut account = json!({
+            "id": "id",
+            "storageVersion": STORAGE_VERSION,
+            "messages": [
+                { "id": "old", "value": 1, "storageVersion": STORAGE_VERSION },
+                { "id": "new", "payload": { "value": 2 }, "storageVersion": STORAGE_VERSION + 1 },
+            ],
+            "addresses": [],
+        });
+        let (from_version, steps) = migrate_record_with(&mut account, migrations, STORAGE_VERSION + 1).unwrap();
+        assert_eq!(from_version, STORAGE_VERSION);
+        assert_eq!(steps, vec!["nest the message value on the payload".to_string()]);
+        assert_eq!(
+            account["messages"],
+            json!([
+                { "id": "old", "payload": { "value": 1 }, "storageVersion": STORAGE_VERSION + 1 },
+                { "id": "new", "payload": { "value": 2 }, "storageVersion": STORAGE_VERSION + 1 },
+            ])
+        );
+        assert_eq!(account[STORAGE_VERSION_KEY], STORAGE_VERSION + 1);
+
+        // the migrated record is on the latest version
+        let (_, steps) = migrate_record_with(&mut account, migrations, STORAGE_VERSION + 1).unwrap();
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn flag_legacy_mnemonic_passphrase() {
+        let mut env_mnemonic_account = json!({ "id": "id", "signer_type": { "type": "EnvMnemonic" } });
+        let mut custom_account = json!({ "id": "id", "signer_type": { "type": "Custom", "data": "custom" } });
+        let mut new_account = json!({
+            "id": "id",
+            "storageVersion": STORAGE_VERSION,
+            "signer_type": { "type": "EnvMnemonic" },
+            "messages": [{ "id": "message" }],
+        });
+        for account in &mut [&mut env_mnemonic_account, &mut custom_account, &mut new_account] {
+            migrate_record(account).unwrap();
+        }
+        assert_eq!(env_mnemonic_account["legacyMnemonicPassphrase"], true);
+        assert!(custom_account.get("legacyMnemonicPassphrase").is_none());
+        // the accounts created with the new default don't take the legacy passphrase
+        assert!(new_account.get("legacyMnemonicPassphrase").is_none());
+    }
+
+    #[test]
+    fn reject_newer_record() {
+        let mut account = serde_json::json!({ "id": "id", "storageVersion": STORAGE_VERSION + 1 });
+        assert!(migrate_record(&mut account).is_err());
+    }
+
+    #[test]
+    fn reject_newer_nested_record() {
+        // a message written by a newer wallet
+        let mut account = json!({
+            "id": "id",
+            "storageVersion": STORAGE_VERSION,
+            "messages": [{ "id": "message", "storageVersion": STORAGE_VERSION + 1 }],
+            "addresses": [],
+        });
+        assert!(matches!(
+            migrate_record(&mut account),
+            Err(crate::WalletError::UnsupportedStorageVersion(version)) if version == STORAGE_VERSION + 1
+        ));
+        assert_eq!(account["messages"][0][STORAGE_VERSION_KEY], STORAGE_VERSION + 1);
+    }
+}
@@ -1,6 +1,10 @@
 // Copyright 2020 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+/// In-memory storage.
+pub mod memory;
+/// Storage schema migrations.
+pub mod migration;
 #[cfg(feature = "sqlite")]
 /// Sqlite storage.
 pub mod sqlite;
@@ -8,24 +12,24 @@ pub mod sqlite;
 /// Stronghold storage.
 pub mod stronghold;
 
-use crate::account::{Account, AccountIdentifier};
-use once_cell::sync::OnceCell;
+use crate::{
+    account::{Account, AccountIdentifier},
+    message::Message,
+};
+use iota::message::prelude::MessageId;
 
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
-    sync::{Arc, Mutex, RwLock},
+    sync::{Arc, Mutex},
 };
 
-type Storage = Box<dyn StorageAdapter + Sync + Send>;
-type Storages = Arc<RwLock<HashMap<PathBuf, Storage>>>;
+pub(crate) type Storage = Box<dyn StorageAdapter + Sync + Send>;
 type AccountReadLockMap = HashMap<AccountIdentifier, Arc<Mutex<()>>>;
-static INSTANCES: OnceCell<Storages> = OnceCell::new();
 
-/// Sets the storage adapter.
+/// Sets the storage adapter of the wallet instance associated with the storage path.
 pub fn set_adapter<P: AsRef<Path>, S: StorageAdapter + Sync + Send + 'static>(storage_path: P, storage: S) {
-    let mut instances = INSTANCES.get_or_init(Default::default).write().unwrap();
-    instances.insert(storage_path.as_ref().to_path_buf(), Box::new(storage));
+    crate::context::get_or_create(storage_path.as_ref()).set_storage(storage);
 }
 
 pub(crate) fn stronghold_snapshot_filename() -> &'static str {
@@ -33,12 +37,25 @@ pub(crate) fn stronghold_snapshot_filename() -> &'static str {
 }
 
 /// gets the storage adapter
-pub(crate) fn with_adapter<T, F: FnOnce(&Storage) -> T>(storage_path: &PathBuf, cb: F) -> T {
-    let instances = INSTANCES.get_or_init(Default::default).read().unwrap();
-    if let Some(instance) = instances.get(storage_path) {
-        cb(instance)
-    } else {
-        panic!(format!("adapter not initialized with path {:?}", storage_path))
+pub(crate) fn with_adapter<T, F: FnOnce(&Storage) -> crate::Result<T>>(
+    storage_path: &PathBuf,
+    cb: F,
+) -> crate::Result<T> {
+    match crate::context::get(storage_path) {
+        Some(context) => context.with_storage(cb),
+        None => Err(crate::WalletError::StorageAdapterNotSet(storage_path.clone())),
+    }
+}
+
+/// Runs the callback inside a storage transaction of the wallet instance associated with the storage path.
+/// Every change made by the callback is committed at once if it succeeds, or rolled back if it returns an error.
+pub(crate) fn transaction<T, F: FnOnce(&Storage) -> crate::Result<T>>(
+    storage_path: &PathBuf,
+    cb: F,
+) -> crate::Result<T> {
+    match crate::context::get(storage_path) {
+        Some(context) => context.transaction(cb),
+        None => Err(crate::WalletError::StorageAdapterNotSet(storage_path.clone())),
     }
 }
 
@@ -64,15 +81,76 @@ pub trait StorageAdapter {
     fn set(&self, account_id: &AccountIdentifier, account: String) -> crate::Result<()>;
     /// Removes an account from the storage.
     fn remove(&self, account_id: &AccountIdentifier) -> crate::Result<()>;
+    /// Gets a page of the account messages, ordered by timestamp (most recent first).
+    /// A zero `count` fetches every message after `from`.
+    /// The default implementation reads the whole account record; adapters with indexed messages should override it.
+    fn list_messages(&self, account_id: &AccountIdentifier, count: usize, from: usize) -> crate::Result<Vec<Message>> {
+        let mut messages = get_stored_messages(self.get(account_id)?)?;
+        messages.sort_by(|a, b| b.timestamp().cmp(a.timestamp()));
+        let messages = messages.into_iter().skip(from);
+        if count == 0 {
+            Ok(messages.collect())
+        } else {
+            Ok(messages.take(count).collect())
+        }
+    }
+    /// Gets the account message with the given id.
+    /// The default implementation reads the whole account record; adapters with indexed messages should override it.
+    fn get_message(&self, account_id: &AccountIdentifier, message_id: &MessageId) -> crate::Result<Option<Message>> {
+        let messages = get_stored_messages(self.get(account_id)?)?;
+        Ok(messages.into_iter().find(|message| message.id() == message_id))
+    }
+    /// Begins a transaction.
+    /// The `set` and `remove` calls made until `commit_transaction` or `rollback_transaction` must be applied
+    /// atomically. The default implementation doesn't support transactions and writes every change immediately.
+    /// The wallet doesn't access the adapter from other threads until the transaction is committed or rolled back.
+    fn begin_transaction(&self) -> crate::Result<()> {
+        Ok(())
+    }
+    /// Persists every change made since `begin_transaction`.
+    fn commit_transaction(&self) -> crate::Result<()> {
+        Ok(())
+    }
+    /// Discards every change made since `begin_transaction`.
+    fn rollback_transaction(&self) -> crate::Result<()> {
+        Ok(())
+    }
+    /// Sets the password used to encrypt the stored accounts.
+    /// If the storage is already encrypted, the password unlocks it.
+    fn set_password(&self, _password: &str) -> crate::Result<()> {
+        Err(anyhow::anyhow!("the storage adapter doesn't support encryption").into())
+    }
+    /// Changes the storage password, re-encrypting the stored accounts.
+    fn change_password(&self, _current_password: &str, _new_password: &str) -> crate::Result<()> {
+        Err(anyhow::anyhow!("the storage adapter doesn't support encryption").into())
+    }
+    /// Whether the stored accounts can only be read after a password is provided.
+    /// The stored accounts are migrated and verified when the account manager is created, or once they're unlocked.
+    fn is_locked(&self) -> crate::Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Parses the messages of a stored account record.
+fn get_stored_messages(account: String) -> crate::Result<Vec<Message>> {
+    let mut account: serde_json::Value = serde_json::from_str(&account)?;
+    match account.get_mut("messages").map(serde_json::Value::take) {
+        Some(messages) => Ok(serde_json::from_value(messages)?),
+        None => Ok(vec![]),
+    }
 }
 
 pub(crate) fn parse_accounts(storage_path: &PathBuf, accounts: &[String]) -> crate::Result<Vec<Account>> {
+    let context = crate::context::get(storage_path);
     let mut err = None;
     let accounts: Vec<Option<Account>> = accounts
         .iter()
         .map(|account| match serde_json::from_str::<Account>(&account) {
             Ok(mut acc) => {
                 acc.set_storage_path(storage_path.clone());
+                if let Some(context) = &context {
+                    acc.set_context(context.clone());
+                }
                 Some(acc)
             }
             Err(e) => {
@@ -98,27 +176,46 @@ pub(crate) fn get_account(storage_path: &PathBuf, account_id: &AccountIdentifier
 }
 
 pub(crate) fn save_account(storage_path: &PathBuf, account: &mut Account) -> crate::Result<()> {
-    with_adapter(&storage_path, |storage| {
-        if let Ok(current) = storage.get(account.id()) {
-            let current: crate::account::Account = serde_json::from_str(&current)?;
-            account.append_messages(
-                current
-                    .messages()
-                    .iter()
-                    .cloned()
-                    .filter(|m| !account.messages().contains(m))
-                    .collect(),
-            );
-            account.append_addresses(
-                current
-                    .addresses()
-                    .iter()
-                    .cloned()
-                    .filter(|m| !account.addresses().contains(m))
-                    .collect(),
-            );
+    transaction(&storage_path, |storage| merge_and_set(storage, account))
+}
+
+/// Saves the accounts in a single storage transaction.
+pub(crate) fn save_accounts(storage_path: &PathBuf, accounts: Vec<&mut Account>) -> crate::Result<()> {
+    transaction(&storage_path, |storage| {
+        for account in accounts {
+            merge_and_set(storage, account)?;
         }
-        storage.set(account.id(), serde_json::to_string(&account)?)
-    })?;
-    Ok(())
+        Ok(())
+    })
+}
+
+/// Merges the stored account messages and addresses into the account and writes it to the storage.
+pub(crate) fn merge_and_set(storage: &Storage, account: &mut Account) -> crate::Result<()> {
+    if let Ok(current) = storage.get(account.id()) {
+        let current: crate::account::Account = serde_json::from_str(&current)?;
+        account.append_messages(
+            current
+                .messages()
+                .iter()
+                .cloned()
+                .filter(|m| !account.messages().contains(m))
+                .collect(),
+        );
+        account.append_addresses(
+            current
+                .addresses()
+                .iter()
+                .cloned()
+                .filter(|m| !account.addresses().contains(m))
+                .collect(),
+        );
+    }
+    set_account(storage, account)
+}
+
+/// Writes the account to the storage, replacing the stored record.
+pub(crate) fn set_account(storage: &Storage, account: &Account) -> crate::Result<()> {
+    let mut account_json = serde_json::to_value(&account)?;
+    migration::stamp(&mut account_json);
+    storage.set(account.id(), account_json.to_string())
 }
@@ -2,120 +2,1000 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::StorageAdapter;
-use crate::account::AccountIdentifier;
-use chrono::Utc;
-use rusqlite::{
-    params,
-    types::{ToSqlOutput, Value},
-    Connection, NO_PARAMS,
+use crate::{account::AccountIdentifier, message::Message};
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    Key, XChaCha20Poly1305, XNonce,
 };
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use iota::message::prelude::MessageId;
+use rusqlite::{params, types::Value, Connection, OptionalExtension, NO_PARAMS};
+use serde_json::Value as JsonValue;
 use std::{
+    collections::HashSet,
     path::Path,
     sync::{Arc, Mutex},
 };
+use zeroize::Zeroize;
 
-/// Key value storage adapter.
+/// The tables holding the account children records.
+/// Every child table has the `account_id`, `key`, `position` and `value` columns plus the indexed columns listed here.
+const ADDRESSES_TABLE: (&str, &[&str]) = ("addresses", &["address", "key_index", "internal", "balance"]);
+const ADDRESS_OUTPUTS_TABLE: (&str, &[&str]) = ("address_outputs", &["address", "message_id", "amount", "is_spent"]);
+const MESSAGES_TABLE: (&str, &[&str]) = ("messages", &["timestamp"]);
+
+const PBKDF2_ROUNDS: usize = 100_000;
+const SALT_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+/// The plaintext encrypted on the meta table to check the storage password.
+const PASSWORD_CHECK: &str = "wallet.rs-storage-password-check";
+
+/// A row of one of the account children tables.
+struct ChildRow {
+    key: String,
+    columns: Vec<Value>,
+    value: String,
+}
+
+/// The keys derived from the storage password.
+struct StorageCipher {
+    /// The key used to encrypt the records.
+    encryption_key: [u8; 32],
+    /// The key used to blind the indexed identifiers (account ids, addresses and message ids).
+    blinding_key: [u8; 32],
+}
+
+impl Drop for StorageCipher {
+    fn drop(&mut self) {
+        self.encryption_key.zeroize();
+        self.blinding_key.zeroize();
+    }
+}
+
+impl StorageCipher {
+    /// Derives the keys from the password with PBKDF2-HMAC-SHA512.
+    fn derive(password: &str, salt: &[u8]) -> Self {
+        let mut derived = [0u8; 64];
+        pbkdf2::pbkdf2::<Hmac<sha2::Sha512>>(password.as_bytes(), salt, PBKDF2_ROUNDS, &mut derived);
+        let mut cipher = Self {
+            encryption_key: [0; 32],
+            blinding_key: [0; 32],
+        };
+        cipher.encryption_key.copy_from_slice(&derived[..32]);
+        cipher.blinding_key.copy_from_slice(&derived[32..]);
+        derived.zeroize();
+        cipher
+    }
+
+    /// Encrypts the value with XChaCha20-Poly1305, returning the hex encoded nonce and ciphertext.
+    fn encrypt(&self, plaintext: &str) -> crate::Result<String> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        getrandom::getrandom(&mut nonce).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(&self.encryption_key))
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("failed to encrypt record"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(hex::encode(sealed))
+    }
+
+    /// Decrypts a value created with `encrypt`.
+    fn decrypt(&self, sealed: &str) -> crate::Result<String> {
+        let sealed = hex::decode(sealed).map_err(|_| crate::WalletError::InvalidStoragePassword)?;
+        if sealed.len() < NONCE_LENGTH {
+            return Err(crate::WalletError::InvalidStoragePassword);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+        let plaintext = XChaCha20Poly1305::new(Key::from_slice(&self.encryption_key))
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| crate::WalletError::InvalidStoragePassword)?;
+        String::from_utf8(plaintext).map_err(|_| crate::WalletError::InvalidStoragePassword)
+    }
+
+    /// Computes the HMAC-SHA256 digest of the identifier, so it can be indexed without being stored in plaintext.
+    fn blind(&self, value: &str) -> String {
+        let mut mac = Hmac::<sha2::Sha256>::new_varkey(&self.blinding_key).expect("HMAC accepts any key length");
+        mac.input(value.as_bytes());
+        hex::encode(mac.result().code())
+    }
+}
+
+/// Relational storage adapter.
+///
+/// The accounts, addresses, address outputs and messages are stored on separate tables,
+/// indexed by message id, bech32 address and message timestamp.
+///
+/// If a password is set, the records are encrypted with a key derived from it and the indexed identifiers
+/// are replaced by keyed digests. Only the account index and message timestamps are kept in plaintext,
+/// so the accounts and messages can still be ordered by the database.
 pub struct SqliteStorageAdapter {
     table_name: String,
     connection: Arc<Mutex<Connection>>,
+    cipher: Arc<Mutex<Option<StorageCipher>>>,
 }
 
 impl SqliteStorageAdapter {
     /// Initialises the storage adapter.
+    /// Databases using the legacy key-value layout are migrated to the relational schema.
+    /// If the database is encrypted, it must be unlocked with `set_password` before it can be used.
     pub fn new(path: impl AsRef<Path>, table_name: impl AsRef<str>) -> crate::Result<Self> {
         std::fs::create_dir_all(&path)?;
 
         let connection = Connection::open(path.as_ref().join("wallet.db"))?;
+        // overwrites the deleted records, so the plaintext isn't left behind when the storage is encrypted
+        connection.execute_batch("PRAGMA secure_delete = ON")?;
+        let table_name = table_name.as_ref();
+
+        let is_legacy_database = connection
+            .prepare(&format!("PRAGMA table_info({})", table_name))?
+            .query_map(NO_PARAMS, |row| row.get::<_, String>(1))?
+            .any(|column| column.map(|c| c == "key").unwrap_or(false));
+
+        let database = Database {
+            connection: &connection,
+            table_name,
+            cipher: None,
+        };
+        database.in_transaction(|| {
+            if is_legacy_database {
+                database.migrate_legacy_table()
+            } else {
+                database.create_tables()
+            }
+        })?;
+
+        Ok(Self {
+            table_name: table_name.to_string(),
+            connection: Arc::new(Mutex::new(connection)),
+            cipher: Default::default(),
+        })
+    }
+
+    /// Initialises the storage adapter with the given password.
+    /// Existing plaintext databases are encrypted with it.
+    pub fn with_password(
+        path: impl AsRef<Path>,
+        table_name: impl AsRef<str>,
+        password: impl AsRef<str>,
+    ) -> crate::Result<Self> {
+        let adapter = Self::new(path, table_name)?;
+        adapter.set_password(password.as_ref())?;
+        Ok(adapter)
+    }
+
+    /// Whether the database is encrypted or not.
+    pub fn is_encrypted(&self) -> crate::Result<bool> {
+        let connection = self.connection.lock().expect("failed to get connection lock");
+        Ok(get_salt(&connection, &self.table_name)?.is_some())
+    }
+
+    /// Runs the callback with the database tables, failing if the database is encrypted and locked.
+    fn with_database<T, F: FnOnce(&Database<'_>) -> crate::Result<T>>(&self, cb: F) -> crate::Result<T> {
+        let connection = self.connection.lock().expect("failed to get connection lock");
+        let cipher = self.cipher.lock().expect("failed to get cipher lock");
+        if cipher.is_none() && get_salt(&connection, &self.table_name)?.is_some() {
+            return Err(crate::WalletError::StorageIsEncrypted);
+        }
+        cb(&Database {
+            connection: &connection,
+            table_name: &self.table_name,
+            cipher: cipher.as_ref(),
+        })
+    }
+
+    /// Gets the messages that created outputs on the given bech32 address, ordered by timestamp (most recent first).
+    pub fn list_address_messages(&self, address: &str) -> crate::Result<Vec<Message>> {
+        self.with_database(|database| {
+            let mut query = database.connection.prepare(&format!(
+                "SELECT DISTINCT m.value, m.timestamp FROM {table}_messages m
+                    INNER JOIN {table}_address_outputs o ON o.account_id = m.account_id AND o.message_id = m.key
+                    WHERE o.address = ?1 ORDER BY m.timestamp DESC",
+                table = database.table_name
+            ))?;
+            let messages = query
+                .query_map(params![database.index(address.to_string())], |row| {
+                    row.get::<_, String>(0)
+                })?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            database.parse_messages(messages)
+        })
+    }
+
+    /// Gets the account messages with timestamp in the given interval, ordered by timestamp (most recent first).
+    pub fn list_messages_between(
+        &self,
+        account_id: &AccountIdentifier,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> crate::Result<Vec<Message>> {
+        self.with_database(|database| {
+            let account_id = database.account_key(account_id)?;
+            let mut query = database.connection.prepare(&format!(
+                "SELECT value FROM {}_messages WHERE account_id = ?1 AND timestamp BETWEEN ?2 AND ?3
+                    ORDER BY timestamp DESC",
+                database.table_name
+            ))?;
+            let messages = query
+                .query_map(params![account_id, start.timestamp(), end.timestamp()], |row| {
+                    row.get::<_, String>(0)
+                })?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            database.parse_messages(messages)
+        })
+    }
+
+    /// Rewrites every record with the new cipher and stores its salt and password check.
+    fn rekey(
+        &self,
+        connection: &Connection,
+        current: Option<&StorageCipher>,
+        salt: &[u8],
+        new: &StorageCipher,
+    ) -> crate::Result<()> {
+        let current_database = Database {
+            connection,
+            table_name: &self.table_name,
+            cipher: current,
+        };
+        let new_database = Database {
+            connection,
+            table_name: &self.table_name,
+            cipher: Some(new),
+        };
+        current_database.in_transaction(|| {
+            let accounts = current_database.read_accounts()?;
+            current_database.clear()?;
+            for account in accounts {
+                new_database.write_account(&account)?;
+            }
+            connection.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO {}_meta (key, value) VALUES ('salt', ?1), ('check', ?2)",
+                    self.table_name
+                ),
+                params![hex::encode(salt), new.encrypt(PASSWORD_CHECK)?],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+fn get_salt(connection: &Connection, table_name: &str) -> crate::Result<Option<Vec<u8>>> {
+    let salt = connection
+        .query_row(
+            &format!("SELECT value FROM {}_meta WHERE key = 'salt'", table_name),
+            NO_PARAMS,
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+    match salt {
+        Some(salt) => Ok(Some(hex::decode(salt).map_err(|e| anyhow::anyhow!(e.to_string()))?)),
+        None => Ok(None),
+    }
+}
+
+/// Derives the cipher from the password and checks it against the stored password check.
+fn unlock(connection: &Connection, table_name: &str, salt: &[u8], password: &str) -> crate::Result<StorageCipher> {
+    let cipher = StorageCipher::derive(password, salt);
+    let check = connection.query_row(
+        &format!("SELECT value FROM {}_meta WHERE key = 'check'", table_name),
+        NO_PARAMS,
+        |row| row.get::<_, String>(0),
+    )?;
+    if cipher.decrypt(&check)? != PASSWORD_CHECK {
+        return Err(crate::WalletError::InvalidStoragePassword);
+    }
+    Ok(cipher)
+}
+
+fn generate_salt() -> crate::Result<[u8; SALT_LENGTH]> {
+    let mut salt = [0u8; SALT_LENGTH];
+    getrandom::getrandom(&mut salt).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(salt)
+}
+
+fn get_string_field(value: &JsonValue, field: &str) -> crate::Result<String> {
+    value[field]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| anyhow::anyhow!("stored record is missing the `{}` field", field).into())
+}
+
+/// Gets the message id hex string from its serialized form.
+fn get_message_id_field(value: &JsonValue, field: &str) -> crate::Result<String> {
+    match &value[field] {
+        JsonValue::String(id) => Ok(id.clone()),
+        id => {
+            let id: MessageId = serde_json::from_value(id.clone())?;
+            Ok(id.to_string())
+        }
+    }
+}
+
+fn take_array(value: &mut JsonValue, field: &str) -> Vec<JsonValue> {
+    match value.get_mut(field).map(JsonValue::take) {
+        Some(JsonValue::Array(array)) => array,
+        _ => vec![],
+    }
+}
+
+/// The database tables, with the cipher protecting the records if the storage is encrypted.
+struct Database<'a> {
+    connection: &'a Connection,
+    table_name: &'a str,
+    cipher: Option<&'a StorageCipher>,
+}
 
-        connection.execute(
+impl<'a> Database<'a> {
+    /// Runs the callback inside a transaction, unless the caller already started one.
+    fn in_transaction<T, F: FnOnce() -> crate::Result<T>>(&self, cb: F) -> crate::Result<T> {
+        let is_autocommit = self.connection.is_autocommit();
+        if is_autocommit {
+            self.connection.execute_batch("BEGIN IMMEDIATE TRANSACTION")?;
+        }
+        let result = cb();
+        if is_autocommit {
+            match result {
+                Ok(_) => self.connection.execute_batch("COMMIT TRANSACTION")?,
+                Err(_) => self.connection.execute_batch("ROLLBACK TRANSACTION")?,
+            }
+        }
+        result
+    }
+
+    /// Encrypts the record if the storage is encrypted.
+    fn seal(&self, value: String) -> crate::Result<String> {
+        match self.cipher {
+            Some(cipher) => cipher.encrypt(&value),
+            None => Ok(value),
+        }
+    }
+
+    /// Decrypts the record if the storage is encrypted.
+    fn open(&self, value: String) -> crate::Result<String> {
+        match self.cipher {
+            Some(cipher) => cipher.decrypt(&value),
+            None => Ok(value),
+        }
+    }
+
+    /// Blinds the indexed identifier if the storage is encrypted.
+    fn index(&self, value: String) -> String {
+        match self.cipher {
+            Some(cipher) => cipher.blind(&value),
+            None => value,
+        }
+    }
+
+    /// The indexed amounts (balances, key indexes and flags) are only stored if the storage isn't encrypted.
+    fn index_amount(&self, value: i64) -> Value {
+        match self.cipher {
+            Some(_) => Value::Integer(0),
+            None => Value::Integer(value),
+        }
+    }
+
+    fn parse_messages(&self, messages: Vec<String>) -> crate::Result<Vec<Message>> {
+        let mut parsed = vec![];
+        for message in messages {
+            parsed.push(serde_json::from_str(&self.open(message)?)?);
+        }
+        Ok(parsed)
+    }
+
+    fn create_tables(&self) -> crate::Result<()> {
+        self.connection.execute(
             &format!(
                 "CREATE TABLE IF NOT EXISTS {} (
-                    key TEXT NOT NULL UNIQUE,
-                    value TEXT,
+                    id TEXT NOT NULL PRIMARY KEY,
+                    account_index INTEGER NOT NULL,
+                    value TEXT NOT NULL,
                     created_at INTEGER
                 )",
-                table_name.as_ref()
+                self.table_name
             ),
             NO_PARAMS,
         )?;
 
-        Ok(Self {
-            table_name: table_name.as_ref().to_string(),
-            connection: Arc::new(Mutex::new(connection)),
-        })
+        for (child_table, columns) in &[ADDRESSES_TABLE, ADDRESS_OUTPUTS_TABLE, MESSAGES_TABLE] {
+            self.connection.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {table}_{child} (
+                        account_id TEXT NOT NULL,
+                        key TEXT NOT NULL,
+                        position INTEGER NOT NULL,
+                        value TEXT NOT NULL,
+                        {columns},
+                        PRIMARY KEY (account_id, key)
+                    )",
+                    table = self.table_name,
+                    child = child_table,
+                    columns = columns
+                        .iter()
+                        .map(|column| format!("{} NOT NULL", column))
+                        .collect::<Vec<String>>()
+                        .join(",\n"),
+                ),
+                NO_PARAMS,
+            )?;
+        }
+
+        self.connection.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {table}_meta (key TEXT NOT NULL PRIMARY KEY, value TEXT NOT NULL);
+            CREATE INDEX IF NOT EXISTS {table}_addresses_address ON {table}_addresses (address);
+            CREATE INDEX IF NOT EXISTS {table}_address_outputs_address ON {table}_address_outputs (address);
+            CREATE INDEX IF NOT EXISTS {table}_address_outputs_message_id ON {table}_address_outputs (message_id);
+            CREATE INDEX IF NOT EXISTS {table}_messages_key ON {table}_messages (key);
+            CREATE INDEX IF NOT EXISTS {table}_messages_timestamp ON {table}_messages (timestamp);",
+            table = self.table_name
+        ))?;
+
+        Ok(())
+    }
+
+    /// Converts the legacy `key/value/created_at` table, where each account was stored as a JSON blob,
+    /// to the relational schema.
+    fn migrate_legacy_table(&self) -> crate::Result<()> {
+        let legacy_table_name = format!("{}_legacy", self.table_name);
+        self.connection.execute(
+            &format!("ALTER TABLE {} RENAME TO {}", self.table_name, legacy_table_name),
+            NO_PARAMS,
+        )?;
+        self.create_tables()?;
+
+        let accounts = self
+            .connection
+            .prepare(&format!("SELECT value FROM {} ORDER BY created_at", legacy_table_name))?
+            .query_map(NO_PARAMS, |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        for account in accounts {
+            self.write_account(&account)?;
+        }
+
+        self.connection
+            .execute(&format!("DROP TABLE {}", legacy_table_name), NO_PARAMS)?;
+        Ok(())
+    }
+
+    /// Gets the account id stored on the accounts table from the identifier.
+    fn account_key(&self, account_id: &AccountIdentifier) -> crate::Result<String> {
+        let id = match account_id {
+            AccountIdentifier::Id(id) => self
+                .connection
+                .query_row(
+                    &format!("SELECT id FROM {} WHERE id = ?1 LIMIT 1", self.table_name),
+                    params![self.index(id.clone())],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?,
+            AccountIdentifier::Index(index) => self
+                .connection
+                .query_row(
+                    &format!(
+                        "SELECT id FROM {} ORDER BY account_index LIMIT 1 OFFSET {}",
+                        self.table_name, index
+                    ),
+                    NO_PARAMS,
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?,
+        };
+        id.ok_or(crate::WalletError::AccountNotFound)
+    }
+
+    /// Splits the account JSON into the accounts, addresses, address outputs and messages rows.
+    fn write_account(&self, account: &str) -> crate::Result<()> {
+        let mut account: JsonValue = serde_json::from_str(account)?;
+        let account_id = self.index(get_string_field(&account, "id")?);
+        let account_index = account["index"].as_i64().unwrap_or_default();
+
+        let mut address_rows = vec![];
+        let mut output_rows = vec![];
+        for mut address in take_array(&mut account, "addresses") {
+            let bech32_address = get_string_field(&address, "address")?;
+            for mut output in take_array(&mut address, "outputs") {
+                let key = format!("{}:{}:{}", bech32_address, output["transaction_id"], output["index"]);
+                output_rows.push(ChildRow {
+                    key: self.index(key),
+                    columns: vec![
+                        Value::Text(self.index(bech32_address.clone())),
+                        Value::Text(self.index(get_message_id_field(&output, "message_id")?)),
+                        self.index_amount(output["amount"].as_i64().unwrap_or_default()),
+                        self.index_amount(output["is_spent"].as_bool().unwrap_or_default() as i64),
+                    ],
+                    value: self.seal(output.take().to_string())?,
+                });
+            }
+            address_rows.push(ChildRow {
+                key: self.index(bech32_address.clone()),
+                columns: vec![
+                    Value::Text(self.index(bech32_address)),
+                    self.index_amount(address["keyIndex"].as_i64().unwrap_or_default()),
+                    self.index_amount(address["internal"].as_bool().unwrap_or_default() as i64),
+                    self.index_amount(address["balance"].as_i64().unwrap_or_default()),
+                ],
+                value: self.seal(address.to_string())?,
+            });
+        }
+
+        let mut message_rows = vec![];
+        for message in take_array(&mut account, "messages") {
+            let timestamp = get_string_field(&message, "timestamp")?
+                .parse::<DateTime<Utc>>()
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            message_rows.push(ChildRow {
+                key: self.index(get_message_id_field(&message, "id")?),
+                columns: vec![Value::Integer(timestamp.timestamp())],
+                value: self.seal(message.to_string())?,
+            });
+        }
+
+        self.connection.execute(
+            &format!(
+                "INSERT INTO {} (id, account_index, value, created_at) VALUES (?1, ?2, ?3, ?4)
+                    ON CONFLICT(id) DO UPDATE SET account_index = excluded.account_index, value = excluded.value",
+                self.table_name
+            ),
+            params![
+                account_id,
+                account_index,
+                self.seal(account.to_string())?,
+                Utc::now().timestamp()
+            ],
+        )?;
+
+        self.write_child_rows(ADDRESSES_TABLE, &account_id, address_rows)?;
+        self.write_child_rows(ADDRESS_OUTPUTS_TABLE, &account_id, output_rows)?;
+        self.write_child_rows(MESSAGES_TABLE, &account_id, message_rows)?;
+
+        Ok(())
+    }
+
+    /// Upserts the rows of an account child table and deletes the rows that are no longer associated with the
+    /// account. Unchanged rows aren't rewritten, so saving an account doesn't rewrite its whole message history.
+    fn write_child_rows(
+        &self,
+        (child_table, columns): (&str, &[&str]),
+        account_id: &str,
+        rows: Vec<ChildRow>,
+    ) -> crate::Result<()> {
+        let stored_rows = self.get_child_rows(child_table, account_id)?;
+        let keys: HashSet<&String> = rows.iter().map(|row| &row.key).collect();
+
+        for (stale_key, _) in stored_rows.iter().filter(|(key, _)| !keys.contains(key)) {
+            self.connection.execute(
+                &format!(
+                    "DELETE FROM {}_{} WHERE account_id = ?1 AND key = ?2",
+                    self.table_name, child_table
+                ),
+                params![account_id, stale_key],
+            )?;
+        }
+
+        let sql = format!(
+            "INSERT INTO {table}_{child} (account_id, key, position, value, {columns}) VALUES ({placeholders})
+                ON CONFLICT(account_id, key) DO UPDATE SET position = excluded.position, value = excluded.value, {updates}",
+            table = self.table_name,
+            child = child_table,
+            columns = columns.join(", "),
+            placeholders = (1..=columns.len() + 4)
+                .map(|i| format!("?{}", i))
+                .collect::<Vec<String>>()
+                .join(", "),
+            updates = columns
+                .iter()
+                .map(|column| format!("{column} = excluded.{column}", column = column))
+                .collect::<Vec<String>>()
+                .join(", "),
+        );
+        let mut statement = self.connection.prepare(&sql)?;
+        for (position, row) in rows.into_iter().enumerate() {
+            // encrypted records never match their stored ciphertext, so they're compared after decryption
+            let stored_row = stored_rows.iter().position(|(key, _)| key == &row.key);
+            if let Some(stored_row) = stored_row {
+                let (_, stored_value) = &stored_rows[stored_row];
+                if stored_row == position && self.open(stored_value.clone())? == self.open(row.value.clone())? {
+                    continue;
+                }
+            }
+            let mut values = vec![
+                Value::Text(account_id.to_string()),
+                Value::Text(row.key),
+                Value::Integer(position as i64),
+                Value::Text(row.value),
+            ];
+            values.extend(row.columns);
+            statement.execute(values)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_child_rows(&self, child_table: &str, account_id: &str) -> crate::Result<Vec<(String, String)>> {
+        let rows = self
+            .connection
+            .prepare(&format!(
+                "SELECT key, value FROM {}_{} WHERE account_id = ?1 ORDER BY position",
+                self.table_name, child_table
+            ))?
+            .query_map(params![account_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+        Ok(rows)
+    }
+
+    /// Rebuilds the account JSON from the accounts, addresses, address outputs and messages rows.
+    fn read_account(&self, account_id: &str) -> crate::Result<String> {
+        let account = self
+            .connection
+            .query_row(
+                &format!("SELECT value FROM {} WHERE id = ?1 LIMIT 1", self.table_name),
+                params![account_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .ok_or(crate::WalletError::AccountNotFound)?;
+        let mut account: JsonValue = serde_json::from_str(&self.open(account)?)?;
+
+        let mut outputs: Vec<(String, JsonValue)> = vec![];
+        let output_rows = self
+            .connection
+            .prepare(&format!(
+                "SELECT address, value FROM {}_address_outputs WHERE account_id = ?1 ORDER BY position",
+                self.table_name
+            ))?
+            .query_map(params![account_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+        for (address, output) in output_rows {
+            outputs.push((address, serde_json::from_str(&self.open(output)?)?));
+        }
+
+        let mut addresses = vec![];
+        for (address_key, address) in self.get_child_rows(ADDRESSES_TABLE.0, account_id)? {
+            let mut address: JsonValue = serde_json::from_str(&self.open(address)?)?;
+            address["outputs"] = JsonValue::Array(
+                outputs
+                    .iter()
+                    .filter(|(output_address, _)| output_address == &address_key)
+                    .map(|(_, output)| output.clone())
+                    .collect(),
+            );
+            addresses.push(address);
+        }
+
+        let mut messages = vec![];
+        for (_, message) in self.get_child_rows(MESSAGES_TABLE.0, account_id)? {
+            messages.push(serde_json::from_str(&self.open(message)?)?);
+        }
+
+        account["addresses"] = JsonValue::Array(addresses);
+        account["messages"] = JsonValue::Array(messages);
+        Ok(account.to_string())
+    }
+
+    fn read_accounts(&self) -> crate::Result<Vec<String>> {
+        let account_ids = self
+            .connection
+            .prepare(&format!("SELECT id FROM {} ORDER BY account_index", self.table_name))?
+            .query_map(NO_PARAMS, |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        let mut accounts = vec![];
+        for account_id in account_ids {
+            accounts.push(self.read_account(&account_id)?);
+        }
+        Ok(accounts)
+    }
+
+    /// Removes every account record.
+    fn clear(&self) -> crate::Result<()> {
+        self.connection.execute_batch(&format!(
+            "DELETE FROM {table};
+            DELETE FROM {table}_addresses;
+            DELETE FROM {table}_address_outputs;
+            DELETE FROM {table}_messages;",
+            table = self.table_name
+        ))?;
+        Ok(())
+    }
+
+    fn remove_account(&self, account_id: &str) -> crate::Result<()> {
+        for table in &[
+            self.table_name.to_string(),
+            format!("{}_{}", self.table_name, ADDRESSES_TABLE.0),
+            format!("{}_{}", self.table_name, ADDRESS_OUTPUTS_TABLE.0),
+            format!("{}_{}", self.table_name, MESSAGES_TABLE.0),
+        ] {
+            let id_column = if table == self.table_name { "id" } else { "account_id" };
+            self.connection.execute(
+                &format!("DELETE FROM {} WHERE {} = ?1", table, id_column),
+                params![account_id],
+            )?;
+        }
+        Ok(())
     }
 }
 
 impl StorageAdapter for SqliteStorageAdapter {
     fn get(&self, account_id: &AccountIdentifier) -> crate::Result<String> {
-        let (sql, params) = match account_id {
-            AccountIdentifier::Id(id) => (
-                format!("SELECT value FROM {} WHERE key = ?1 LIMIT 1", self.table_name),
-                vec![ToSqlOutput::Owned(Value::Text(id.clone()))],
-            ),
-            AccountIdentifier::Index(index) => (
-                format!("SELECT value FROM {} LIMIT 1 OFFSET {}", self.table_name, index),
-                vec![],
-            ),
-        };
+        self.with_database(|database| {
+            let account_id = database.account_key(account_id)?;
+            database.read_account(&account_id)
+        })
+    }
+
+    fn get_all(&self) -> crate::Result<std::vec::Vec<String>> {
+        self.with_database(|database| database.read_accounts())
+    }
+
+    fn set(&self, account_id: &AccountIdentifier, account: String) -> crate::Result<()> {
+        if let AccountIdentifier::Index(_) = account_id {
+            return Err(anyhow::anyhow!("only Id is supported").into());
+        }
+        self.with_database(|database| {
+            // the account is split into multiple tables, so we need a transaction if the caller didn't start one
+            database.in_transaction(|| database.write_account(&account))
+        })
+    }
+
+    fn remove(&self, account_id: &AccountIdentifier) -> crate::Result<()> {
+        self.with_database(|database| {
+            let account_id = database.account_key(account_id)?;
+            database.in_transaction(|| database.remove_account(&account_id))
+        })
+    }
+
+    fn is_locked(&self) -> crate::Result<bool> {
+        let is_encrypted = self.is_encrypted()?;
+        Ok(is_encrypted && self.cipher.lock().expect("failed to get cipher lock").is_none())
+    }
+
+    fn list_messages(&self, account_id: &AccountIdentifier, count: usize, from: usize) -> crate::Result<Vec<Message>> {
+        self.with_database(|database| {
+            let account_id = database.account_key(account_id)?;
+            let limit = if count == 0 { -1 } else { count as i64 };
+            let mut query = database.connection.prepare(&format!(
+                "SELECT value FROM {}_messages WHERE account_id = ?1 ORDER BY timestamp DESC LIMIT ?2 OFFSET ?3",
+                database.table_name
+            ))?;
+            let messages = query
+                .query_map(params![account_id, limit, from as i64], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            database.parse_messages(messages)
+        })
+    }
+
+    fn get_message(&self, account_id: &AccountIdentifier, message_id: &MessageId) -> crate::Result<Option<Message>> {
+        self.with_database(|database| {
+            let account_id = database.account_key(account_id)?;
+            let message = database
+                .connection
+                .query_row(
+                    &format!(
+                        "SELECT value FROM {}_messages WHERE account_id = ?1 AND key = ?2 LIMIT 1",
+                        database.table_name
+                    ),
+                    params![account_id, database.index(message_id.to_string())],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?;
+            match message {
+                Some(message) => Ok(database.parse_messages(vec![message])?.pop()),
+                None => Ok(None),
+            }
+        })
+    }
 
+    fn begin_transaction(&self) -> crate::Result<()> {
         let connection = self.connection.lock().expect("failed to get connection lock");
-        let mut query = connection.prepare(&sql)?;
-        let results = query
-            .query_and_then(params, |row| row.get(0))?
-            .collect::<Vec<rusqlite::Result<String>>>();
-        let account = results
-            .first()
-            .map(|val| val.as_ref().unwrap().to_string())
-            .ok_or(crate::WalletError::AccountNotFound)?;
-        Ok(account)
+        connection.execute_batch("BEGIN IMMEDIATE TRANSACTION")?;
+        Ok(())
     }
 
-    fn get_all(&self) -> crate::Result<std::vec::Vec<String>> {
+    fn commit_transaction(&self) -> crate::Result<()> {
         let connection = self.connection.lock().expect("failed to get connection lock");
-        let mut query = connection.prepare(&format!("SELECT value FROM {} ORDER BY created_at", self.table_name))?;
-        let accounts = query
-            .query_and_then(NO_PARAMS, |row| row.get(0))?
-            .map(|val| val.unwrap())
-            .collect::<Vec<String>>();
-        Ok(accounts)
+        connection.execute_batch("COMMIT TRANSACTION")?;
+        Ok(())
     }
 
-    fn set(&self, account_id: &AccountIdentifier, account: String) -> crate::Result<()> {
-        let id = match account_id {
-            AccountIdentifier::Id(id) => id,
-            _ => return Err(anyhow::anyhow!("only Id is supported").into()),
-        };
+    fn rollback_transaction(&self) -> crate::Result<()> {
         let connection = self.connection.lock().expect("failed to get connection lock");
-        let result = connection
-            .execute(
-                &format!("INSERT OR REPLACE INTO {} VALUES (?1, ?2, ?3)", self.table_name),
-                params![id, account, Utc::now().timestamp()],
-            )
-            .map_err(|_| anyhow::anyhow!("failed to insert data"))?;
+        connection.execute_batch("ROLLBACK TRANSACTION")?;
         Ok(())
     }
 
-    fn remove(&self, account_id: &AccountIdentifier) -> crate::Result<()> {
-        let (sql, params) = match account_id {
-            AccountIdentifier::Id(id) => (
-                format!("DELETE FROM {} WHERE key = ?1", self.table_name),
-                vec![ToSqlOutput::Owned(Value::Text(id.clone()))],
-            ),
-            AccountIdentifier::Index(index) => (
-                format!(
-                    "DELETE FROM {table} WHERE key IN (SELECT key from {table} LIMIT 1 OFFSET {offset})",
-                    table = self.table_name,
-                    offset = index
-                ),
-                vec![],
-            ),
-        };
+    fn set_password(&self, password: &str) -> crate::Result<()> {
+        let connection = self.connection.lock().expect("failed to get connection lock");
+        let mut cipher = self.cipher.lock().expect("failed to get cipher lock");
+        match get_salt(&connection, &self.table_name)? {
+            // the database is already encrypted, so we just unlock it
+            Some(salt) => {
+                *cipher = Some(unlock(&connection, &self.table_name, &salt, password)?);
+            }
+            None => {
+                let salt = generate_salt()?;
+                let new_cipher = StorageCipher::derive(password, &salt);
+                self.rekey(&connection, None, &salt, &new_cipher)?;
+                *cipher = Some(new_cipher);
+            }
+        }
+        Ok(())
+    }
 
+    fn change_password(&self, current_password: &str, new_password: &str) -> crate::Result<()> {
         let connection = self.connection.lock().expect("failed to get connection lock");
-        let result = connection
-            .execute(&sql, params)
-            .map_err(|_| anyhow::anyhow!("failed to delete data"))?;
+        let mut cipher = self.cipher.lock().expect("failed to get cipher lock");
+        let salt = get_salt(&connection, &self.table_name)?
+            .ok_or_else(|| anyhow::anyhow!("the storage isn't encrypted, use `set_password` instead"))?;
+        let current_cipher = unlock(&connection, &self.table_name, &salt, current_password)?;
+
+        let new_salt = generate_salt()?;
+        let new_cipher = StorageCipher::derive(new_password, &new_salt);
+        self.rekey(&connection, Some(&current_cipher), &new_salt, &new_cipher)?;
+        *cipher = Some(new_cipher);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{SqliteStorageAdapter, StorageAdapter};
+    use crate::{account::AccountIdentifier, message::Message};
+    use iota::message::prelude::{Indexation, Message as IotaMessage, MessageId, Payload};
+    use rand::{thread_rng, Rng};
+    use rusqlite::{params, Connection, NO_PARAMS};
+    use std::path::PathBuf;
+
+    fn storage_path() -> PathBuf {
+        let storage_path: String = thread_rng().gen_ascii_chars().take(10).collect();
+        PathBuf::from(format!("./example-database/{}", storage_path))
+    }
+
+    fn account_json(message_id: [u8; 32]) -> serde_json::Value {
+        let message = Message::from_iota_message(
+            MessageId::new(message_id),
+            &[],
+            &IotaMessage::builder()
+                .with_parent1(MessageId::new([0; 32]))
+                .with_parent2(MessageId::new([0; 32]))
+                .with_payload(Payload::Indexation(Box::new(
+                    Indexation::new("index".to_string(), &[0; 16]).unwrap(),
+                )))
+                .with_network_id(0)
+                .finish()
+                .unwrap(),
+            None,
+        )
+        .unwrap();
+        serde_json::json!({
+            "id": "account-id",
+            "index": 0,
+            "alias": "alias",
+            "addresses": [{
+                "address": "iota1qz",
+                "balance": 0,
+                "keyIndex": 0,
+                "internal": false,
+                "outputs": []
+            }],
+            "messages": [message]
+        })
+    }
+
+    #[test]
+    fn roundtrip_account() {
+        let adapter = SqliteStorageAdapter::new(storage_path(), "accounts").unwrap();
+        let account = account_json([1; 32]);
+        let account_id = AccountIdentifier::Id("account-id".to_string());
+        adapter.set(&account_id, account.to_string()).unwrap();
+
+        let stored: serde_json::Value = serde_json::from_str(&adapter.get(&account_id).unwrap()).unwrap();
+        assert_eq!(stored, account);
+        let stored: serde_json::Value =
+            serde_json::from_str(&adapter.get(&AccountIdentifier::Index(0)).unwrap()).unwrap();
+        assert_eq!(stored, account);
+
+        assert_eq!(adapter.list_messages(&account_id, 1, 0).unwrap().len(), 1);
+        assert_eq!(adapter.list_messages(&account_id, 1, 1).unwrap().len(), 0);
+        assert!(adapter
+            .get_message(&account_id, &MessageId::new([1; 32]))
+            .unwrap()
+            .is_some());
+        assert!(adapter
+            .get_message(&account_id, &MessageId::new([2; 32]))
+            .unwrap()
+            .is_none());
+
+        adapter.remove(&account_id).unwrap();
+        assert!(adapter.get(&account_id).is_err());
+        assert!(adapter.get_message(&account_id, &MessageId::new([1; 32])).is_err());
+    }
+
+    #[test]
+    fn commit_and_rollback_transaction() {
+        let adapter = SqliteStorageAdapter::new(storage_path(), "accounts").unwrap();
+        let account = account_json([1; 32]);
+        let account_id = AccountIdentifier::Id("account-id".to_string());
+
+        adapter.begin_transaction().unwrap();
+        adapter.set(&account_id, account.to_string()).unwrap();
+        adapter.rollback_transaction().unwrap();
+        assert!(adapter.get(&account_id).is_err());
+        assert!(adapter.get_message(&account_id, &MessageId::new([1; 32])).is_err());
+
+        adapter.begin_transaction().unwrap();
+        adapter.set(&account_id, account.to_string()).unwrap();
+        adapter.commit_transaction().unwrap();
+        let stored: serde_json::Value = serde_json::from_str(&adapter.get(&account_id).unwrap()).unwrap();
+        assert_eq!(stored, account);
+
+        adapter.begin_transaction().unwrap();
+        adapter.remove(&account_id).unwrap();
+        adapter.rollback_transaction().unwrap();
+        assert!(adapter.get(&account_id).is_ok());
+    }
+
+    #[test]
+    fn migrate_legacy_table() {
+        let storage_path = storage_path();
+        std::fs::create_dir_all(&storage_path).unwrap();
+        let account = account_json([1; 32]);
+        {
+            let connection = Connection::open(storage_path.join("wallet.db")).unwrap();
+            connection
+                .execute(
+                    "CREATE TABLE accounts (key TEXT NOT NULL UNIQUE, value TEXT, created_at INTEGER)",
+                    NO_PARAMS,
+                )
+                .unwrap();
+            connection
+                .execute(
+                    "INSERT INTO accounts VALUES (?1, ?2, ?3)",
+                    params!["account-id", account.to_string(), 0],
+                )
+                .unwrap();
+        }
+
+        let adapter = SqliteStorageAdapter::new(&storage_path, "accounts").unwrap();
+        let stored = adapter.get_all().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&stored[0]).unwrap(), account);
+    }
+
+    #[test]
+    fn encrypt_storage() {
+        let storage_path = storage_path();
+        let account = account_json([1; 32]);
+        let account_id = AccountIdentifier::Id("account-id".to_string());
+        {
+            let adapter = SqliteStorageAdapter::new(&storage_path, "accounts").unwrap();
+            adapter.set(&account_id, account.to_string()).unwrap();
+            adapter.set_password("password").unwrap();
+            assert!(adapter.is_encrypted().unwrap());
+        }
+
+        let database = std::fs::read(storage_path.join("wallet.db")).unwrap();
+        assert!(!String::from_utf8_lossy(&database).contains("iota1qz"));
+
+        let adapter = SqliteStorageAdapter::new(&storage_path, "accounts").unwrap();
+        assert!(matches!(
+            adapter.get(&account_id),
+            Err(crate::WalletError::StorageIsEncrypted)
+        ));
+        assert!(matches!(
+            adapter.set_password("wrong-password"),
+            Err(crate::WalletError::InvalidStoragePassword)
+        ));
+        adapter.set_password("password").unwrap();
+        let stored: serde_json::Value = serde_json::from_str(&adapter.get(&account_id).unwrap()).unwrap();
+        assert_eq!(stored, account);
+        assert!(adapter
+            .get_message(&account_id, &MessageId::new([1; 32]))
+            .unwrap()
+            .is_some());
+
+        adapter.change_password("password", "new-password").unwrap();
+        let adapter = SqliteStorageAdapter::with_password(&storage_path, "accounts", "new-password").unwrap();
+        let stored: serde_json::Value = serde_json::from_str(&adapter.get(&account_id).unwrap()).unwrap();
+        assert_eq!(stored, account);
+    }
+}
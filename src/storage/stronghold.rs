@@ -4,7 +4,10 @@
 use super::StorageAdapter;
 use crate::account::AccountIdentifier;
 
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
 
 use stronghold::{RecordHint, RecordId, Stronghold};
 
@@ -12,9 +15,17 @@ static ACCOUNT_ID_INDEX_HINT: &str = "wallet.rs-account-ids";
 
 type AccountIdIndex = Vec<(AccountIdentifier, RecordId)>;
 
+/// A write operation waiting for the transaction commit.
+enum PendingOperation {
+    Set(AccountIdentifier, String),
+    Remove(AccountIdentifier),
+}
+
 /// Stronghold storage adapter.
 pub struct StrongholdStorageAdapter {
     path: PathBuf,
+    /// The operations of the current transaction; `None` if there's no transaction in progress.
+    pending_operations: Mutex<Option<Vec<PendingOperation>>>,
 }
 
 impl StrongholdStorageAdapter {
@@ -22,8 +33,23 @@ impl StrongholdStorageAdapter {
     pub fn new<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
         Ok(Self {
             path: path.as_ref().to_path_buf(),
+            pending_operations: Mutex::new(None),
         })
     }
+
+    /// Queues the operation if there's a transaction in progress, otherwise applies it immediately.
+    fn write(&self, operation: PendingOperation) -> crate::Result<()> {
+        let mut pending_operations = self.pending_operations.lock().unwrap();
+        match pending_operations.as_mut() {
+            Some(operations) => {
+                operations.push(operation);
+                Ok(())
+            }
+            None => crate::with_stronghold_from_path(&self.path, |stronghold| {
+                apply_operations(&stronghold, vec![operation])
+            }),
+        }
+    }
 }
 
 fn get_account_index(stronghold: &Stronghold) -> crate::Result<(RecordId, AccountIdIndex)> {
@@ -49,19 +75,16 @@ fn get_account_index(stronghold: &Stronghold) -> crate::Result<(RecordId, Accoun
     Ok((record_id, index))
 }
 
-fn get_from_index(
+fn get_position_in_index(
     #[allow(clippy::ptr_arg)] index: &AccountIdIndex,
     account_id: &AccountIdentifier,
-) -> Option<RecordId> {
+) -> Option<usize> {
     match account_id {
-        AccountIdentifier::Id(id) => index
-            .iter()
-            .find(|(acc_id, _)| acc_id == account_id)
-            .map(|(_, record_id)| *record_id),
+        AccountIdentifier::Id(id) => index.iter().position(|(acc_id, _)| acc_id == account_id),
         AccountIdentifier::Index(pos) => {
             let pos = *pos as usize;
             if index.len() > pos {
-                Some(index[pos].1)
+                Some(pos)
             } else {
                 None
             }
@@ -69,8 +92,68 @@ fn get_from_index(
     }
 }
 
+fn get_from_index(
+    #[allow(clippy::ptr_arg)] index: &AccountIdIndex,
+    account_id: &AccountIdentifier,
+) -> Option<RecordId> {
+    get_position_in_index(index, account_id).map(|pos| index[pos].1)
+}
+
+/// Applies the write operations to the stronghold records.
+/// The new account records are written before the index is replaced and the stale records are only removed after it,
+/// so an interruption never leaves the index pointing to missing records.
+fn apply_operations(stronghold: &Stronghold, operations: Vec<PendingOperation>) -> crate::Result<()> {
+    let (index_record_id, mut index) = get_account_index(&stronghold)?;
+    let mut stale_records = vec![];
+
+    for operation in operations {
+        match operation {
+            PendingOperation::Set(account_id, account) => {
+                let stronghold_id = stronghold.record_create(account.as_str())?;
+                match get_position_in_index(&index, &account_id) {
+                    // account already existed; update the RecordId
+                    Some(pos) => {
+                        stale_records.push(index[pos].1);
+                        index[pos] = (account_id, stronghold_id);
+                    }
+                    // new account; push to the index
+                    None => index.push((account_id, stronghold_id)),
+                }
+            }
+            PendingOperation::Remove(account_id) => {
+                let pos = get_position_in_index(&index, &account_id).ok_or(crate::WalletError::AccountNotFound)?;
+                stale_records.push(index.remove(pos).1);
+            }
+        }
+    }
+
+    stronghold.record_remove(index_record_id)?;
+    stronghold.record_create_with_hint(
+        &serde_json::to_string(&index)?,
+        RecordHint::new(ACCOUNT_ID_INDEX_HINT).unwrap(),
+    )?;
+
+    for record_id in stale_records {
+        stronghold.record_remove(record_id)?;
+    }
+
+    Ok(())
+}
+
 impl StorageAdapter for StrongholdStorageAdapter {
     fn get(&self, account_id: &AccountIdentifier) -> crate::Result<String> {
+        // reads the uncommitted changes first
+        if let Some(operations) = self.pending_operations.lock().unwrap().as_ref() {
+            let pending_operation = operations.iter().rev().find(|operation| match operation {
+                PendingOperation::Set(id, _) | PendingOperation::Remove(id) => id == account_id,
+            });
+            match pending_operation {
+                Some(PendingOperation::Set(_, account)) => return Ok(account.clone()),
+                Some(PendingOperation::Remove(_)) => return Err(crate::WalletError::AccountNotFound),
+                None => {}
+            }
+        }
+
         let account = crate::with_stronghold_from_path(&self.path, |stronghold| {
             let (_, index) = get_account_index(&stronghold)?;
             let stronghold_id = get_from_index(&index, &account_id).ok_or(crate::WalletError::AccountNotFound)?;
@@ -84,71 +167,133 @@ impl StorageAdapter for StrongholdStorageAdapter {
     fn get_all(&self) -> crate::Result<std::vec::Vec<String>> {
         let mut accounts = vec![];
         let (_, index) = crate::with_stronghold_from_path(&self.path, |stronghold| get_account_index(&stronghold))?;
-        for (_, record_id) in index {
+        for (account_id, record_id) in index {
             let account =
                 crate::with_stronghold_from_path(&self.path, |stronghold| stronghold.record_read(&record_id))?;
-            accounts.push(account);
+            accounts.push((account_id, account));
+        }
+
+        // reads the uncommitted changes
+        if let Some(operations) = self.pending_operations.lock().unwrap().as_ref() {
+            for operation in operations {
+                match operation {
+                    PendingOperation::Set(account_id, account) => {
+                        match accounts.iter().position(|(id, _)| id == account_id) {
+                            Some(pos) => accounts[pos].1 = account.clone(),
+                            None => accounts.push((account_id.clone(), account.clone())),
+                        }
+                    }
+                    PendingOperation::Remove(account_id) => accounts.retain(|(id, _)| id != account_id),
+                }
+            }
         }
-        Ok(accounts)
+
+        Ok(accounts.into_iter().map(|(_, account)| account).collect())
     }
 
     fn set(&self, account_id: &AccountIdentifier, account: String) -> crate::Result<()> {
-        let res: crate::Result<()> = crate::with_stronghold_from_path(&self.path, |stronghold| {
-            let (index_record_id, mut index) = get_account_index(&stronghold)?;
-            let account_in_index = get_from_index(&index, &account_id);
-
-            if let Some(stronghold_id) = account_in_index {
-                stronghold.record_remove(stronghold_id)?;
-            }
+        self.write(PendingOperation::Set(account_id.clone(), account))
+    }
 
-            let stronghold_id = stronghold.record_create(account.as_str())?;
+    fn remove(&self, account_id: &AccountIdentifier) -> crate::Result<()> {
+        self.write(PendingOperation::Remove(account_id.clone()))
+    }
 
-            if account_in_index.is_some() {
-                // account already existed; update the RecordId
-                let pos = index.iter().position(|(acc_id, _)| acc_id == account_id).unwrap();
-                index[pos] = (account_id.clone(), stronghold_id);
-            } else {
-                // new account; push to the index
-                index.push((account_id.clone(), stronghold_id))
-            }
+    fn begin_transaction(&self) -> crate::Result<()> {
+        let mut pending_operations = self.pending_operations.lock().unwrap();
+        if pending_operations.is_some() {
+            return Err(anyhow::anyhow!("a storage transaction is already in progress").into());
+        }
+        pending_operations.replace(vec![]);
+        Ok(())
+    }
 
-            stronghold.record_remove(index_record_id)?;
-            stronghold.record_create_with_hint(
-                &serde_json::to_string(&index)?,
-                RecordHint::new(ACCOUNT_ID_INDEX_HINT).unwrap(),
-            )?;
-            Ok(())
-        });
-        res?;
+    fn commit_transaction(&self) -> crate::Result<()> {
+        let operations = self
+            .pending_operations
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("no storage transaction in progress"))?;
+        if operations.is_empty() {
+            return Ok(());
+        }
+        crate::with_stronghold_from_path(&self.path, |stronghold| apply_operations(&stronghold, operations))
+    }
 
+    fn rollback_transaction(&self) -> crate::Result<()> {
+        self.pending_operations.lock().unwrap().take();
         Ok(())
     }
 
-    fn remove(&self, account_id: &AccountIdentifier) -> crate::Result<()> {
-        let res: crate::Result<()> = crate::with_stronghold_from_path(&self.path, |stronghold| {
-            let (index_record_id, index) = get_account_index(&stronghold)?;
-            let stronghold_id = get_from_index(&index, &account_id).ok_or(crate::WalletError::AccountNotFound)?;
+    fn is_locked(&self) -> crate::Result<bool> {
+        // the accounts are stored on the snapshot, so they can only be read while it's loaded
+        Ok(!crate::context::get(&self.path)
+            .map(|context| context.is_stronghold_loaded())
+            .unwrap_or(false))
+    }
+}
 
-            stronghold.record_remove(stronghold_id)?;
+#[cfg(test)]
+mod tests {
+    use super::StrongholdStorageAdapter;
+    use crate::{account::AccountIdentifier, storage::StorageAdapter};
+    use rand::{thread_rng, Rng};
+    use std::path::PathBuf;
+    use stronghold::Stronghold;
 
-            let mut new_index = vec![];
-            for (acc_id, record_id) in index {
-                if &acc_id != account_id {
-                    new_index.push((acc_id, record_id));
-                }
-            }
+    fn adapter() -> StrongholdStorageAdapter {
+        let storage_path: String = thread_rng().gen_ascii_chars().take(10).collect();
+        let storage_path = PathBuf::from(format!("./example-database/{}", storage_path));
+        std::fs::create_dir_all(&storage_path).unwrap();
+        let stronghold = Stronghold::new(
+            &storage_path.join(crate::storage::stronghold_snapshot_filename()),
+            true,
+            "password".to_string(),
+            None,
+        )
+        .unwrap();
+        crate::init_stronghold(&storage_path, stronghold, "password".to_string());
+        StrongholdStorageAdapter::new(&storage_path).unwrap()
+    }
 
-            stronghold.record_remove(index_record_id)?;
-            stronghold
-                .record_create_with_hint(
-                    &serde_json::to_string(&new_index)?,
-                    RecordHint::new(ACCOUNT_ID_INDEX_HINT).unwrap(),
-                )
-                .map_err(crate::WalletError::GenericError)?;
-            Ok(())
-        });
-        res?;
+    #[test]
+    fn commit_transaction() {
+        let adapter = adapter();
+        let account_id = AccountIdentifier::Id("id".to_string());
+        let other_account_id = AccountIdentifier::Id("other-id".to_string());
+        adapter.set(&account_id, "account".to_string()).unwrap();
 
-        Ok(())
+        adapter.begin_transaction().unwrap();
+        adapter.set(&account_id, "updated account".to_string()).unwrap();
+        adapter.set(&other_account_id, "other account".to_string()).unwrap();
+        // the uncommitted changes are visible to the transaction
+        assert_eq!(adapter.get(&account_id).unwrap(), "updated account".to_string());
+        adapter.commit_transaction().unwrap();
+
+        assert_eq!(adapter.get(&account_id).unwrap(), "updated account".to_string());
+        assert_eq!(
+            adapter.get_all().unwrap(),
+            vec!["updated account".to_string(), "other account".to_string()]
+        );
+        assert!(adapter.commit_transaction().is_err());
+    }
+
+    #[test]
+    fn rollback_transaction() {
+        let adapter = adapter();
+        let account_id = AccountIdentifier::Id("id".to_string());
+        let other_account_id = AccountIdentifier::Id("other-id".to_string());
+        adapter.set(&account_id, "account".to_string()).unwrap();
+
+        adapter.begin_transaction().unwrap();
+        adapter.set(&other_account_id, "other account".to_string()).unwrap();
+        adapter.remove(&account_id).unwrap();
+        assert!(adapter.get(&account_id).is_err());
+        adapter.rollback_transaction().unwrap();
+
+        assert_eq!(adapter.get(&account_id).unwrap(), "account".to_string());
+        assert!(adapter.get(&other_account_id).is_err());
+        assert_eq!(adapter.get_all().unwrap(), vec!["account".to_string()]);
     }
 }